@@ -1,12 +1,19 @@
-var cacheName = "egui-template-pwa";
-var filesToCache = [
-  "./",
-  "./index.html",
-  "./egui-multinode.js",
-  "./egui-multinode_bg.wasm",
-];
+/* The crate version and a hash of the build, stamped in by sw_version.sh after each Trunk build.
+ * It names the cache, so each build is cached apart from the others, and it changes this file,
+ * so browsers notice a new build and install its service worker. */
+var version = "{{VERSION}}";
+var cachePrefix = "multinode-";
+var cacheName = cachePrefix + version;
 
-/* Start the service worker and cache all of the app's content */
+/* Message of the app asking a new build's service worker to take over */
+var SKIP_WAITING = "skip-waiting";
+
+/* The app's entry point, and the scripts and wasm of the build, whose names Trunk hashes and
+ * sw_version.sh stamps in, so the whole app works offline once installed */
+var filesToCache = ["./", "./index.html" /* {{FILES}} */];
+
+/* Cache the app. A new build then waits until open pages reload into it, which they offer once
+ * they see it installed. */
 self.addEventListener("install", function (e) {
   e.waitUntil(
     caches.open(cacheName).then(function (cache) {
@@ -15,11 +22,55 @@ self.addEventListener("install", function (e) {
   );
 });
 
-/* Serve cached content when offline */
+/* Delete the caches of older builds, and serve the pages already open */
+self.addEventListener("activate", function (e) {
+  e.waitUntil(
+    caches
+      .keys()
+      .then(function (names) {
+        return Promise.all(
+          names
+            .filter(function (name) {
+              return name.startsWith(cachePrefix) && name !== cacheName;
+            })
+            .map(function (name) {
+              return caches.delete(name);
+            }),
+        );
+      })
+      .then(function () {
+        return self.clients.claim();
+      }),
+  );
+});
+
+/* The app asks the new build to take over when the user reloads into it */
+self.addEventListener("message", function (e) {
+  if (e.data === SKIP_WAITING) {
+    self.skipWaiting();
+  }
+});
+
+/* Serve cached content, caching what this build fetches, so the app also works offline */
 self.addEventListener("fetch", function (e) {
+  var url = new URL(e.request.url);
+  if (e.request.method !== "GET" || url.origin !== self.location.origin) {
+    return;
+  }
+
   e.respondWith(
-    caches.match(e.request).then(function (response) {
-      return response || fetch(e.request);
+    caches.open(cacheName).then(function (cache) {
+      return cache.match(e.request).then(function (cached) {
+        return (
+          cached ||
+          fetch(e.request).then(function (response) {
+            if (response.ok) {
+              cache.put(e.request, response.clone());
+            }
+            return response;
+          })
+        );
+      });
     }),
   );
 });
@@ -0,0 +1,288 @@
+# Deutsch
+
+## Top bar
+menu-file = Datei
+menu-quit = Beenden
+menu-view = Ansicht
+menu-reset-layout = Layout zurücksetzen
+menu-settings = ⚙ Einstellungen
+menu-preferences = Aussehen und Sprache…
+menu-export-settings = Einstellungen exportieren…
+menu-import-settings = Einstellungen importieren…
+menu-reset-settings = Alle Einstellungen zurücksetzen…
+powered-by = Basiert auf{" "}
+powered-by-and = {" "}und{" "}
+powered-by-end = .
+
+## Views
+view-node = 🖧 Knoten
+view-log = 📜 Knotenprotokoll
+view-plugins = 🔌 Plugins
+view-peers = 👥 Peers
+view-wallet = 👛 Wallet
+dock-pop-out = ⧉ In einem Fenster öffnen
+dock-close-tab = ✖ Schließen
+
+## Node
+node-connect = Mit Multinode verbinden:
+node-log-remote = Das Protokoll führt der Multinode, mit dem du dich verbindest
+peers-own-identity = Der Knoten erzeugt seine eigene Identität, da er noch keine aus der Wallet übernehmen kann
+peers-none = Keine Peers verbunden
+peers-remote = Verbinde dich mit einem Multinode, um seine Peers zu sehen
+
+## Plugins
+plugins-drop-files = Dateien auf das Fenster ziehen und ablegen!
+plugins-open-file = Datei öffnen…
+plugins-picked-file = Gewählte Datei:
+plugins-dropped = Plugins:
+plugins-signatures = 🔏 Plugin-Signaturen
+plugins-storage = 🗄 Plugin-Speicher
+plugins-messages = 📡 Plugin-Nachrichten
+messages-none = Noch hat kein Plugin den Nachrichtenbus benutzt
+messages-topic = { $topic } ({ $count ->
+    [one] 1 Nachricht
+   *[other] { $count } Nachrichten
+})
+messages-subscribers = Abonnenten: { $plugins }
+plugin-status = Status: { $status }
+plugin-not-loaded = Status: nicht geladen
+plugin-loading = ⏳ Wird geladen
+plugin-running = ✅ Läuft
+plugin-panel = 🪟 Panel
+plugin-fuel = Fuel pro Aufruf
+plugin-memory = Speicher (MiB)
+plugin-timeout = Zeit pro Aufruf (ms)
+plugin-reset-limits = Auf Standardwerte zurücksetzen
+plugin-unsupported-import = importiert { $import }, das nur der Knoten bereitstellt, daher kann die App es nicht aufrufen
+plugin-crashed = Das Plugin ist abgestürzt: { $error }
+plugin-stopped = Das Plugin wurde beendet
+plugin-busy = Das Plugin ist zu beschäftigt
+plugin-bad-export = Fehlerhafter Export { $interface }: { $error }
+plugin-no-export = Kein Export namens { $name }
+plugin-not-a-function = { $name } ist keine Funktion
+plugin-takes = { $name } erwartet ({ $params })
+limit-memory = Speicherlimit überschritten: braucht { $requested } KiB, erlaubt sind { $limit } KiB
+limit-timeout = Zeitlimit überschritten: ein Aufruf dauerte über { $ms } ms
+limit-fuel = Fuel-Limit überschritten: ein Aufruf verbrauchte über { $fuel } Fuel
+limit-invalid = Ungültiges Wasm: { $error }
+remote-title = Plugin von einer URL laden, oder per CID:
+remote-source-hint = https://… oder CID
+remote-digest-hint = Erwarteter sha256 (optional)
+remote-gateway = IPFS-Gateway (Ausweichlösung)
+remote-gateway-fallback = Der Knoten kann noch keine Blöcke von seinen Peers abrufen, daher werden CIDs per HTTP von diesem Gateway geladen. Jeder Block wird mit dem CID geprüft, du musst dem Gateway also nicht vertrauen.
+remote-fetch = ⬇ Plugin herunterladen
+remote-progress = { $received } / { $total } KiB
+remote-received = { $received } KiB
+remote-fetched = ✅ { $name } heruntergeladen
+remote-failed = ❌ { $error }
+remote-invalid-source = { $source } ist weder eine http(s)-URL noch ein CID
+remote-invalid-digest = { $digest } ist kein hexadezimaler SHA-256-Hash
+remote-digest-mismatch = Hash stimmt nicht überein: erhalten sha256:{ $actual }
+trust-policy = Unsigniert oder nicht vertrauenswürdig:
+trust-warn = ⚠ Warnen
+trust-block = ⛔ Blockieren
+trust-signed-by = signiert von { $name }
+trust-unsigned = unsigniert
+trust-untrusted = signiert mit dem nicht vertrauenswürdigen Schlüssel { $key }
+trust-invalid = ungültige Signatur: { $error }
+trust-last-trusted = ✅ { $name }: { $verdict }
+trust-last-warned = ⚠ { $name }: { $verdict }
+trust-last-blocked = ⛔ { $name } blockiert: { $verdict }
+trust-this-publisher = Diesem Herausgeber vertrauen
+trust-publishers = Vertrauenswürdige Herausgeber
+trust-remove = Entfernen
+trust-name-hint = Name des Herausgebers
+trust-key-hint = Ed25519-Schlüssel in Hex
+trust-add = ➕ Herausgeber vertrauen
+detail-title = 🔌 Plugin-Details
+detail-no-plugins = Lade ein Plugin, um seine exportierten Funktionen aufzurufen.
+detail-plugin = Plugin
+detail-select-plugin = Plugin auswählen
+detail-no-functions = Dieses Plugin exportiert keine Funktionen.
+detail-call = ▶ Aufrufen
+detail-returned = ✅ Zurückgekehrt
+detail-add-item = ➕ Eintrag hinzufügen
+detail-some = some
+detail-unsupported = { $name } (nicht unterstützt)
+detail-not-a-number = „{ $text }“ ist keine gültige Zahl
+detail-not-a-char = „{ $text }“ ist kein einzelnes Zeichen
+detail-not-enterable = { $name } kann nicht von Hand eingegeben werden
+
+## Wallet
+wallet-create = Wallet erstellen
+wallet-recover = Wallet wiederherstellen
+wallet-phrase-hint = Seed-Phrase, 24 Wörter
+wallet-unlock = Wallet entsperren
+wallet-recover-button = Wiederherstellen
+wallet-create-button = Erstellen
+wallet-unlock-button = Entsperren
+wallet-unlocked = 🔓 Wallet entsperrt
+wallet-lock = 🔒 Sperren
+wallet-seed-phrase = 🔑 Seed-Phrase
+wallet-not-backed-up = ⚠ Seed-Phrase nicht gesichert
+wallet-identities = 🪪 Identitäten
+wallet-change-password = 🔑 Passwort ändern
+wallet-always-allowed = Plugins, die ohne Nachfrage signieren dürfen:
+wallet-ask-every-time = Jedes Mal fragen
+wallet-save-failed = Die Wallet konnte nicht gespeichert werden: { $error }
+wallet-auto-lock = Sperren nach Inaktivität von
+wallet-auto-lock-minutes = { $minutes } Min.
+wallet-auto-lock-never = Nie
+backup-explain = Deine Seed-Phrase stellt diese Wallet und jede daraus abgeleitete Identität wieder her. Wer sie sieht, kann sie an sich nehmen, also schreib sie an einem sicheren, privaten Ort auf.
+backup-reveal = 👁 Seed-Phrase anzeigen
+backup-written-down = ✅ Ich habe sie aufgeschrieben
+backup-hide = 🙈 Verbergen
+backup-pick-words = Wähle die Wörter der Reihe nach
+backup-wrong-word = Falsches Wort, fang noch einmal an
+backup-show-again = ⬅ Wörter wieder anzeigen
+wallet-error-password-too-short = Das Passwort muss mindestens { $min } Zeichen haben
+wallet-error-wrong-password = Falsches Passwort
+wallet-error-empty = Die Wallet hat noch keinen Seed
+wallet-error-random = Kein Zufall verfügbar: { $error }
+wallet-error-invalid-path = Ungültiger Ableitungspfad: { $path }
+wallet-error-reserved-path = { $path } ist für Plugin-Schlüssel reserviert
+wallet-error-invalid-mnemonic = Ungültige Seed-Phrase: { $error }
+identities-main = Haupt
+identities-label = Bezeichnung
+identities-public-key = Öffentlicher Schlüssel
+identities-peer-id = Peer-ID
+identities-copy = Kopieren
+identities-remove = Entfernen
+identities-derive = Neue Identität ableiten
+password-old = Altes Passwort
+password-new = Neues Passwort
+password-change = Passwort ändern
+password-changed = ✅ Passwort geändert
+password-show-hide = Passwort zeigen/verbergen
+password-confirm = Passwort bestätigen
+password-long-enough = ✔ { $min }+ Zeichen
+password-too-short = { $len }/{ $min } Zeichen
+password-match = ✔ Passwörter stimmen überein
+password-mismatch = ⚠ Passwörter stimmen nicht überein
+password-caps-lock = ⇪ Feststelltaste ist an
+password-very-weak = Sehr schwach
+password-weak = Schwach
+password-fair = Mittel
+password-strong = Stark
+password-very-strong = Sehr stark
+
+## Signing requests
+sign-title = ✍ Signaturanfrage
+sign-plugin = Plugin
+sign-asks = möchte signieren:
+sign-bytes = { $count } Bytes
+sign-always-allow = Diesem Plugin immer erlauben
+sign-approve = ✅ Genehmigen
+sign-deny = ❌ Ablehnen
+
+## Settings
+settings-title = ⚙ Einstellungen
+settings-language = Sprache
+settings-theme = Design
+settings-theme-hover = Design: { $theme }
+settings-theme-dark = Dunkel
+settings-theme-light = Hell
+settings-theme-system = System
+settings-high-contrast = Hoher Kontrast
+settings-accent = Akzentfarbe
+settings-font-scale = Schriftskalierung
+settings-text-sizes = Textgrößen
+settings-font = Schriftart
+settings-default-font = Standard
+settings-load-font = Schriftart laden…
+settings-use-default-font = Standardschriftart verwenden
+settings-not-a-font = { $name } ist keine Schriftart, die egui verwenden kann
+settings-font-unavailable = { $name } konnte nicht geladen werden, daher wird die Standardschrift verwendet
+settings-high-contrast-preset = ◑ Hoher Kontrast
+settings-reset-appearance = Darstellung zurücksetzen
+settings-background = Knoten beim Schließen des Fensters im Infobereich weiterlaufen lassen
+settings-background-hint = Das Symbol im Infobereich zeigt das Fenster wieder an und beendet den Knoten
+settings-notifications = Benachrichtigen, wenn das Fenster nicht im Fokus ist, über
+settings-notify-plugin-crashed = Abstürzende Plugins
+settings-notify-wallet-locked = Das Sperren der Wallet
+settings-notify-sign-request = Signaturanfragen
+settings-notify-peer-connected = Verbindende Peers
+settings-notify-unavailable = Der Knoten meldet diese der App noch nicht
+settings-notify-allow = 🔔 Benachrichtigungen erlauben
+settings-notify-denied = Der Browser blockiert Benachrichtigungen dieser Seite
+settings-notify-unsupported = Dieser Browser kann keine Benachrichtigungen anzeigen
+
+## Commands
+command-palette = Befehlspalette
+command-show-view = { $view } anzeigen
+command-settings = Einstellungen öffnen
+command-open-plugin-file = Plugin-Datei öffnen
+command-focus-log-search = Knotenprotokoll durchsuchen
+command-lock-wallet = Wallet sperren
+command-reset-layout = Layout zurücksetzen
+command-export-settings = Einstellungen exportieren
+command-import-settings = Einstellungen importieren
+command-reset-settings = Alle Einstellungen zurücksetzen
+command-quit = Beenden
+command-open-panel = Panel öffnen: { $title }
+command-select-plugin = Plugin { $name } anzeigen
+command-run-action = { $plugin }: { $title }
+palette-hint = Befehl eingeben
+palette-no-match = Keine passenden Befehle
+log-search = 🔍 Protokoll durchsuchen
+
+## Settings files
+transfer-title = Einstellungsdatei
+transfer-imported = Einstellungen aus { $name } importiert
+transfer-import-failed = { $name } konnte nicht importiert werden: { $error }
+transfer-export-failed = Die Einstellungen konnten nicht exportiert werden: { $error }
+transfer-reset-title = Alle Einstellungen zurücksetzen?
+transfer-reset-question = Anordnung, Aussehen, Sprache, Plugin-Einstellungen und vertrauenswürdige Herausgeber werden auf ihre Standardwerte zurückgesetzt. Die Wallet bleibt erhalten.
+transfer-reset = Zurücksetzen
+transfer-cancel = Abbrechen
+transfer-ok = OK
+transfer-unrestored = Die gespeicherten Einstellungen konnten nicht wiederhergestellt werden, daher hat die App mit den Standardwerten neu begonnen: { $error }. Eine Sicherung liegt unter dem Schlüssel { $key } in { $location }.
+transfer-local-storage = dem lokalen Speicher des Browsers für diese Seite
+transfer-saved-nowhere = dem Datenverzeichnis der App
+
+## Profiles
+profile-menu = 👤 { $name }
+profile-switch = Zu einem anderen Knotenprofil wechseln
+profile-new-hint = Neues Profil
+profile-add = ➕ Hinzufügen
+profile-invalid-name = Nur Buchstaben, Ziffern, - und _
+
+## Updates
+update-available = ✨ Update verfügbar
+update-reload = ⟳ Neu laden
+
+## Tray
+tray-show = 👁 Fenster anzeigen
+tray-hide = Fenster ausblenden
+tray-status = Laufende Plugins: { $plugins }
+tray-quit = Beenden
+
+## Notifications
+notify-plugin-crashed-title = Plugin gestoppt
+notify-plugin-crashed = { $name }: { $reason }
+notify-sign-request-title = Signaturanfrage
+notify-sign-request = { $plugin } möchte mit deiner Wallet signieren
+notify-wallet-locked-title = Wallet gesperrt
+notify-wallet-locked = Entsperre sie, um wieder zu signieren und deine Identitäten zu nutzen
+
+## Plugin storage
+storage-empty = Noch hat kein Plugin etwas gespeichert
+storage-plugin = { $plugin } ({ $count ->
+    [one] 1 Schlüssel
+   *[other] { $count } Schlüssel
+})
+storage-invalid-hex = Ungültiges Hex: { $error }
+storage-save = Speichern
+storage-delete = Löschen
+storage-new-key = Neuer Schlüssel
+storage-new-value = Text oder hex:…
+storage-add = Hinzufügen
+storage-export = 📤 Exportieren
+storage-clear = 🧹 Leeren
+storage-clear-question = { $count ->
+    [one] Den einen Schlüssel löschen?
+   *[other] Alle { $count } Schlüssel löschen?
+}
+storage-clear-confirm = Ja, leeren
+storage-cancel = Abbrechen
+storage-quota-exceeded = Speicherkontingent überschritten: { $size } KiB würden gespeichert, erlaubt sind { $limit } KiB
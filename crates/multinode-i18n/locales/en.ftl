@@ -0,0 +1,288 @@
+# English, which every other language falls back to
+
+## Top bar
+menu-file = File
+menu-quit = Quit
+menu-view = View
+menu-reset-layout = Reset layout
+menu-settings = ⚙ Settings
+menu-preferences = Appearance and language…
+menu-export-settings = Export settings…
+menu-import-settings = Import settings…
+menu-reset-settings = Reset all settings…
+powered-by = Powered by{" "}
+powered-by-and = {" "}and{" "}
+powered-by-end = .
+
+## Views
+view-node = 🖧 Node
+view-log = 📜 Node Log
+view-plugins = 🔌 Plugins
+view-peers = 👥 Peers
+view-wallet = 👛 Wallet
+dock-pop-out = ⧉ Open in a window
+dock-close-tab = ✖ Close
+
+## Node
+node-connect = Connect to multinode:
+node-log-remote = The log is kept by the multinode you connect to
+peers-own-identity = The node makes its own identity, as it can't be given one from the wallet yet
+peers-none = No peers connected
+peers-remote = Connect to a multinode to see its peers
+
+## Plugins
+plugins-drop-files = Drag-and-drop files onto the window!
+plugins-open-file = Open file…
+plugins-picked-file = Picked file:
+plugins-dropped = Plugins:
+plugins-signatures = 🔏 Plugin signatures
+plugins-storage = 🗄 Plugin storage
+plugins-messages = 📡 Plugin messages
+messages-none = No plugin has used the message bus yet
+messages-topic = { $topic } ({ $count ->
+    [one] 1 message
+   *[other] { $count } messages
+})
+messages-subscribers = Subscribers: { $plugins }
+plugin-status = Status: { $status }
+plugin-not-loaded = Status: not loaded
+plugin-loading = ⏳ Loading
+plugin-running = ✅ Running
+plugin-panel = 🪟 Panel
+plugin-fuel = Fuel per call
+plugin-memory = Memory (MiB)
+plugin-timeout = Time per call (ms)
+plugin-reset-limits = Reset to defaults
+plugin-unsupported-import = imports { $import }, which only the node provides, so it can't be called from the app
+plugin-crashed = The plugin crashed: { $error }
+plugin-stopped = The plugin stopped
+plugin-busy = The plugin is too busy
+plugin-bad-export = Bad { $interface } export: { $error }
+plugin-no-export = No export named { $name }
+plugin-not-a-function = { $name } is not a function
+plugin-takes = { $name } takes ({ $params })
+limit-memory = Memory limit exceeded: needs { $requested } KiB, the limit is { $limit } KiB
+limit-timeout = Time limit exceeded: a call took over { $ms } ms
+limit-fuel = Fuel limit exceeded: a call used over { $fuel } fuel
+limit-invalid = Invalid wasm: { $error }
+remote-title = Load plugin from a URL, or by CID:
+remote-source-hint = https://… or CID
+remote-digest-hint = Expected sha256 (optional)
+remote-gateway = IPFS gateway (fallback)
+remote-gateway-fallback = The node can't fetch blocks from its peers yet, so CIDs are fetched over HTTP from this gateway. Every block is checked against the CID, so it needn't be trusted.
+remote-fetch = ⬇ Fetch plugin
+remote-progress = { $received } / { $total } KiB
+remote-received = { $received } KiB
+remote-fetched = ✅ Fetched { $name }
+remote-failed = ❌ { $error }
+remote-invalid-source = { $source } is neither an http(s) URL nor a CID
+remote-invalid-digest = { $digest } is not a hex SHA-256 digest
+remote-digest-mismatch = Digest mismatch: got sha256:{ $actual }
+trust-policy = Unsigned or untrusted:
+trust-warn = ⚠ Warn
+trust-block = ⛔ Block
+trust-signed-by = signed by { $name }
+trust-unsigned = unsigned
+trust-untrusted = signed by untrusted key { $key }
+trust-invalid = invalid signature: { $error }
+trust-last-trusted = ✅ { $name }: { $verdict }
+trust-last-warned = ⚠ { $name }: { $verdict }
+trust-last-blocked = ⛔ Blocked { $name }: { $verdict }
+trust-this-publisher = Trust this publisher
+trust-publishers = Trusted publishers
+trust-remove = Remove
+trust-name-hint = Publisher name
+trust-key-hint = Hex ed25519 key
+trust-add = ➕ Trust publisher
+detail-title = 🔌 Plugin Detail
+detail-no-plugins = Load a plugin to call its exported functions.
+detail-plugin = Plugin
+detail-select-plugin = Select a plugin
+detail-no-functions = This plugin exports no functions.
+detail-call = ▶ Call
+detail-returned = ✅ Returned
+detail-add-item = ➕ Add item
+detail-some = some
+detail-unsupported = { $name } (unsupported)
+detail-not-a-number = "{ $text }" is not a valid number
+detail-not-a-char = "{ $text }" is not a single char
+detail-not-enterable = { $name } can't be entered by hand
+
+## Wallet
+wallet-create = Create Wallet
+wallet-recover = Recover Wallet
+wallet-phrase-hint = Seed phrase, 24 words
+wallet-unlock = Unlock Wallet
+wallet-recover-button = Recover
+wallet-create-button = Create
+wallet-unlock-button = Unlock
+wallet-unlocked = 🔓 Wallet unlocked
+wallet-lock = 🔒 Lock
+wallet-seed-phrase = 🔑 Seed phrase
+wallet-not-backed-up = ⚠ Seed phrase not backed up
+wallet-identities = 🪪 Identities
+wallet-change-password = 🔑 Change password
+wallet-always-allowed = Plugins allowed to sign without asking:
+wallet-ask-every-time = Ask every time
+wallet-save-failed = Couldn't save the wallet: { $error }
+wallet-auto-lock = Lock after idling for
+wallet-auto-lock-minutes = { $minutes } min
+wallet-auto-lock-never = Never
+backup-explain = Your seed phrase restores this wallet and every identity derived from it. Anyone who sees it can take them, so write it down somewhere safe and private.
+backup-reveal = 👁 Reveal seed phrase
+backup-written-down = ✅ I wrote it down
+backup-hide = 🙈 Hide
+backup-pick-words = Pick the words in order
+backup-wrong-word = Wrong word, start again
+backup-show-again = ⬅ Show the words again
+wallet-error-password-too-short = The password must be at least { $min } characters
+wallet-error-wrong-password = Wrong password
+wallet-error-empty = The wallet has no seed yet
+wallet-error-random = No randomness available: { $error }
+wallet-error-invalid-path = Invalid derivation path: { $path }
+wallet-error-reserved-path = { $path } is reserved for plugin keys
+wallet-error-invalid-mnemonic = Invalid seed phrase: { $error }
+identities-main = Main
+identities-label = Label
+identities-public-key = Public key
+identities-peer-id = Peer ID
+identities-copy = Copy
+identities-remove = Remove
+identities-derive = Derive a new identity
+password-old = Old password
+password-new = New password
+password-change = Change password
+password-changed = ✅ Password changed
+password-show-hide = Show/hide password
+password-confirm = Confirm password
+password-long-enough = ✔ { $min }+ characters
+password-too-short = { $len }/{ $min } characters
+password-match = ✔ Passwords match
+password-mismatch = ⚠ Passwords don't match
+password-caps-lock = ⇪ Caps lock is on
+password-very-weak = Very weak
+password-weak = Weak
+password-fair = Fair
+password-strong = Strong
+password-very-strong = Very strong
+
+## Signing requests
+sign-title = ✍ Signing request
+sign-plugin = Plugin
+sign-asks = asks to sign:
+sign-bytes = { $count } bytes
+sign-always-allow = Always allow for this plugin
+sign-approve = ✅ Approve
+sign-deny = ❌ Deny
+
+## Settings
+settings-title = ⚙ Settings
+settings-language = Language
+settings-theme = Theme
+settings-theme-hover = Theme: { $theme }
+settings-theme-dark = Dark
+settings-theme-light = Light
+settings-theme-system = System
+settings-high-contrast = High contrast
+settings-accent = Accent color
+settings-font-scale = Font scale
+settings-text-sizes = Text sizes
+settings-font = Font
+settings-default-font = Default
+settings-load-font = Load font…
+settings-use-default-font = Use default font
+settings-not-a-font = { $name } is not a font egui can use
+settings-font-unavailable = Couldn't load { $name }, so the default font is used
+settings-high-contrast-preset = ◑ High contrast
+settings-reset-appearance = Reset appearance
+settings-background = Keep the node running in the tray when the window is closed
+settings-background-hint = The tray icon shows the window again, and quits the node
+settings-notifications = Notify me when the window isn't focused of
+settings-notify-plugin-crashed = Plugins crashing
+settings-notify-wallet-locked = The wallet locking
+settings-notify-sign-request = Requests to sign
+settings-notify-peer-connected = Peers connecting
+settings-notify-unavailable = The node doesn't tell the app about these yet
+settings-notify-allow = 🔔 Allow notifications
+settings-notify-denied = The browser blocks notifications from this page
+settings-notify-unsupported = This browser can't show notifications
+
+## Commands
+command-palette = Command palette
+command-show-view = Show { $view }
+command-settings = Open settings
+command-open-plugin-file = Open a plugin file
+command-focus-log-search = Search the node log
+command-lock-wallet = Lock the wallet
+command-reset-layout = Reset layout
+command-export-settings = Export settings
+command-import-settings = Import settings
+command-reset-settings = Reset all settings
+command-quit = Quit
+command-open-panel = Open panel: { $title }
+command-select-plugin = Show plugin { $name }
+command-run-action = { $plugin }: { $title }
+palette-hint = Type a command
+palette-no-match = No matching commands
+log-search = 🔍 Search the log
+
+## Settings files
+transfer-title = Settings file
+transfer-imported = Imported the settings in { $name }
+transfer-import-failed = Couldn't import { $name }: { $error }
+transfer-export-failed = Couldn't export the settings: { $error }
+transfer-reset-title = Reset all settings?
+transfer-reset-question = The layout, appearance, language, plugin settings and trusted publishers go back to their defaults. The wallet is kept.
+transfer-reset = Reset
+transfer-cancel = Cancel
+transfer-ok = OK
+transfer-unrestored = The saved settings couldn't be restored, so the app started over with the defaults: { $error }. They are backed up under the key { $key } in { $location }.
+transfer-local-storage = the browser's local storage for this page
+transfer-saved-nowhere = the app's data directory
+
+## Profiles
+profile-menu = 👤 { $name }
+profile-switch = Switch to another node profile
+profile-new-hint = New profile
+profile-add = ➕ Add
+profile-invalid-name = Use letters, digits, - and _
+
+## Updates
+update-available = ✨ Update available
+update-reload = ⟳ Reload
+
+## Tray
+tray-show = 👁 Show window
+tray-hide = Hide window
+tray-status = Plugins running: { $plugins }
+tray-quit = Quit
+
+## Notifications
+notify-plugin-crashed-title = Plugin stopped
+notify-plugin-crashed = { $name }: { $reason }
+notify-sign-request-title = Request to sign
+notify-sign-request = { $plugin } asks to sign with your wallet
+notify-wallet-locked-title = Wallet locked
+notify-wallet-locked = Unlock it to sign and use your identities again
+
+## Plugin storage
+storage-empty = No plugin has stored anything yet
+storage-plugin = { $plugin } ({ $count ->
+    [one] 1 key
+   *[other] { $count } keys
+})
+storage-invalid-hex = Invalid hex: { $error }
+storage-save = Save
+storage-delete = Delete
+storage-new-key = New key
+storage-new-value = Text or hex:…
+storage-add = Add
+storage-export = 📤 Export
+storage-clear = 🧹 Clear
+storage-clear-question = { $count ->
+    [one] Clear the 1 key?
+   *[other] Clear all { $count } keys?
+}
+storage-clear-confirm = Yes, clear
+storage-cancel = Cancel
+storage-quota-exceeded = Storage quota exceeded: would store { $size } KiB, the limit is { $limit } KiB
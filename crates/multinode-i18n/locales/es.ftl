@@ -0,0 +1,288 @@
+# Español
+
+## Top bar
+menu-file = Archivo
+menu-quit = Salir
+menu-view = Ver
+menu-reset-layout = Restablecer disposición
+menu-settings = ⚙ Ajustes
+menu-preferences = Apariencia e idioma…
+menu-export-settings = Exportar ajustes…
+menu-import-settings = Importar ajustes…
+menu-reset-settings = Restablecer todos los ajustes…
+powered-by = Con la tecnología de{" "}
+powered-by-and = {" "}y{" "}
+powered-by-end = .
+
+## Views
+view-node = 🖧 Nodo
+view-log = 📜 Registro del nodo
+view-plugins = 🔌 Plugins
+view-peers = 👥 Pares
+view-wallet = 👛 Monedero
+dock-pop-out = ⧉ Abrir en una ventana
+dock-close-tab = ✖ Cerrar
+
+## Node
+node-connect = Conectar al multinodo:
+node-log-remote = El registro lo guarda el multinodo al que te conectes
+peers-own-identity = El nodo crea su propia identidad, ya que aún no puede recibir una del monedero
+peers-none = No hay pares conectados
+peers-remote = Conéctate a un multinodo para ver sus pares
+
+## Plugins
+plugins-drop-files = ¡Arrastra y suelta archivos en la ventana!
+plugins-open-file = Abrir archivo…
+plugins-picked-file = Archivo elegido:
+plugins-dropped = Plugins:
+plugins-signatures = 🔏 Firmas de plugins
+plugins-storage = 🗄 Almacenamiento de plugins
+plugins-messages = 📡 Mensajes de plugins
+messages-none = Ningún plugin ha usado aún el bus de mensajes
+messages-topic = { $topic } ({ $count ->
+    [one] 1 mensaje
+   *[other] { $count } mensajes
+})
+messages-subscribers = Suscriptores: { $plugins }
+plugin-status = Estado: { $status }
+plugin-not-loaded = Estado: sin cargar
+plugin-loading = ⏳ Cargando
+plugin-running = ✅ En marcha
+plugin-panel = 🪟 Panel
+plugin-fuel = Combustible por llamada
+plugin-memory = Memoria (MiB)
+plugin-timeout = Tiempo por llamada (ms)
+plugin-reset-limits = Restablecer los valores predeterminados
+plugin-unsupported-import = importa { $import }, que solo proporciona el nodo, así que no se puede llamar desde la app
+plugin-crashed = El plugin falló: { $error }
+plugin-stopped = El plugin se detuvo
+plugin-busy = El plugin está demasiado ocupado
+plugin-bad-export = Exportación { $interface } incorrecta: { $error }
+plugin-no-export = No hay ninguna exportación llamada { $name }
+plugin-not-a-function = { $name } no es una función
+plugin-takes = { $name } recibe ({ $params })
+limit-memory = Límite de memoria superado: necesita { $requested } KiB y el límite es { $limit } KiB
+limit-timeout = Límite de tiempo superado: una llamada tardó más de { $ms } ms
+limit-fuel = Límite de combustible superado: una llamada usó más de { $fuel }
+limit-invalid = Wasm no válido: { $error }
+remote-title = Cargar un plugin desde una URL, o por CID:
+remote-source-hint = https://… o CID
+remote-digest-hint = sha256 esperado (opcional)
+remote-gateway = Pasarela IPFS (alternativa)
+remote-gateway-fallback = El nodo aún no puede obtener bloques de sus pares, así que los CID se descargan por HTTP desde esta pasarela. Cada bloque se comprueba con el CID, así que no hace falta confiar en ella.
+remote-fetch = ⬇ Descargar plugin
+remote-progress = { $received } / { $total } KiB
+remote-received = { $received } KiB
+remote-fetched = ✅ Descargado { $name }
+remote-failed = ❌ { $error }
+remote-invalid-source = { $source } no es ni una URL http(s) ni un CID
+remote-invalid-digest = { $digest } no es un resumen SHA-256 en hexadecimal
+remote-digest-mismatch = El resumen no coincide: se obtuvo sha256:{ $actual }
+trust-policy = Sin firma o no confiable:
+trust-warn = ⚠ Avisar
+trust-block = ⛔ Bloquear
+trust-signed-by = firmado por { $name }
+trust-unsigned = sin firma
+trust-untrusted = firmado por la clave no confiable { $key }
+trust-invalid = firma no válida: { $error }
+trust-last-trusted = ✅ { $name }: { $verdict }
+trust-last-warned = ⚠ { $name }: { $verdict }
+trust-last-blocked = ⛔ Bloqueado { $name }: { $verdict }
+trust-this-publisher = Confiar en este editor
+trust-publishers = Editores de confianza
+trust-remove = Quitar
+trust-name-hint = Nombre del editor
+trust-key-hint = Clave ed25519 en hex
+trust-add = ➕ Confiar en el editor
+detail-title = 🔌 Detalle del plugin
+detail-no-plugins = Carga un plugin para llamar a las funciones que exporta.
+detail-plugin = Plugin
+detail-select-plugin = Elige un plugin
+detail-no-functions = Este plugin no exporta funciones.
+detail-call = ▶ Llamar
+detail-returned = ✅ Devuelto
+detail-add-item = ➕ Añadir elemento
+detail-some = some
+detail-unsupported = { $name } (no admitido)
+detail-not-a-number = "{ $text }" no es un número válido
+detail-not-a-char = "{ $text }" no es un solo carácter
+detail-not-enterable = { $name } no se puede introducir a mano
+
+## Wallet
+wallet-create = Crear monedero
+wallet-recover = Recuperar monedero
+wallet-phrase-hint = Frase semilla, 24 palabras
+wallet-unlock = Desbloquear monedero
+wallet-recover-button = Recuperar
+wallet-create-button = Crear
+wallet-unlock-button = Desbloquear
+wallet-unlocked = 🔓 Monedero desbloqueado
+wallet-lock = 🔒 Bloquear
+wallet-seed-phrase = 🔑 Frase semilla
+wallet-not-backed-up = ⚠ Frase semilla sin copia de seguridad
+wallet-identities = 🪪 Identidades
+wallet-change-password = 🔑 Cambiar contraseña
+wallet-always-allowed = Plugins que pueden firmar sin preguntar:
+wallet-ask-every-time = Preguntar siempre
+wallet-save-failed = No se pudo guardar el monedero: { $error }
+wallet-auto-lock = Bloquear tras estar inactivo
+wallet-auto-lock-minutes = { $minutes } min
+wallet-auto-lock-never = Nunca
+backup-explain = Tu frase semilla restaura este monedero y todas las identidades derivadas de él. Quien la vea puede quedárselas, así que apúntala en un lugar seguro y privado.
+backup-reveal = 👁 Mostrar la frase semilla
+backup-written-down = ✅ Ya la apunté
+backup-hide = 🙈 Ocultar
+backup-pick-words = Elige las palabras en orden
+backup-wrong-word = Palabra incorrecta, empieza de nuevo
+backup-show-again = ⬅ Ver las palabras otra vez
+wallet-error-password-too-short = La contraseña debe tener al menos { $min } caracteres
+wallet-error-wrong-password = Contraseña incorrecta
+wallet-error-empty = El monedero aún no tiene semilla
+wallet-error-random = No hay aleatoriedad disponible: { $error }
+wallet-error-invalid-path = Ruta de derivación no válida: { $path }
+wallet-error-reserved-path = { $path } está reservada para las claves de los plugins
+wallet-error-invalid-mnemonic = Frase semilla no válida: { $error }
+identities-main = Principal
+identities-label = Etiqueta
+identities-public-key = Clave pública
+identities-peer-id = ID de par
+identities-copy = Copiar
+identities-remove = Quitar
+identities-derive = Derivar una identidad nueva
+password-old = Contraseña actual
+password-new = Contraseña nueva
+password-change = Cambiar contraseña
+password-changed = ✅ Contraseña cambiada
+password-show-hide = Mostrar/ocultar la contraseña
+password-confirm = Confirma la contraseña
+password-long-enough = ✔ { $min }+ caracteres
+password-too-short = { $len }/{ $min } caracteres
+password-match = ✔ Las contraseñas coinciden
+password-mismatch = ⚠ Las contraseñas no coinciden
+password-caps-lock = ⇪ Bloq Mayús está activado
+password-very-weak = Muy débil
+password-weak = Débil
+password-fair = Aceptable
+password-strong = Fuerte
+password-very-strong = Muy fuerte
+
+## Signing requests
+sign-title = ✍ Solicitud de firma
+sign-plugin = El plugin
+sign-asks = pide firmar:
+sign-bytes = { $count } bytes
+sign-always-allow = Permitir siempre a este plugin
+sign-approve = ✅ Aprobar
+sign-deny = ❌ Denegar
+
+## Settings
+settings-title = ⚙ Ajustes
+settings-language = Idioma
+settings-theme = Tema
+settings-theme-hover = Tema: { $theme }
+settings-theme-dark = Oscuro
+settings-theme-light = Claro
+settings-theme-system = Sistema
+settings-high-contrast = Alto contraste
+settings-accent = Color de acento
+settings-font-scale = Escala de texto
+settings-text-sizes = Tamaños de texto
+settings-font = Fuente
+settings-default-font = Predeterminada
+settings-load-font = Cargar fuente…
+settings-use-default-font = Usar la fuente predeterminada
+settings-not-a-font = { $name } no es una fuente que egui pueda usar
+settings-font-unavailable = No se pudo cargar { $name }, así que se usa la fuente predeterminada
+settings-high-contrast-preset = ◑ Alto contraste
+settings-reset-appearance = Restablecer apariencia
+settings-background = Mantener el nodo en la bandeja al cerrar la ventana
+settings-background-hint = El icono de la bandeja vuelve a mostrar la ventana y cierra el nodo
+settings-notifications = Avisarme, cuando la ventana no tenga el foco, de
+settings-notify-plugin-crashed = Fallos de plugins
+settings-notify-wallet-locked = El bloqueo del monedero
+settings-notify-sign-request = Solicitudes de firma
+settings-notify-peer-connected = Pares que se conectan
+settings-notify-unavailable = El nodo aún no informa de esto a la aplicación
+settings-notify-allow = 🔔 Permitir notificaciones
+settings-notify-denied = El navegador bloquea las notificaciones de esta página
+settings-notify-unsupported = Este navegador no puede mostrar notificaciones
+
+## Commands
+command-palette = Paleta de comandos
+command-show-view = Mostrar { $view }
+command-settings = Abrir ajustes
+command-open-plugin-file = Abrir un archivo de plugin
+command-focus-log-search = Buscar en el registro del nodo
+command-lock-wallet = Bloquear el monedero
+command-reset-layout = Restablecer disposición
+command-export-settings = Exportar ajustes
+command-import-settings = Importar ajustes
+command-reset-settings = Restablecer todos los ajustes
+command-quit = Salir
+command-open-panel = Abrir panel: { $title }
+command-select-plugin = Mostrar el plugin { $name }
+command-run-action = { $plugin }: { $title }
+palette-hint = Escribe un comando
+palette-no-match = Ningún comando coincide
+log-search = 🔍 Buscar en el registro
+
+## Settings files
+transfer-title = Archivo de ajustes
+transfer-imported = Se importaron los ajustes de { $name }
+transfer-import-failed = No se pudo importar { $name }: { $error }
+transfer-export-failed = No se pudieron exportar los ajustes: { $error }
+transfer-reset-title = ¿Restablecer todos los ajustes?
+transfer-reset-question = La disposición, la apariencia, el idioma, los ajustes de plugins y los editores de confianza vuelven a sus valores predeterminados. El monedero se conserva.
+transfer-reset = Restablecer
+transfer-cancel = Cancelar
+transfer-ok = Aceptar
+transfer-unrestored = No se pudieron restaurar los ajustes guardados, así que la aplicación empezó de nuevo con los predeterminados: { $error }. Se guarda una copia con la clave { $key } en { $location }.
+transfer-local-storage = el almacenamiento local del navegador para esta página
+transfer-saved-nowhere = el directorio de datos de la aplicación
+
+## Profiles
+profile-menu = 👤 { $name }
+profile-switch = Cambiar a otro perfil de nodo
+profile-new-hint = Nuevo perfil
+profile-add = ➕ Añadir
+profile-invalid-name = Usa letras, dígitos, - y _
+
+## Updates
+update-available = ✨ Actualización disponible
+update-reload = ⟳ Recargar
+
+## Tray
+tray-show = 👁 Mostrar ventana
+tray-hide = Ocultar ventana
+tray-status = Plugins en ejecución: { $plugins }
+tray-quit = Salir
+
+## Notifications
+notify-plugin-crashed-title = Plugin detenido
+notify-plugin-crashed = { $name }: { $reason }
+notify-sign-request-title = Solicitud de firma
+notify-sign-request = { $plugin } pide firmar con tu monedero
+notify-wallet-locked-title = Monedero bloqueado
+notify-wallet-locked = Desbloquéalo para volver a firmar y usar tus identidades
+
+## Plugin storage
+storage-empty = Ningún plugin ha guardado nada todavía
+storage-plugin = { $plugin } ({ $count ->
+    [one] 1 clave
+   *[other] { $count } claves
+})
+storage-invalid-hex = Hexadecimal no válido: { $error }
+storage-save = Guardar
+storage-delete = Borrar
+storage-new-key = Clave nueva
+storage-new-value = Texto o hex:…
+storage-add = Añadir
+storage-export = 📤 Exportar
+storage-clear = 🧹 Vaciar
+storage-clear-question = { $count ->
+    [one] ¿Borrar la única clave?
+   *[other] ¿Borrar las { $count } claves?
+}
+storage-clear-confirm = Sí, vaciar
+storage-cancel = Cancelar
+storage-quota-exceeded = Cuota de almacenamiento superada: se guardarían { $size } KiB y el límite es { $limit } KiB
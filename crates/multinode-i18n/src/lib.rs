@@ -0,0 +1,248 @@
+//! Translations of the UI strings.
+//!
+//! Each shipped language has a [Fluent](https://projectfluent.org) message bundle in `locales/`,
+//! formatted with `fluent-bundle`, so messages can select on their `{ $variable }`s, such as by
+//! the plural category of a number. The bundles are compiled in, so both the native and the web
+//! app have every language without fetching anything.
+//!
+//! Look messages up with [`tr!`], in the language last chosen with [`set_language`]. Messages
+//! missing from that language fall back to English, and then to the key itself.
+
+use std::sync::{OnceLock, RwLock};
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use unic_langid::LanguageIdentifier;
+
+/// The shipped languages: their code, their name in themselves, and their message bundle
+const LOCALES: [(&str, &str, &str); 3] = [
+    ("en", "English", include_str!("../locales/en.ftl")),
+    ("es", "Español", include_str!("../locales/es.ftl")),
+    ("de", "Deutsch", include_str!("../locales/de.ftl")),
+];
+
+/// The language messages fall back to
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Index in [`LOCALES`] of the current language
+static CURRENT: RwLock<usize> = RwLock::new(0);
+
+type Bundle = FluentBundle<FluentResource>;
+
+/// The parsed bundles, in the order of [`LOCALES`]
+fn bundles() -> &'static [Bundle] {
+    static BUNDLES: OnceLock<Vec<Bundle>> = OnceLock::new();
+    BUNDLES.get_or_init(|| {
+        LOCALES
+            .iter()
+            .map(|(code, _, ftl)| bundle(code, ftl))
+            .collect()
+    })
+}
+
+/// Parses the messages of a language. Messages which don't parse are left out, to fall back
+/// to English, though the tests make sure there are none.
+fn bundle(code: &str, ftl: &str) -> Bundle {
+    let language: LanguageIdentifier = code.parse().unwrap_or_default();
+    let mut bundle = Bundle::new_concurrent(vec![language]);
+    // Fluent marks where variables start and end for bidirectional text, which egui would show
+    bundle.set_use_isolating(false);
+
+    let resource = FluentResource::try_new(ftl.to_owned()).unwrap_or_else(|(resource, _)| resource);
+    // Only fails on messages defined twice, keeping the first
+    let _ = bundle.add_resource(resource);
+    bundle
+}
+
+/// Codes and names of the shipped languages
+pub fn languages() -> impl Iterator<Item = (&'static str, &'static str)> {
+    LOCALES.iter().map(|(code, name, _)| (*code, *name))
+}
+
+/// Switches to the language with `code`. Returns false, keeping the current language, if it
+/// isn't shipped.
+pub fn set_language(code: &str) -> bool {
+    match LOCALES.iter().position(|(c, _, _)| *c == code) {
+        Some(index) => {
+            *CURRENT.write().unwrap() = index;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Code of the current language
+pub fn language() -> &'static str {
+    LOCALES[*CURRENT.read().unwrap()].0
+}
+
+/// The message `key` in the current language, with its variables given by `args`. Use [`tr!`]
+/// rather than calling this directly.
+pub fn translate(key: &str, args: &[(&str, String)]) -> String {
+    let bundles = bundles();
+    let current = &bundles[*CURRENT.read().unwrap()];
+    let found = [current, &bundles[0]].into_iter().find_map(|bundle| {
+        let pattern = bundle.get_message(key)?.value()?;
+        Some((bundle, pattern))
+    });
+    let Some((bundle, pattern)) = found else {
+        return key.to_owned();
+    };
+
+    let mut fluent_args = FluentArgs::new();
+    for (name, value) in args {
+        fluent_args.set(*name, value_of(value));
+    }
+    // Errors, like a missing variable, are formatted into the message for all to see
+    let mut errors = Vec::new();
+    bundle
+        .format_pattern(pattern, Some(&fluent_args), &mut errors)
+        .into_owned()
+}
+
+/// Values which are plain numbers are passed as such, so messages can select on them
+fn value_of(value: &str) -> FluentValue<'_> {
+    let number = !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_digit() || b == b'.' || b == b'-');
+    if number {
+        FluentValue::try_number(value)
+    } else {
+        FluentValue::from(value)
+    }
+}
+
+/// Looks up a message in the current language, filling in its variables.
+///
+/// ## Example:
+/// ``` ignore
+/// ui.label(tr!("wallet-unlock"));
+/// ui.label(tr!("sign-bytes", count = payload.len()));
+/// ```
+#[macro_export]
+macro_rules! tr {
+    ($key:expr) => {
+        $crate::translate($key, &[])
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::translate($key, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fluent_syntax::ast;
+
+    /// Adds the variables `pattern` uses to `variables`
+    fn pattern_variables<'a>(pattern: &ast::Pattern<&'a str>, variables: &mut Vec<&'a str>) {
+        for element in &pattern.elements {
+            if let ast::PatternElement::Placeable { expression } = element {
+                expression_variables(expression, variables);
+            }
+        }
+    }
+
+    fn expression_variables<'a>(
+        expression: &ast::Expression<&'a str>,
+        variables: &mut Vec<&'a str>,
+    ) {
+        let inline = match expression {
+            ast::Expression::Select { selector, variants } => {
+                for variant in variants {
+                    pattern_variables(&variant.value, variables);
+                }
+                selector
+            }
+            ast::Expression::Inline(inline) => inline,
+        };
+        match inline {
+            ast::InlineExpression::VariableReference { id } => variables.push(id.name),
+            ast::InlineExpression::Placeable { expression } => {
+                expression_variables(expression, variables)
+            }
+            _ => {}
+        }
+    }
+
+    /// The messages of a bundle, and the variables each uses
+    fn messages(ftl: &'static str) -> Vec<(&'static str, Vec<&'static str>)> {
+        let resource = fluent_syntax::parser::parse(ftl).expect("bundles parse");
+        resource
+            .body
+            .iter()
+            .filter_map(|entry| match entry {
+                ast::Entry::Message(message) => {
+                    let mut variables = Vec::new();
+                    if let Some(value) = &message.value {
+                        pattern_variables(value, &mut variables);
+                    }
+                    variables.sort_unstable();
+                    variables.dedup();
+                    Some((message.id.name, variables))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_key_is_in_every_locale() {
+        let (_, _, default) = LOCALES[0];
+        let default = messages(default);
+        assert!(!default.is_empty());
+
+        for (code, _, ftl) in LOCALES.iter().skip(1) {
+            let translated = messages(ftl);
+            for (key, variables) in &default {
+                let (_, translated) = translated
+                    .iter()
+                    .find(|(k, _)| k == key)
+                    .unwrap_or_else(|| panic!("{code} is missing {key}"));
+                assert_eq!(
+                    variables, translated,
+                    "{code} uses other variables in {key}"
+                );
+            }
+            for (key, _) in &translated {
+                assert!(
+                    default.iter().any(|(k, _)| k == key),
+                    "{code} has unknown key {key}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn formats_messages() {
+        let bundle = bundle(
+            "en",
+            "# A comment\n\
+             hello = Hello, { $name }!\n\
+             files = { $count ->\n    [one] One file\n   *[other] { $count } files\n}\n",
+        );
+        let format = |key, args: &[(&str, String)]| {
+            let mut fluent_args = FluentArgs::new();
+            for (name, value) in args {
+                fluent_args.set(*name, value_of(value));
+            }
+            let pattern = bundle.get_message(key).unwrap().value().unwrap();
+            bundle
+                .format_pattern(pattern, Some(&fluent_args), &mut Vec::new())
+                .into_owned()
+        };
+        assert_eq!(format("hello", &[("name", "Ada".into())]), "Hello, Ada!");
+        assert_eq!(format("files", &[("count", "1".into())]), "One file");
+        assert_eq!(format("files", &[("count", "3".into())]), "3 files");
+    }
+
+    #[test]
+    fn translates_with_fallback() {
+        assert!(!set_language("xx"));
+        assert_eq!(language(), DEFAULT_LANGUAGE);
+
+        assert_eq!(tr!("no-such-key"), "no-such-key");
+        assert_eq!(tr!("sign-bytes", count = 3), "3 bytes");
+    }
+}
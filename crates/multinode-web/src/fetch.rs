@@ -0,0 +1,55 @@
+use eframe::wasm_bindgen::{JsCast, JsValue};
+use eframe::web_sys;
+use eframe::web_sys::js_sys::{Reflect, Uint8Array};
+use wasm_bindgen_futures::JsFuture;
+
+/// Downloads `url` with the browser's `fetch`.
+///
+/// `on_progress` is called with the bytes received so far and the total, if the server sent
+/// one. `on_done` is called once with the downloaded bytes or an error.
+pub fn fetch(
+    url: String,
+    mut on_progress: impl FnMut(u64, Option<u64>) + 'static,
+    on_done: impl FnOnce(Result<Vec<u8>, String>) + 'static,
+) {
+    wasm_bindgen_futures::spawn_local(async move {
+        let result = download(&url, &mut on_progress)
+            .await
+            .map_err(|e| e.as_string().unwrap_or_else(|| format!("{e:?}")));
+        on_done(result);
+    });
+}
+
+async fn download(
+    url: &str,
+    on_progress: &mut impl FnMut(u64, Option<u64>),
+) -> Result<Vec<u8>, JsValue> {
+    let window = web_sys::window().ok_or("No window")?;
+    let response: web_sys::Response = JsFuture::from(window.fetch_with_str(url))
+        .await?
+        .dyn_into()?;
+
+    if !response.ok() {
+        return Err(format!("HTTP {} {}", response.status(), response.status_text()).into());
+    }
+
+    let total = response
+        .headers()
+        .get("Content-Length")?
+        .and_then(|len| len.parse::<u64>().ok());
+    let body = response.body().ok_or("Response has no body")?;
+    let reader: web_sys::ReadableStreamDefaultReader = body.get_reader().dyn_into()?;
+
+    let mut bytes = Vec::new();
+    loop {
+        let chunk = JsFuture::from(reader.read()).await?;
+        if Reflect::get(&chunk, &"done".into())?.is_truthy() {
+            break;
+        }
+        let value = Reflect::get(&chunk, &"value".into())?;
+        bytes.extend(Uint8Array::new(&value).to_vec());
+        on_progress(bytes.len() as u64, total);
+    }
+
+    Ok(bytes)
+}
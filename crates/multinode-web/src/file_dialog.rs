@@ -4,7 +4,8 @@ use std::rc::Rc;
 use eframe::wasm_bindgen::prelude::Closure;
 use eframe::wasm_bindgen::{JsCast, JsValue};
 use eframe::web_sys;
-use eframe::web_sys::{js_sys::Uint8Array, Event, FileReader};
+use eframe::web_sys::{js_sys::Array, js_sys::Uint8Array, Event, FileReader};
+use multinode_i18n::tr;
 
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)]
@@ -18,76 +19,17 @@ impl FileDialog {
     pub fn file_dialog(
         &mut self,
         ui: &mut egui::Ui,
-        on_load_callback: impl FnMut(String, Vec<u8>) + 'static,
+        on_load_callback: impl FnMut(String, Vec<u8>, Option<Vec<u8>>) + 'static,
     ) -> Result<(), JsValue> {
-        ui.label("Drag-and-drop files onto the window!");
+        ui.label(tr!("plugins-drop-files"));
 
-        if ui.button("Open file…").clicked() {
-            let window = web_sys::window().unwrap();
-            let document = window.document().unwrap();
-            let input = document.create_element("input").unwrap();
-            input.set_attribute("type", "file").unwrap();
-
-            // Wrap the callback in Rc<RefCell<...>> to allow multiple uses
-            let callback = Rc::new(RefCell::new(on_load_callback));
-
-            let input: web_sys::HtmlInputElement = input.dyn_into().unwrap();
-            input.set_attribute("multiple", "false").unwrap();
-            input.set_attribute("accept", ".wasm").unwrap();
-            let on_loaded = {
-                let callback = callback.clone();
-                Closure::wrap(Box::new(move |event: Event| {
-                    let target = event.target().expect("Event should have a target");
-                    let file_reader = target
-                        .dyn_ref::<FileReader>()
-                        .expect("Target should be a FileReader");
-
-                    if let Ok(result) = file_reader.result() {
-                        let array = Uint8Array::new(&result);
-                        let bytes = array.to_vec();
-
-                        // Now you have the file bytes in the `bytes` vector
-                        tracing::info!("File loaded, size: {} bytes", bytes.len());
-
-                        // Here you can process the bytes as needed
-                        // For example, you could pass them to another function:
-                        // process_file_bytes(&bytes);
-
-                        // Call the provided callback with the loaded bytes
-                        callback.borrow_mut()("file.wasm".to_owned(), bytes);
-                    }
-                }) as Box<dyn FnMut(Event)>)
-            };
-
-            let file_reader = FileReader::new()?;
-            file_reader.set_onload(Some(on_loaded.as_ref().unchecked_ref()));
-            on_loaded.forget(); // Prevent the closure from being dropped
-
-            let onchange = Closure::wrap(Box::new(move |event: Event| {
-                let target = event.target().expect("Event should have a target");
-                let input: web_sys::HtmlInputElement = target
-                    .dyn_into()
-                    .expect("Target should be an HtmlInputElement");
-
-                if let Some(file) = input.files().and_then(|files| files.get(0)) {
-                    file_reader
-                        .read_as_array_buffer(&file)
-                        .expect("Failed to read file");
-                }
-            }) as Box<dyn FnMut(Event)>);
-
-            input.add_event_listener_with_callback("change", onchange.as_ref().unchecked_ref())?;
-            onchange.forget(); // Prevent the closure from being dropped
-
-            // Append the input to the document body
-            document.body().unwrap().append_child(&input)?;
-
-            return Ok(());
+        if ui.button(tr!("plugins-open-file")).clicked() {
+            return self.pick(on_load_callback);
         }
 
         if let Some(picked_path) = &self.picked_path {
             ui.vertical(|ui| {
-                ui.label("Picked file:");
+                ui.label(tr!("plugins-picked-file"));
                 ui.monospace(picked_path);
             });
         }
@@ -95,7 +37,7 @@ impl FileDialog {
         // Show dropped files (if any):
         if !self.dropped_files.is_empty() {
             ui.group(|ui| {
-                ui.label("Plugins:");
+                ui.label(tr!("plugins-dropped"));
 
                 for file in &self.dropped_files {
                     let mut info = if let Some(path) = &file.path {
@@ -130,4 +72,129 @@ impl FileDialog {
         });
         Ok(())
     }
+
+    /// Asks the user for a plugin file, and hands it to `on_load_callback` once read.
+    ///
+    /// Browsers can't read a sidecar signature next to the file, so the user picks it along
+    /// with the plugin: `<file>.wasm.sig` signs `<file>.wasm`.
+    pub fn pick(
+        &mut self,
+        on_load_callback: impl FnMut(String, Vec<u8>, Option<Vec<u8>>) + 'static,
+    ) -> Result<(), JsValue> {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let input: web_sys::HtmlInputElement = document.create_element("input")?.dyn_into()?;
+        input.set_type("file");
+        input.set_multiple(true);
+        input.set_accept(".wasm,.sig");
+
+        let callback = Rc::new(RefCell::new(on_load_callback));
+        let onchange = Closure::wrap(Box::new(move |event: Event| {
+            let Some(files) = event
+                .target()
+                .and_then(|target| target.dyn_into::<web_sys::HtmlInputElement>().ok())
+                .and_then(|input| input.files())
+            else {
+                return;
+            };
+            let files: Vec<web_sys::File> =
+                (0..files.length()).filter_map(|i| files.get(i)).collect();
+
+            for wasm in files.iter().filter(|file| file.name().ends_with(".wasm")) {
+                let file_name = wasm.name();
+                let name = file_name.trim_end_matches(".wasm").to_owned();
+                let sidecar = files
+                    .iter()
+                    .find(|file| file.name() == format!("{file_name}.sig"))
+                    .cloned();
+
+                let callback = callback.clone();
+                read(wasm, move |bytes| {
+                    tracing::info!("File loaded, size: {} bytes", bytes.len());
+                    match sidecar {
+                        Some(sidecar) => read(&sidecar, move |signature| {
+                            callback.borrow_mut()(name, bytes, Some(signature))
+                        }),
+                        None => callback.borrow_mut()(name, bytes, None),
+                    }
+                });
+            }
+        }) as Box<dyn FnMut(Event)>);
+
+        input.add_event_listener_with_callback("change", onchange.as_ref().unchecked_ref())?;
+        onchange.forget(); // Prevent the closure from being dropped
+
+        input.click();
+        Ok(())
+    }
+}
+
+/// Reads `file`, handing its bytes to `on_read` once the browser has
+fn read(file: &web_sys::File, on_read: impl FnOnce(Vec<u8>) + 'static) {
+    let Ok(file_reader) = FileReader::new() else {
+        return;
+    };
+    let reader = file_reader.clone();
+    let on_loaded = Closure::once(move |_: Event| {
+        if let Ok(result) = reader.result() {
+            on_read(Uint8Array::new(&result).to_vec());
+        }
+    });
+    file_reader.set_onload(Some(on_loaded.as_ref().unchecked_ref()));
+    on_loaded.forget(); // Prevent the closure from being dropped
+
+    if let Err(e) = file_reader.read_as_array_buffer(file) {
+        tracing::error!("Failed to read file: {:?}", e);
+    }
+}
+
+/// Offers `bytes` to the user as a download named `file_name`
+pub fn save_file(file_name: &str, bytes: &[u8]) -> Result<(), JsValue> {
+    let parts = Array::of1(&Uint8Array::from(bytes));
+    let blob = web_sys::Blob::new_with_u8_array_sequence(&parts)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+
+    let document = web_sys::window().unwrap().document().unwrap();
+    let anchor: web_sys::HtmlAnchorElement = document.create_element("a")?.dyn_into()?;
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+
+    web_sys::Url::revoke_object_url(&url)
+}
+
+/// Asks the user for a file with one of `extensions`, and hands its name and bytes to `on_open`
+/// once the browser has read it
+pub fn open_file(
+    extensions: &[&str],
+    on_open: impl FnOnce(String, Vec<u8>) + 'static,
+) -> Result<(), JsValue> {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let input: web_sys::HtmlInputElement = document.create_element("input")?.dyn_into()?;
+    input.set_type("file");
+    let accept: Vec<String> = extensions.iter().map(|ext| format!(".{ext}")).collect();
+    input.set_accept(&accept.join(","));
+
+    let mut on_open = Some(on_open);
+    let onchange = Closure::wrap(Box::new(move |event: Event| {
+        let Some(input) = event
+            .target()
+            .and_then(|target| target.dyn_into::<web_sys::HtmlInputElement>().ok())
+        else {
+            return;
+        };
+        let (Some(file), Some(on_open)) =
+            (input.files().and_then(|files| files.get(0)), on_open.take())
+        else {
+            return;
+        };
+
+        let name = file.name();
+        read(&file, move |bytes| on_open(name, bytes));
+    }) as Box<dyn FnMut(Event)>);
+
+    input.add_event_listener_with_callback("change", onchange.as_ref().unchecked_ref())?;
+    onchange.forget(); // Prevent the closure from being dropped
+
+    input.click();
+    Ok(())
 }
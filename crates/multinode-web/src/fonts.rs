@@ -0,0 +1,50 @@
+//! The font file the user picked, kept in the browser's IndexedDB.
+//!
+//! A font is too big for the app state in local storage, and a page can't read a file again
+//! once the user picked it, so its bytes are copied here under a key which the state keeps
+//! instead. There is one custom font at a time, so saving one replaces the last.
+
+use eframe::wasm_bindgen::JsValue;
+use eframe::web_sys::js_sys::Uint8Array;
+use eframe::web_sys::IdbTransactionMode;
+
+use crate::storage::{object_store, request};
+
+/// Kept apart from the profiles' databases, named `peerpiper-multinode-{profile}`, by a `.`
+/// which profile names can't have
+const DB_NAME: &str = "peerpiper-multinode.fonts";
+const STORE_NAME: &str = "fonts";
+
+/// Saves the font's `bytes` under `key` in the background, in place of the last font saved
+pub fn save(key: &str, bytes: &[u8]) {
+    let key = JsValue::from_str(key);
+    let bytes = Uint8Array::from(bytes);
+    wasm_bindgen_futures::spawn_local(async move {
+        let result = async {
+            let store = object_store(DB_NAME, STORE_NAME, IdbTransactionMode::Readwrite).await?;
+            store.clear()?;
+            request(&store.put_with_key(&bytes, &key)?).await
+        }
+        .await;
+        if let Err(e) = result {
+            tracing::error!("Failed to save the font: {e:?}");
+        }
+    });
+}
+
+/// Loads the font saved under `key` in the background, handing its bytes to `on_load`
+pub fn load(key: &str, on_load: impl FnOnce(Result<Vec<u8>, String>) + 'static) {
+    let key = JsValue::from_str(key);
+    wasm_bindgen_futures::spawn_local(async move {
+        let result = async {
+            let store = object_store(DB_NAME, STORE_NAME, IdbTransactionMode::Readonly).await?;
+            request(&store.get(&key)?).await
+        }
+        .await;
+        on_load(match result {
+            Ok(value) if value.is_undefined() => Err("no such font was saved".to_owned()),
+            Ok(value) => Ok(Uint8Array::new(&value).to_vec()),
+            Err(e) => Err(format!("{e:?}")),
+        });
+    });
+}
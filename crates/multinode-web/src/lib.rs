@@ -1,4 +1,10 @@
 #![cfg(target_arch = "wasm32")]
 
+pub mod fetch;
 pub mod file_dialog;
+pub mod fonts;
+pub mod notify;
 pub mod platform;
+pub mod storage;
+pub mod update;
+pub mod vault;
@@ -0,0 +1,96 @@
+//! Notifications through the browser's Notifications API
+//!
+//! Browsers only show notifications the user allowed, and only ask them when the page asks in
+//! response to something they did, such as clicking a button. Pages with a service worker show
+//! them through it, as some browsers, such as Chrome for Android, don't let those pages show
+//! them themselves.
+
+use eframe::wasm_bindgen::{JsCast, JsValue};
+use eframe::web_sys;
+use eframe::web_sys::js_sys::Reflect;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    Notification, NotificationOptions, NotificationPermission, ServiceWorkerRegistration,
+};
+
+use crate::update::service_workers;
+
+/// Whether the page may show notifications
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Permission {
+    /// The browser has no Notifications API
+    Unsupported,
+
+    /// The user wasn't asked yet
+    Ask,
+
+    Granted,
+    Denied,
+}
+
+fn supported() -> bool {
+    web_sys::window().is_some_and(|window| {
+        Reflect::has(&window, &JsValue::from_str("Notification")).unwrap_or(false)
+    })
+}
+
+/// Whether the page may show notifications
+pub fn permission() -> Permission {
+    if !supported() {
+        return Permission::Unsupported;
+    }
+    match Notification::permission() {
+        NotificationPermission::Granted => Permission::Granted,
+        NotificationPermission::Denied => Permission::Denied,
+        _ => Permission::Ask,
+    }
+}
+
+/// Asks the user to allow notifications, calling `on_answer` once they did
+pub fn request_permission(on_answer: impl FnOnce() + 'static) {
+    if !supported() {
+        return;
+    }
+    match Notification::request_permission() {
+        Ok(promise) => wasm_bindgen_futures::spawn_local(async move {
+            if let Err(e) = JsFuture::from(promise).await {
+                tracing::error!("Failed to ask for notifications: {e:?}");
+            }
+            on_answer();
+        }),
+        Err(e) => tracing::error!("Failed to ask for notifications: {e:?}"),
+    }
+}
+
+/// The registration of the page's service worker, if it has one
+async fn registration() -> Option<ServiceWorkerRegistration> {
+    JsFuture::from(service_workers()?.get_registration())
+        .await
+        .ok()?
+        .dyn_into()
+        .ok()
+}
+
+/// Shows a notification in the background, if the user allowed them
+pub fn show(title: &str, body: &str) {
+    if permission() != Permission::Granted {
+        return;
+    }
+    let title = title.to_owned();
+    let options = NotificationOptions::new();
+    options.set_body(body);
+    wasm_bindgen_futures::spawn_local(async move {
+        let shown = match registration().await {
+            Some(registration) => {
+                match registration.show_notification_with_options(&title, &options) {
+                    Ok(promise) => JsFuture::from(promise).await.map(drop),
+                    Err(e) => Err(e),
+                }
+            }
+            None => Notification::new_with_options(&title, &options).map(drop),
+        };
+        if let Err(e) = shown {
+            tracing::error!("Failed to show notification: {e:?}");
+        }
+    });
+}
@@ -1,6 +1,13 @@
 use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
 use std::rc::Rc;
 
+use multinode_i18n::tr;
+
+use crate::storage::{KvStore, QuotaExceeded};
+use crate::update::Updates;
+use crate::vault::VaultKey;
+
 /// Reference counted [egui::Context] with a flag to indicate whether it has been set
 /// Track whether the Context has been set
 #[derive(Debug, Default)]
@@ -22,7 +29,6 @@ impl ContextSet {
     }
 
     /// Requests repaint. Successful only if the Context has been set.
-    #[allow(dead_code)]
     pub(crate) fn request_repaint(&self) {
         if self.set {
             self.ctx.request_repaint();
@@ -34,18 +40,61 @@ impl ContextSet {
 pub struct Platform {
     /// The Context
     ctx: Rc<RefCell<ContextSet>>,
+
+    /// Key-value storage of every plugin, namespaced by plugin name
+    storage: KvStore,
+
+    /// The profile's wallet, saved as soon as it changes
+    vault: VaultKey,
+
+    /// Whether a new build of the app is waiting
+    updates: Updates,
+
+    /// Decides which plugins may load, from their wasm and sidecar signature. Until the trust
+    /// settings are handed over, none may.
+    trust: Rc<RefCell<Option<TrustCheck>>>,
 }
 
-impl Default for Platform {
-    fn default() -> Self {
+/// Whether the named plugin may load, given its wasm and sidecar signature
+pub type TrustCheck = Box<dyn Fn(&str, &[u8], Option<&[u8]>) -> bool>;
+
+impl Platform {
+    /// Starts with the data of the named profile, or of the default profile if `None`
+    pub fn new(profile: Option<&str>) -> Self {
+        let ctx = Rc::new(RefCell::new(ContextSet::new()));
+
+        let ctx_clone = ctx.clone();
+        let storage = KvStore::open(profile, move || ctx_clone.borrow().request_repaint());
+
+        let ctx_clone = ctx.clone();
+        let updates = Updates::watch(move || ctx_clone.borrow().request_repaint());
+
         Self {
-            ctx: Rc::new(RefCell::new(ContextSet::new())),
+            ctx,
+            storage,
+            vault: VaultKey::new(profile),
+            updates,
+            trust: Default::default(),
         }
     }
-}
 
-impl Platform {
-    pub fn close(&mut self) {}
+    /// Shows a notification, if the user allowed them, see [`crate::notify`]
+    pub fn notify(&self, title: &str, body: &str) {
+        crate::notify::show(title, body);
+    }
+
+    /// Whether a new build of the app is waiting to be reloaded into
+    pub fn update_available(&self) -> bool {
+        self.updates.available()
+    }
+
+    /// Reloads the page into the new build
+    pub fn apply_update(&self) {
+        self.updates.apply();
+    }
+
+    /// The browser connects to a node rather than running one, so there's none to stop
+    pub fn close(&self) {}
 
     /// Returns whether the ctx is set or not
     pub fn egui_ctx(&self) -> bool {
@@ -61,12 +110,114 @@ impl Platform {
     /// Show the GUI for this platform
     pub fn show(&self, _ctx: &egui::Context, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
-            ui.label("Connect to multinode: ");
+            ui.label(tr!("node-connect"));
         });
     }
 
-    /// Loads the plugin (TODO)
-    pub fn load_plugin(&self, _name: String, _bytes: Vec<u8>) {
-        // TODO
+    /// A browser node keeps no log of its own
+    pub fn log_ui(&self, ui: &mut egui::Ui) {
+        ui.weak(tr!("node-log-remote"));
+    }
+
+    /// Peers are those of the multinode you connect to
+    pub fn peers_ui(&self, ui: &mut egui::Ui) {
+        ui.weak(tr!("peers-remote"));
+    }
+
+    /// Plugin panels need a plugin runtime, which only a native node has
+    pub fn panels(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    /// Plugin panels need a plugin runtime, which only a native node has
+    pub fn panel_ui(&self, _name: &str, _ui: &mut egui::Ui) -> bool {
+        false
+    }
+
+    /// Plugin panels need a plugin runtime, which only a native node has
+    pub fn set_panel_open(&self, _name: &str, _open: bool) {}
+
+    /// Plugins need a plugin runtime, which only a native node has
+    pub fn plugin_commands<C>(&self) -> Vec<C> {
+        Vec::new()
+    }
+
+    /// Plugins need a plugin runtime, which only a native node has
+    pub fn select_plugin(&self, _name: &str) {}
+
+    /// Plugins need a plugin runtime, which only a native node has
+    pub fn run_action(&self, _name: &str, _id: &str) {}
+
+    /// Replaces what decides which plugins may load
+    pub fn set_trust(&self, check: impl Fn(&str, &[u8], Option<&[u8]>) -> bool + 'static) {
+        *self.trust.borrow_mut() = Some(Box::new(check));
+    }
+
+    /// Loads the plugin, if the trust settings allow it (TODO)
+    pub fn load_plugin(&self, name: String, wasm: Vec<u8>, signature: Option<Vec<u8>>) {
+        let allowed = match &*self.trust.borrow() {
+            Some(check) => check(&name, &wasm, signature.as_deref()),
+            None => {
+                tracing::error!("Blocked plugin {name}, as there are no trust settings yet");
+                false
+            }
+        };
+        if allowed {
+            // TODO
+        }
+    }
+
+    /// Names of the plugins with stored keys
+    pub fn storage_plugins(&self) -> Vec<String> {
+        self.storage.plugins()
+    }
+
+    /// Snapshot of the named plugin's stored keys and values
+    pub fn storage_entries(&self, name: &str) -> BTreeMap<String, Vec<u8>> {
+        self.storage.entries(name)
+    }
+
+    /// Stores `value` under `key` for the named plugin, within its storage quota
+    pub fn storage_set(
+        &self,
+        name: &str,
+        key: String,
+        value: Vec<u8>,
+    ) -> Result<(), QuotaExceeded> {
+        self.storage.set(name, key, value)
     }
+
+    /// Removes `key` from the named plugin's storage
+    pub fn storage_delete(&self, name: &str, key: &str) {
+        self.storage.delete(name, key);
+    }
+
+    /// Removes every key from the named plugin's storage
+    pub fn storage_clear(&self, name: &str) {
+        self.storage.clear(name);
+    }
+
+    /// The wallet's vault as last saved, see [`crate::vault`]
+    pub fn saved_vault(&self) -> Option<String> {
+        self.vault.load()
+    }
+
+    /// Saves the wallet's vault
+    pub fn save_vault(&self, vault: &str) -> std::io::Result<()> {
+        self.vault.save(vault)
+    }
+
+    /// Plugins only sign on a native node, so the wallet's seed isn't needed here
+    pub fn set_wallet<S>(&self, _seed: Option<S>) {}
+
+    /// Plugins only sign on a native node
+    pub fn set_always_allow(&self, _plugins: BTreeSet<String>) {}
+
+    /// Plugins only sign on a native node, so there are never requests to sign
+    pub fn sign_request<R>(&self) -> Option<R> {
+        None
+    }
+
+    /// Plugins only sign on a native node
+    pub fn answer_sign_request(&self, _approve: bool) {}
 }
@@ -0,0 +1,295 @@
+//! Per-plugin key-value storage, persisted in the browser's IndexedDB.
+//!
+//! Keys are kept in memory for the UI and written through to an object store keyed by
+//! `[plugin, key]` arrays, so each plugin's keys form a contiguous range. Writes are queued, so
+//! they land in the order they were made. Every profile but the default one has a database of
+//! its own, and each plugin may store up to [QUOTA] bytes.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::rc::Rc;
+
+use eframe::wasm_bindgen::prelude::Closure;
+use eframe::wasm_bindgen::{JsCast, JsValue};
+use eframe::web_sys;
+use eframe::web_sys::js_sys::{Array, Promise, Uint8Array};
+use multinode_i18n::tr;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbKeyRange, IdbObjectStore, IdbRequest, IdbTransactionMode};
+
+const DB_NAME: &str = "peerpiper-multinode";
+const STORE_NAME: &str = "plugin-kv";
+
+/// Most bytes of keys and values each plugin may store
+pub const QUOTA: usize = 4 * 1024 * 1024;
+
+/// Keys and values of one plugin
+type Entries = BTreeMap<String, Vec<u8>>;
+
+/// Storing a value would take the plugin over its [QUOTA]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaExceeded {
+    /// Bytes the plugin would store
+    pub size: usize,
+}
+
+impl fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = tr!(
+            "storage-quota-exceeded",
+            size = self.size.div_ceil(1024),
+            limit = QUOTA / 1024
+        );
+        f.write_str(&message)
+    }
+}
+
+/// A write to the object store
+type Op = Box<dyn FnOnce(IdbObjectStore) -> Result<IdbRequest, JsValue>>;
+
+/// Writes waiting for those before them to land
+#[derive(Default)]
+struct Writes {
+    queue: VecDeque<(&'static str, Op)>,
+
+    /// Whether a task is working through the queue
+    busy: bool,
+}
+
+/// Every plugin's keys, kept in memory and written through to IndexedDB
+#[derive(Clone)]
+pub struct KvStore {
+    /// Name of the profile's database
+    db: String,
+
+    plugins: Rc<RefCell<BTreeMap<String, Entries>>>,
+
+    writes: Rc<RefCell<Writes>>,
+}
+
+/// Resolves once the request succeeds or fails
+pub(crate) async fn request(request: &IdbRequest) -> Result<JsValue, JsValue> {
+    let promise = Promise::new(&mut |resolve, reject| {
+        let on_success = Closure::once_into_js(move |event: web_sys::Event| {
+            let result = event
+                .target()
+                .and_then(|target| target.dyn_into::<IdbRequest>().ok())
+                .map(|request| request.result().unwrap_or(JsValue::UNDEFINED))
+                .unwrap_or(JsValue::UNDEFINED);
+            let _ = resolve.call1(&JsValue::NULL, &result);
+        });
+        let on_error = Closure::once_into_js(move |event: web_sys::Event| {
+            let _ = reject.call1(&JsValue::NULL, &event);
+        });
+        request.set_onsuccess(Some(on_success.unchecked_ref()));
+        request.set_onerror(Some(on_error.unchecked_ref()));
+    });
+    JsFuture::from(promise).await
+}
+
+/// Name of the profile's database. The default profile's is the one from before there were
+/// profiles.
+fn db_name(profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) => format!("{DB_NAME}-{profile}"),
+        None => DB_NAME.to_owned(),
+    }
+}
+
+/// Opens the database, creating its one object store on first use
+async fn open(db: &str, store: &'static str) -> Result<IdbDatabase, JsValue> {
+    let factory = web_sys::window()
+        .ok_or("No window")?
+        .indexed_db()?
+        .ok_or("IndexedDB is not available")?;
+    let open = factory.open_with_u32(db, 1)?;
+
+    let on_upgrade = Closure::once_into_js(move |event: web_sys::Event| {
+        let db = event
+            .target()
+            .and_then(|target| target.dyn_into::<IdbRequest>().ok())
+            .and_then(|request| request.result().ok())
+            .and_then(|db| db.dyn_into::<IdbDatabase>().ok());
+        if let Some(db) = db {
+            if let Err(e) = db.create_object_store(store) {
+                tracing::error!("Failed to create object store {store}: {e:?}");
+            }
+        }
+    });
+    open.set_onupgradeneeded(Some(on_upgrade.unchecked_ref()));
+
+    request(&open).await?.dyn_into()
+}
+
+/// The database's object store, in a transaction of its own
+pub(crate) async fn object_store(
+    db: &str,
+    store: &'static str,
+    mode: IdbTransactionMode,
+) -> Result<IdbObjectStore, JsValue> {
+    open(db, store)
+        .await?
+        .transaction_with_str_and_mode(store, mode)?
+        .object_store(store)
+}
+
+fn key(plugin: &str, key: &str) -> JsValue {
+    Array::of2(&plugin.into(), &key.into()).into()
+}
+
+/// Every key of the plugin: from `[plugin]` up to `[plugin, []]`, since arrays sort after strings
+fn range(plugin: &str) -> Result<IdbKeyRange, JsValue> {
+    IdbKeyRange::bound(
+        &Array::of1(&plugin.into()),
+        &Array::of2(&plugin.into(), &Array::new()),
+    )
+}
+
+/// Runs the queued writes one after another, logging failures
+async fn drain(db: String, writes: Rc<RefCell<Writes>>) {
+    loop {
+        let next = writes.borrow_mut().queue.pop_front();
+        let Some((what, op)) = next else {
+            writes.borrow_mut().busy = false;
+            return;
+        };
+
+        let result = async {
+            let store = object_store(&db, STORE_NAME, IdbTransactionMode::Readwrite).await?;
+            request(&op(store)?).await
+        }
+        .await;
+
+        if let Err(e) = result {
+            tracing::error!("Failed to {what} plugin storage: {e:?}");
+        }
+    }
+}
+
+impl KvStore {
+    /// Opens the profile's store and loads its keys in the background, calling `on_loaded` when
+    /// done
+    pub fn open(profile: Option<&str>, on_loaded: impl FnOnce() + 'static) -> Self {
+        let store = Self {
+            db: db_name(profile),
+            plugins: Default::default(),
+            writes: Default::default(),
+        };
+        let plugins = store.plugins.clone();
+        let db = store.db.clone();
+
+        wasm_bindgen_futures::spawn_local(async move {
+            let result = async {
+                let store = object_store(&db, STORE_NAME, IdbTransactionMode::Readonly).await?;
+                let keys: Array = request(&store.get_all_keys()?).await?.dyn_into()?;
+                let values: Array = request(&store.get_all()?).await?.dyn_into()?;
+                Ok::<_, JsValue>((keys, values))
+            }
+            .await;
+
+            match result {
+                Ok((keys, values)) => {
+                    let mut plugins = plugins.borrow_mut();
+                    for (key, value) in keys.iter().zip(values.iter()) {
+                        let key: Array = key.unchecked_into();
+                        if let (Some(plugin), Some(key)) =
+                            (key.get(0).as_string(), key.get(1).as_string())
+                        {
+                            // Keys set while loading are newer than what was stored
+                            let value = Uint8Array::new(&value).to_vec();
+                            plugins
+                                .entry(plugin)
+                                .or_default()
+                                .entry(key)
+                                .or_insert(value);
+                        }
+                    }
+                }
+                Err(e) => tracing::error!("Failed to load plugin storage: {e:?}"),
+            }
+
+            on_loaded();
+        });
+
+        store
+    }
+
+    /// Queues the write after those made before it
+    fn write(
+        &self,
+        what: &'static str,
+        op: impl FnOnce(IdbObjectStore) -> Result<IdbRequest, JsValue> + 'static,
+    ) {
+        let mut writes = self.writes.borrow_mut();
+        writes.queue.push_back((what, Box::new(op)));
+        if !writes.busy {
+            writes.busy = true;
+            let db = self.db.clone();
+            wasm_bindgen_futures::spawn_local(drain(db, self.writes.clone()));
+        }
+    }
+
+    /// Names of the plugins with stored keys
+    pub fn plugins(&self) -> Vec<String> {
+        self.plugins.borrow().keys().cloned().collect()
+    }
+
+    /// Snapshot of the plugin's keys and values
+    pub fn entries(&self, plugin: &str) -> Entries {
+        self.plugins
+            .borrow()
+            .get(plugin)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Stores `value` under `key`, unless that would take the plugin over its [QUOTA]
+    pub fn set(&self, plugin: &str, key: String, value: Vec<u8>) -> Result<(), QuotaExceeded> {
+        let mut plugins = self.plugins.borrow_mut();
+        let size = plugins.get(plugin).map_or(0, |entries| {
+            entries
+                .iter()
+                .filter(|(stored, _)| **stored != key)
+                .map(|(key, value)| key.len() + value.len())
+                .sum()
+        }) + key.len()
+            + value.len();
+        if size > QUOTA {
+            return Err(QuotaExceeded { size });
+        }
+
+        let id = self::key(plugin, &key);
+        let bytes = Uint8Array::from(value.as_slice());
+        self.write("write", move |store| store.put_with_key(&bytes, &id));
+
+        plugins
+            .entry(plugin.to_owned())
+            .or_default()
+            .insert(key, value);
+        Ok(())
+    }
+
+    pub fn delete(&self, plugin: &str, key: &str) {
+        let id = self::key(plugin, key);
+        self.write("delete from", move |store| store.delete(&id));
+
+        let mut plugins = self.plugins.borrow_mut();
+        if let Some(entries) = plugins.get_mut(plugin) {
+            entries.remove(key);
+            if entries.is_empty() {
+                plugins.remove(plugin);
+            }
+        }
+    }
+
+    /// Removes every key of the plugin
+    pub fn clear(&self, plugin: &str) {
+        let plugin = plugin.to_owned();
+        self.plugins.borrow_mut().remove(&plugin);
+        self.write("clear", move |store| {
+            let range: JsValue = range(&plugin)?.into();
+            store.delete(&range)
+        });
+    }
+}
@@ -0,0 +1,174 @@
+//! Updates of the app, announced by its service worker.
+//!
+//! Each build's service worker caches the build apart from the others (see `assets/sw.js`). When
+//! a new build is deployed, the browser finds its service worker and installs it next to the one
+//! serving the page, where it waits. The page watches it until it is installed, and the app can
+//! then offer to reload, which has the waiting worker take over before the page reloads into the
+//! new build.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use eframe::wasm_bindgen::prelude::Closure;
+use eframe::wasm_bindgen::{JsCast, JsValue};
+use eframe::web_sys;
+use eframe::web_sys::js_sys::Reflect;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{ServiceWorkerContainer, ServiceWorkerRegistration, ServiceWorkerState};
+
+/// Message asking the waiting service worker to take over
+const SKIP_WAITING: &str = "skip-waiting";
+
+/// What to call when a new build becomes available, shared with the listeners
+type OnAvailable = Rc<RefCell<Option<Box<dyn Fn()>>>>;
+
+thread_local! {
+    /// The one watcher, as the listeners are added to the page once
+    static WATCHER: RefCell<Option<Updates>> = const { RefCell::new(None) };
+}
+
+/// Whether a new build is waiting
+#[derive(Clone, Default)]
+pub struct Updates {
+    available: Rc<Cell<bool>>,
+
+    /// Called when a new build becomes available
+    on_available: OnAvailable,
+}
+
+/// The page's service workers, if the browser has them
+pub(crate) fn service_workers() -> Option<ServiceWorkerContainer> {
+    let navigator = web_sys::window()?.navigator();
+    Reflect::has(&navigator, &JsValue::from_str("serviceWorker"))
+        .unwrap_or(false)
+        .then(|| navigator.service_worker())
+}
+
+/// The page's service worker registration, once it has a worker serving the page
+async fn ready(container: &ServiceWorkerContainer) -> Option<ServiceWorkerRegistration> {
+    JsFuture::from(container.ready().ok()?)
+        .await
+        .ok()?
+        .dyn_into()
+        .ok()
+}
+
+/// The service worker of a new build waiting to take over, if any
+async fn waiting(container: &ServiceWorkerContainer) -> Option<web_sys::ServiceWorker> {
+    JsFuture::from(container.get_registration())
+        .await
+        .ok()?
+        .dyn_into::<ServiceWorkerRegistration>()
+        .ok()?
+        .waiting()
+}
+
+fn reload_page() {
+    if let Some(window) = web_sys::window() {
+        if let Err(e) = window.location().reload() {
+            tracing::error!("Failed to reload: {e:?}");
+        }
+    }
+}
+
+impl Updates {
+    /// Watches for new builds, calling `on_available` when one is waiting
+    pub fn watch(on_available: impl Fn() + 'static) -> Self {
+        let updates = WATCHER.with(|watcher| {
+            watcher
+                .borrow_mut()
+                .get_or_insert_with(|| {
+                    let updates = Self::default();
+                    updates.listen();
+                    updates
+                })
+                .clone()
+        });
+        *updates.on_available.borrow_mut() = Some(Box::new(on_available));
+        updates
+    }
+
+    fn listen(&self) {
+        let Some(container) = service_workers() else {
+            return;
+        };
+
+        let updates = self.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let Some(registration) = ready(&container).await else {
+                return;
+            };
+
+            // A new build may have been found, or be waiting, since before the app started
+            if registration.waiting().is_some() {
+                updates.set_available();
+            }
+            if let Some(worker) = registration.installing() {
+                updates.watch_install(worker);
+            }
+
+            let on_update_found = Closure::<dyn Fn()>::new({
+                let registration = registration.clone();
+                move || {
+                    if let Some(worker) = registration.installing() {
+                        updates.watch_install(worker);
+                    }
+                }
+            });
+            registration.set_onupdatefound(Some(on_update_found.as_ref().unchecked_ref()));
+            on_update_found.forget(); // Prevent the closure from being dropped
+        });
+    }
+
+    /// Has the new build available once its installing `worker` is installed, and waits to take
+    /// over
+    fn watch_install(&self, worker: web_sys::ServiceWorker) {
+        let updates = self.clone();
+        let installing = worker.clone();
+        let on_state_change = Closure::<dyn Fn()>::new(move || {
+            if installing.state() == ServiceWorkerState::Installed {
+                updates.set_available();
+            }
+        });
+        worker.set_onstatechange(Some(on_state_change.as_ref().unchecked_ref()));
+        on_state_change.forget(); // Prevent the closure from being dropped
+    }
+
+    fn set_available(&self) {
+        self.available.set(true);
+        if let Some(on_available) = self.on_available.borrow().as_ref() {
+            on_available();
+        }
+    }
+
+    /// Whether a new build is waiting to take over
+    pub fn available(&self) -> bool {
+        self.available.get()
+    }
+
+    /// Has the new build take over, and reloads the page into it
+    pub fn apply(&self) {
+        let Some(container) = service_workers() else {
+            reload_page();
+            return;
+        };
+
+        wasm_bindgen_futures::spawn_local(async move {
+            let Some(worker) = waiting(&container).await else {
+                reload_page();
+                return;
+            };
+
+            // Reloading before the new worker serves the page would load the old build again
+            let on_change = Closure::once_into_js(reload_page);
+            if let Err(e) = container
+                .add_event_listener_with_callback("controllerchange", on_change.unchecked_ref())
+            {
+                tracing::error!("Failed to wait for the new build: {e:?}");
+            }
+            if let Err(e) = worker.post_message(&JsValue::from_str(SKIP_WAITING)) {
+                tracing::error!("Failed to start the new build: {e:?}");
+            }
+        });
+    }
+}
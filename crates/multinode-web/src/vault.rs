@@ -0,0 +1,43 @@
+//! The wallet's encrypted vault, kept in `localStorage` under a key of its own
+//!
+//! The app state is only saved now and then, so a changed password could be lost with it. The
+//! vault is written on its own as soon as it changes instead, and `localStorage` replaces a key's
+//! value whole, so the last vault stays until the new one is stored.
+
+use std::io;
+
+use eframe::web_sys;
+
+const KEY: &str = "peerpiper-multinode-vault";
+
+/// The `localStorage` key of a profile's vault
+#[derive(Debug, Clone)]
+pub struct VaultKey(String);
+
+impl VaultKey {
+    /// The key of the named profile's vault, or the default profile's if `None`
+    pub fn new(profile: Option<&str>) -> Self {
+        Self(match profile {
+            Some(profile) => format!("{KEY}-{profile}"),
+            None => KEY.to_owned(),
+        })
+    }
+
+    fn storage() -> io::Result<web_sys::Storage> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no localStorage"))
+    }
+
+    /// The vault last saved, if there is one
+    pub fn load(&self) -> Option<String> {
+        Self::storage().ok()?.get_item(&self.0).ok().flatten()
+    }
+
+    /// Replaces the saved vault with `vault`
+    pub fn save(&self, vault: &str) -> io::Result<()> {
+        Self::storage()?
+            .set_item(&self.0, vault)
+            .map_err(|e| io::Error::other(format!("{e:?}")))
+    }
+}
@@ -1,136 +1,330 @@
 mod backend_panel;
+mod commands;
 pub mod error;
+mod notifications;
+mod persistence;
 mod platform;
+pub(crate) mod profiles;
+mod settings;
 mod style;
+mod transfer;
+#[cfg(not(target_arch = "wasm32"))]
+mod tray;
+mod views;
+mod wallet;
 
 pub use error::Error;
 
-use backend_panel::BackendPanel;
+use commands::Command;
 use eframe::glow::Context;
+use multinode_i18n::tr;
 pub(crate) use platform::Platform;
 use style::is_mobile;
+use views::View;
 
 const IS_WEB: bool = cfg!(target_arch = "wasm32");
 
+/// What persists between runs, in the layout [`persistence::VERSION`]
 #[derive(Default, serde::Deserialize, serde::Serialize)]
 #[serde(default)]
 pub struct State {
-    backend_panel: BackendPanel,
+    /// Each node's wallet and plugins, and which node is running
+    profiles: profiles::Profiles,
+
+    /// The views of the main window, and where their own windows are
+    views: views::Views,
+
+    /// How the app looks
+    settings: settings::Settings,
 }
 
-/// We derive Deserialize/Serialize so we can persist app state on shutdown.
-#[derive(serde::Deserialize, serde::Serialize)]
-#[serde(default)] // if we add new fields, give them default values when deserializing old state
+/// The app. Only its [`State`] persists, see [`persistence`].
 pub struct TemplateApp {
     /// State
     state: State,
 
-    // Example stuff:
-    label: String,
-
-    #[serde(skip)] // This how you opt-out of serialization of a field
-    value: f32,
-
     // Platform specific fields
-    #[serde(skip)]
     /// Platform  specific handlers for native and web     
     platform: Platform,
-}
 
-impl Default for TemplateApp {
-    fn default() -> Self {
-        Self {
-            platform: Default::default(),
-            label: "/dnsaddr/peerpiper.io/".to_owned(),
-            value: 2.7,
-            state: Default::default(),
-        }
-    }
-}
+    palette: commands::Palette,
+
+    /// Exports, imports and resets the settings
+    transfer: transfer::Transfer,
+
+    /// Saved state which couldn't be restored, until it's backed up
+    unrestored: Option<String>,
 
-/// APP_KEY constant, concat of eframe::APP_KEY and crate name
-const APP_KEY: &str = concat!("eframe-app-", env!("CARGO_PKG_NAME"));
+    /// Notices node events to notify the user of
+    watcher: notifications::Watcher,
+
+    /// Plugin files the OS asked the app to open
+    #[cfg(not(target_arch = "wasm32"))]
+    files: crate::instance::Files,
+
+    /// The tray icon, while the node runs in the background
+    #[cfg(not(target_arch = "wasm32"))]
+    background: tray::Background,
+}
 
 impl TemplateApp {
-    /// Called once before the first frame.
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+    /// Called once before the first frame. Starts the named profile's node, adding the profile
+    /// if need be, or the node of the profile active when the app was last closed.
+    pub fn new(cc: &eframe::CreationContext<'_>, profile: Option<&str>) -> Self {
         // crate::style::fonts(&cc.egui_ctx);
 
-        eprintln!("app_key: {}", APP_KEY);
+        tracing::debug!("app_key: {}", persistence::APP_KEY);
+
+        let mut state = State::default();
+        let mut transfer = transfer::Transfer::default();
+        let mut unrestored = None;
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            if let Some(app) = eframe::get_value(storage, APP_KEY) {
+        match cc.storage.map(persistence::load) {
+            Some(Ok(Some(saved))) => {
                 tracing::info!("💾 💾 💾 Loaded app state from disk");
-                return app;
+                state = saved;
+            }
+            Some(Err((saved, e))) => {
+                tracing::error!("Failed to restore app state, starting over: {e}");
+                transfer.unrestored(&e);
+                unrestored = Some(saved);
             }
+            _ => tracing::info!("🆕 🆕 🆕 No app state found on disk"),
         }
 
-        tracing::info!("🆕 🆕 🆕 No app state found on disk");
+        if let Some(profile) = profile {
+            if !state.profiles.select(profile) {
+                tracing::error!("Profile names may only use letters, digits, - and _: {profile}");
+            }
+        }
+
+        let platform = Platform::new(state.profiles.data());
+        state
+            .profiles
+            .current()
+            .backend_panel
+            .sync_platform(&platform);
 
         Self {
-            platform: Default::default(),
-            label: "/dnsaddr/peerpiper.io/".to_owned(),
-            value: 2.7,
-            state: Default::default(),
+            platform,
+            state,
+            palette: Default::default(),
+            transfer,
+            unrestored,
+            watcher: Default::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            files: Default::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            background: Default::default(),
+        }
+    }
+
+    /// Loads the plugin files the OS asks the app to open, see [`crate::instance`]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn opening(mut self, files: crate::instance::Files) -> Self {
+        files.listen_for(self.state.profiles.data());
+        self.files = files;
+        self
+    }
+
+    /// Loads the plugin files the OS asked to open since the last frame, bringing the window
+    /// forward if the app was launched again
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_files(&mut self, ctx: &egui::Context) {
+        let Some(paths) = self.files.take(ctx) else {
+            return;
+        };
+        ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+        let backend_panel = &self.state.profiles.current().backend_panel;
+        for path in paths {
+            backend_panel.load_file(&path, &self.platform);
         }
     }
 
     /// Contents of the Top Bar
     fn bar_contents(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, _frame: &mut eframe::Frame) {
-        egui::widgets::global_theme_preference_switch(ui);
-
-        if is_mobile(ui.ctx()) {
-            ui.menu_button("💻 Backend", |ui| {
-                ui.set_style(ui.ctx().style()); // ignore the "menu" style set by `menu_button`.
-            });
-        } else {
-            ui.toggle_value(&mut self.state.backend_panel.open, "💻 Menu");
+        self.state.settings.theme_switch(ui);
+        if let Some(profile) = self.state.profiles.switcher(ui) {
+            self.switch_profile(&profile);
         }
 
         egui::menu::bar(ui, |ui| {
             if !IS_WEB {
-                ui.menu_button("File", |ui| {
-                    if ui.button("Quit").clicked() {
-                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                ui.menu_button(tr!("menu-file"), |ui| {
+                    if ui.button(tr!("menu-quit")).clicked() {
+                        self.quit(ctx);
                     }
                 });
-                ui.add_space(16.0);
             }
+            ui.menu_button(tr!("menu-view"), |ui| {
+                let palette = command_button(ctx, tr!("command-palette"), &Command::Palette);
+                if ui.add(palette).clicked() {
+                    self.palette.toggle();
+                    ui.close_menu();
+                }
+                if ui.button(tr!("menu-reset-layout")).clicked() {
+                    self.state.views.reset_layout();
+                    ui.close_menu();
+                }
+            });
+            ui.menu_button(tr!("menu-settings"), |ui| {
+                let preferences = command_button(ctx, tr!("menu-preferences"), &Command::Settings);
+                if ui.add(preferences).clicked() {
+                    self.state.settings.open = true;
+                    ui.close_menu();
+                }
+                ui.separator();
+                if ui.button(tr!("menu-export-settings")).clicked() {
+                    self.transfer.export(&self.state);
+                    ui.close_menu();
+                }
+                if ui.button(tr!("menu-import-settings")).clicked() {
+                    self.transfer.import(ctx);
+                    ui.close_menu();
+                }
+                ui.separator();
+                if ui.button(tr!("menu-reset-settings")).clicked() {
+                    self.transfer.reset();
+                    ui.close_menu();
+                }
+            });
+            ui.add_space(16.0);
         });
     }
 
-    fn backend_panel(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
-        // The backend-panel can be toggled on/off.
-        // We show a little animation when the user switches it.
-        let is_open =
-            self.state.backend_panel.open || ctx.memory(|mem| mem.everything_is_visible());
-
-        egui::SidePanel::left("backend_panel")
-            .resizable(true)
-            .show_animated(ctx, is_open, |ui| {
-                ui.add_space(4.0);
-                ui.vertical_centered(|ui| {
-                    ui.heading("💻 Backend");
-                });
+    /// Switches to other settings. Wallets aren't settings, so they're kept.
+    fn replace_state(&mut self, mut state: State) {
+        let restart = state.profiles.active() != self.state.profiles.active();
+        if restart {
+            let current = self.state.profiles.current();
+            current.backend_panel.lock_wallet(&self.platform);
+        }
+        state.profiles.take_wallets(&mut self.state.profiles);
+        self.state = state;
+        if restart {
+            self.restart_platform();
+        } else {
+            self.state
+                .profiles
+                .current()
+                .backend_panel
+                .sync_platform(&self.platform);
+        }
+    }
 
-                ui.separator();
-                self.state.backend_panel.ui(ui, frame, &self.platform);
-            });
+    /// Locks the active profile's wallet, and restarts the platform with the named profile
+    fn switch_profile(&mut self, name: &str) {
+        if !profiles::valid_name(name) {
+            return;
+        }
+        let current = self.state.profiles.current();
+        current.backend_panel.lock_wallet(&self.platform);
+        self.state.profiles.select(name);
+        self.restart_platform();
+    }
+
+    /// Replaces the platform with one running the active profile's node
+    fn restart_platform(&mut self) {
+        tracing::info!(
+            "Starting the node of profile {}",
+            self.state.profiles.active()
+        );
+        self.platform.close();
+        self.platform = Platform::new(self.state.profiles.data());
+        let backend_panel = &mut self.state.profiles.current().backend_panel;
+        backend_panel.resync();
+        backend_panel.sync_platform(&self.platform);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.files.listen_for(self.state.profiles.data());
+    }
+
+    /// Closes the app, stopping the node even if it runs in the background
+    fn quit(&mut self, ctx: &egui::Context) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.background.quit(ctx);
+        #[cfg(target_arch = "wasm32")]
+        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+    }
+
+    /// Carries out a command from a shortcut or the palette
+    fn run(&mut self, ctx: &egui::Context, command: Command) {
+        let State {
+            profiles,
+            views,
+            settings,
+        } = &mut self.state;
+        let backend_panel = &mut profiles.current().backend_panel;
+
+        match command {
+            Command::Palette => self.palette.toggle(),
+            Command::ShowView(view) => views.show_view(ctx, view),
+            Command::Settings => settings.open = !settings.open,
+            Command::OpenPluginFile => {
+                views.show_view(ctx, View::Plugins);
+                backend_panel.open_plugin_file(&self.platform);
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Command::FocusLogSearch => {
+                views.show_view(ctx, View::Log);
+                ctx.memory_mut(|m| m.request_focus(egui::Id::new(commands::LOG_SEARCH)));
+            }
+            Command::LockWallet => backend_panel.lock_wallet(&self.platform),
+            Command::ResetLayout => views.reset_layout(),
+            Command::ExportSettings => self.transfer.export(&self.state),
+            Command::ImportSettings => self.transfer.import(ctx),
+            Command::ResetSettings => self.transfer.reset(),
+            Command::Quit => self.quit(ctx),
+            Command::OpenPanel { name, .. } => {
+                self.platform.set_panel_open(&name, true);
+                views.show_view(ctx, View::Plugin(name));
+            }
+            Command::SelectPlugin(name) => {
+                self.platform.select_plugin(&name);
+                views.show_view(ctx, View::Node);
+            }
+            Command::RunAction { plugin, id, .. } => self.platform.run_action(&plugin, &id),
+        }
     }
 }
 
+/// How the node is doing, for the tray
+#[cfg(not(target_arch = "wasm32"))]
+fn node_status(platform: &Platform) -> String {
+    let running = platform
+        .plugins()
+        .iter()
+        .filter(|(_, status)| *status == platform::PluginStatus::Running)
+        .count();
+    tr!("tray-status", plugins = running)
+}
+
+/// A menu button for `command`, showing its shortcut
+fn command_button<'a>(ctx: &egui::Context, text: String, command: &Command) -> egui::Button<'a> {
+    egui::Button::new(text).shortcut_text(
+        command
+            .shortcut()
+            .map(|shortcut| ctx.format_shortcut(&shortcut))
+            .unwrap_or_default(),
+    )
+}
+
 impl eframe::App for TemplateApp {
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        tracing::info!("💾 💾 💾 Saving app state to disk, key: {}", APP_KEY);
-        eframe::set_value(storage, APP_KEY, self);
+        tracing::info!(
+            "💾 💾 💾 Saving app state to disk, key: {}",
+            persistence::APP_KEY
+        );
+        if let Some(saved) = self.unrestored.take() {
+            persistence::back_up(storage, saved);
+        }
+        persistence::save(storage, &self.state);
     }
 
-    /// Kill server on exit
+    /// Stops the node on exit
     fn on_exit(&mut self, _gl: Option<&Context>) {
-        // drop the platform to Kill the server process
         self.platform.close();
     }
 
@@ -141,8 +335,37 @@ impl eframe::App for TemplateApp {
             self.platform.set_egui_ctx(ctx.clone());
         }
 
-        // set the style
-        style::style(ctx);
+        let mobile = is_mobile(ctx);
+
+        // set the style, if the settings changed since it was last set
+        self.state.settings.apply(ctx, mobile);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.open_files(ctx);
+            let status = node_status(&self.platform);
+            self.background
+                .update(ctx, self.state.settings.background, status);
+        }
+
+        let auto_locked = self
+            .state
+            .profiles
+            .current()
+            .backend_panel
+            .auto_lock(ctx, &self.platform);
+        let notifications = &self.state.settings.notifications;
+        self.watcher
+            .update(ctx, &self.platform, notifications, auto_locked);
+
+        // Shortcuts, and the command palette
+        let mut commands = Command::builtin();
+        commands.extend(self.platform.plugin_commands());
+        let pressed = commands::pressed(ctx, &commands);
+        let picked = self.palette.show(ctx, &commands);
+        for command in pressed.into_iter().chain(picked) {
+            self.run(ctx, command);
+        }
 
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             ui.horizontal_wrapped(|ui| {
@@ -151,40 +374,111 @@ impl eframe::App for TemplateApp {
             });
         });
 
-        if !is_mobile(ctx) {
-            self.backend_panel(ctx, frame);
+        if self.platform.update_available() {
+            egui::TopBottomPanel::top("update_banner").show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr!("update-available"));
+                    if ui.button(tr!("update-reload")).clicked() {
+                        self.platform.apply_update();
+                    }
+                });
+            });
         }
 
-        egui::TopBottomPanel::bottom("footer").show(ctx, |ui| {
-            ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
-                powered_by_egui_and_eframe(ui);
-                ui.add(egui::github_link_file!(
-                    "https://github.com/PeerPiper/egui-multinode/blob/main/",
-                    format!("🖹 Rust Source Code")
-                ));
-                egui::warn_if_debug_build(ui);
+        let panels = self.platform.panels();
+
+        if mobile {
+            // Narrow screens pick one view at a time from tabs at the bottom, in place of the footer
+            egui::TopBottomPanel::bottom("mobile_tabs")
+                .show(ctx, |ui| self.state.views.mobile_tabs_ui(ui, &panels));
+        } else {
+            egui::TopBottomPanel::bottom("footer").show(ctx, |ui| {
+                ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
+                    powered_by_egui_and_eframe(ui);
+                    ui.add(egui::github_link_file!(
+                        "https://github.com/PeerPiper/egui-multinode/blob/main/",
+                        "🖹 Rust Source Code"
+                    ));
+                    egui::warn_if_debug_build(ui);
+                });
             });
-        });
+        }
 
         egui::CentralPanel::default().show(ctx, |ui| {
             // The central panel the region left after adding TopPanel's and SidePanel's
-            ui.vertical(|ui| {
-                self.platform.show(ctx, ui);
-            });
+            let State {
+                profiles, views, ..
+            } = &mut self.state;
+            let backend_panel = &mut profiles.current().backend_panel;
+            let platform = self.platform.clone();
+
+            let content = |view: &View, ui: &mut egui::Ui| match view {
+                View::Node => platform.show(ctx, ui),
+                View::Log => platform.log_ui(ui),
+                View::Plugins => {
+                    egui::ScrollArea::vertical()
+                        .show(ui, |ui| backend_panel.plugins_ui(ui, &platform));
+                }
+                View::Peers => platform.peers_ui(ui),
+                View::Wallet => {
+                    egui::ScrollArea::vertical().show(ui, |ui| backend_panel.ui(ui, &platform));
+                }
+                View::Plugin(name) => {
+                    egui::ScrollArea::vertical().show(ui, |ui| platform.panel_ui(name, ui));
+                }
+            };
+
+            if mobile {
+                views.show_mobile(ui, content);
+                return;
+            }
+            let closed = views.show(ctx, ui, &panels, content);
+
+            // Closing a plugin's tab closes its panel, until it is opened again from the Plugins view
+            for view in closed {
+                if let View::Plugin(name) = view {
+                    platform.set_panel_open(&name, false);
+                }
+            }
         });
+
+        // Requests to sign show even while the Wallet view is hidden
+        self.state
+            .profiles
+            .current()
+            .backend_panel
+            .show_windows(ctx, &self.platform);
+
+        self.state.settings.show(ctx);
+
+        match self.transfer.show(ctx) {
+            Some(transfer::Action::Import(state)) => self.replace_state(*state),
+            Some(transfer::Action::Reset) => {
+                // Also puts the platform's trust settings and plugin limits back to the defaults
+                self.replace_state(State::default());
+                // Cleared straight away, so the old settings don't come back if the app is killed.
+                // The wallets are kept, so the defaults are saved with them.
+                if let Some(storage) = frame.storage_mut() {
+                    persistence::clear(storage);
+                    persistence::save(storage, &self.state);
+                    storage.flush();
+                }
+            }
+            None => {}
+        }
     }
 }
 
 fn powered_by_egui_and_eframe(ui: &mut egui::Ui) {
     ui.horizontal(|ui| {
         ui.spacing_mut().item_spacing.x = 0.0;
-        ui.label("Powered by ");
+        ui.label(tr!("powered-by"));
         ui.hyperlink_to("egui", "https://github.com/emilk/egui");
-        ui.label(" and ");
+        ui.label(tr!("powered-by-and"));
         ui.hyperlink_to(
             "eframe",
             "https://github.com/emilk/egui/tree/master/crates/eframe",
         );
-        ui.label(".");
+        ui.label(tr!("powered-by-end"));
     });
 }
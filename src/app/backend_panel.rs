@@ -1,64 +1,143 @@
 //! Backend panel module
 
 use super::platform::Platform;
+use multinode_i18n::tr;
 
+mod backup;
 // mod login;
 mod password;
 // mod unlock;
-mod file_dialog;
+pub(crate) mod file_dialog;
+mod identities;
+#[cfg(not(target_arch = "wasm32"))]
+mod plugins;
+mod remote;
+mod storage;
+#[cfg(not(target_arch = "wasm32"))]
+mod topics;
+pub(crate) mod trust;
+mod wallet;
 
 /// Backend panel state
-#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Default, serde::Deserialize, serde::Serialize)]
 #[serde(default)]
 pub struct BackendPanel {
-    /// Whether the panel is open
-    pub open: bool,
-
     // /// The unlocking credential
     // creds: unlock::Credentials,
-    password: String,
+    /// The wallet, and which plugins may sign with it
+    wallet: wallet::WalletPanel,
 
     file_dialog: file_dialog::FileDialog,
+
+    /// Loads plugins by URL or CID
+    remote: remote::RemotePlugin,
+
+    /// Per-plugin settings, such as resource limits
+    #[cfg(not(target_arch = "wasm32"))]
+    plugins: plugins::Plugins,
+
+    /// Trusted plugin publishers and what to do with everything else
+    trust: trust::Trust,
+
+    #[serde(skip)]
+    storage: storage::StorageInspector,
 }
 
-impl Default for BackendPanel {
-    fn default() -> Self {
-        Self {
-            open: false,
-            // creds: unlock::Credentials::default(),
-            password: "default password".to_string(),
-            file_dialog: file_dialog::FileDialog::default(),
+impl BackendPanel {
+    pub fn ui(&mut self, ui: &mut egui::Ui, platform: &Platform) {
+        ui.vertical(|ui| self.wallet.ui(ui, platform));
+    }
+
+    /// Loads plugin files, which the platform checks against the trust settings
+    fn on_load(&self, platform: &Platform) -> impl Fn(String, Vec<u8>, Option<Vec<u8>>) + 'static {
+        let platform = platform.clone();
+        move |name: String, bytes: Vec<u8>, signature: Option<Vec<u8>>| {
+            platform.load_plugin(name, bytes, signature);
         }
     }
-}
 
-impl BackendPanel {
-    pub fn ui(&mut self, ui: &mut egui::Ui, _frame: &mut eframe::Frame, platform: &Platform) {
-        ui.horizontal(|ui| {
-            ui.vertical(|ui| {
-                ui.label("Unlock Wallet");
-                // Unlock Widget
-                ui.add(password::password(&mut self.password));
-
-                // unlock button
-                if ui.button("Unlock").clicked() {
-                    // unlock the wallet
-                }
-            });
-        });
-        ui.separator();
+    /// Loads the plugin file at `path`, if the trust settings allow it
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_file(&self, path: &std::path::Path, platform: &Platform) {
+        match file_dialog::read_plugin(path) {
+            Ok((name, bytes, signature)) => self.on_load(platform)(name, bytes, signature),
+            Err(e) => tracing::error!("Failed to read plugin {}: {e}", path.display()),
+        }
+    }
 
-        ui.label("Node");
-        let platform_clone = platform.clone();
-        let on_load_callback = move |name, bytes| {
-            platform_clone.load_plugin(name, bytes);
-        };
+    /// Asks the user for a plugin file to load
+    pub fn open_plugin_file(&mut self, platform: &Platform) {
+        let on_load_callback = self.on_load(platform);
+        if let Err(e) = self.file_dialog.pick(on_load_callback) {
+            tracing::error!("Failed to open file dialog: {:?}", e);
+        }
+    }
+
+    /// Swaps wallets with `other`, so this panel has the one in use
+    pub fn take_wallet(&mut self, other: &mut BackendPanel) {
+        std::mem::swap(&mut self.wallet, &mut other.wallet);
+    }
+
+    /// Hands the wallet to the platform again on the next frame, as it was restarted
+    pub fn resync(&mut self) {
+        self.wallet.resync();
+    }
+
+    /// Hands the trust settings and plugin limits to the platform, replacing those it had.
+    /// Done whenever the state or the platform is replaced, before any plugin is loaded.
+    pub fn sync_platform(&self, platform: &Platform) {
+        self.sync_trust(platform);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.plugins.sync(platform);
+    }
+
+    fn sync_trust(&self, platform: &Platform) {
+        let checker = self.trust.checker();
+        platform.set_trust(move |name, wasm, signature| checker.allow(name, wasm, signature));
+    }
+
+    /// Locks the wallet once the app was left idle for long enough. Returns whether it locked
+    /// just now.
+    pub fn auto_lock(&mut self, ctx: &egui::Context, platform: &Platform) -> bool {
+        self.wallet.auto_lock(ctx, platform)
+    }
+
+    /// Locks the wallet, if unlocked
+    pub fn lock_wallet(&mut self, platform: &Platform) {
+        self.wallet.lock(platform);
+    }
+
+    /// Loading plugins, and everything about the loaded ones
+    pub fn plugins_ui(&mut self, ui: &mut egui::Ui, platform: &Platform) {
+        let on_load_callback = self.on_load(platform);
         if let Err(e) = self.file_dialog.file_dialog(ui, on_load_callback) {
             tracing::error!("Failed to open file dialog: {:?}", e);
         }
-        ui.separator();
 
-        ui.label("Peers");
-        ui.separator();
+        let platform_clone = platform.clone();
+        self.remote.ui(ui, move |name, bytes| {
+            platform_clone.load_plugin(name, bytes, None);
+        });
+
+        let changed = ui
+            .collapsing(tr!("plugins-signatures"), |ui| self.trust.ui(ui))
+            .body_returned
+            .unwrap_or_default();
+        if changed {
+            self.sync_trust(platform);
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        self.plugins.ui(ui, platform);
+
+        ui.collapsing(tr!("plugins-storage"), |ui| self.storage.ui(ui, platform));
+
+        #[cfg(not(target_arch = "wasm32"))]
+        ui.collapsing(tr!("plugins-messages"), |ui| topics::ui(ui, platform));
+    }
+
+    /// Shows the windows the panel opens over the app, such as requests to sign
+    pub fn show_windows(&mut self, ctx: &egui::Context, platform: &Platform) {
+        self.wallet.show_sign_request(ctx, platform);
     }
 }
@@ -0,0 +1,163 @@
+//! Seed phrase backup
+//!
+//! Shows the unlocked seed as its mnemonic once the user asks to reveal it, then has them pick
+//! the words in order from a shuffled list to check they wrote it down.
+
+use multinode_i18n::tr;
+use zeroize::Zeroizing;
+
+use crate::app::wallet::Seed;
+
+/// Where the user is in the backup
+#[derive(Default)]
+enum Step {
+    /// The words are hidden until the user asks to see them
+    #[default]
+    Hidden,
+
+    /// The words are shown to be written down
+    Revealed,
+
+    /// The user picks the words in order
+    Verify {
+        /// Indices of the words, in the order they are offered
+        shuffled: Vec<usize>,
+
+        /// Positions in `shuffled` picked so far
+        picked: Vec<usize>,
+
+        /// Whether the last pick was wrong
+        wrong: bool,
+    },
+}
+
+/// State of the seed phrase backup
+#[derive(Default)]
+pub struct Backup {
+    step: Step,
+
+    /// The mnemonic, while revealed or being verified
+    mnemonic: Zeroizing<String>,
+}
+
+/// The numbers `0..len` in random order
+fn shuffle(len: usize) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..len).collect();
+    for i in (1..len).rev() {
+        let mut random = [0u8; 4];
+        if getrandom::getrandom(&mut random).is_err() {
+            break;
+        }
+        let j = u32::from_le_bytes(random) as usize % (i + 1);
+        order.swap(i, j);
+    }
+    order
+}
+
+impl Backup {
+    /// Forgets the mnemonic, for when the wallet locks
+    pub fn hide(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Guides the user through backing up `seed`. Returns true once they verified it.
+    pub fn ui(&mut self, ui: &mut egui::Ui, seed: &Seed) -> bool {
+        let mut verified = false;
+
+        match &mut self.step {
+            Step::Hidden => {
+                ui.label(tr!("backup-explain"));
+                if ui.button(tr!("backup-reveal")).clicked() {
+                    self.mnemonic = seed.mnemonic();
+                    self.step = Step::Revealed;
+                }
+            }
+            Step::Revealed => {
+                let words: Vec<&str> = self.mnemonic.split(' ').collect();
+                egui::Grid::new("seed_phrase")
+                    .num_columns(4)
+                    .show(ui, |ui| {
+                        for (i, word) in words.iter().enumerate() {
+                            ui.monospace(format!("{:>2}. {word}", i + 1));
+                            if i % 4 == 3 {
+                                ui.end_row();
+                            }
+                        }
+                    });
+
+                let len = words.len();
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("backup-written-down")).clicked() {
+                        self.step = Step::Verify {
+                            shuffled: shuffle(len),
+                            picked: Vec::new(),
+                            wrong: false,
+                        };
+                    }
+                    if ui.button(tr!("backup-hide")).clicked() {
+                        self.hide();
+                    }
+                });
+            }
+            Step::Verify {
+                shuffled,
+                picked,
+                wrong,
+            } => {
+                let words: Vec<&str> = self.mnemonic.split(' ').collect();
+                ui.label(tr!("backup-pick-words"));
+                ui.monospace(
+                    picked
+                        .iter()
+                        .map(|&i| words[shuffled[i]])
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                );
+
+                ui.horizontal_wrapped(|ui| {
+                    for (i, &word) in shuffled.iter().enumerate() {
+                        let word = words[word];
+                        if ui
+                            .add_enabled(!picked.contains(&i), egui::Button::new(word))
+                            .clicked()
+                        {
+                            // Repeated words are interchangeable, so compare words, not indices
+                            *wrong = word != words[picked.len()];
+                            if *wrong {
+                                picked.clear();
+                            } else {
+                                picked.push(i);
+                            }
+                        }
+                    }
+                });
+
+                if *wrong {
+                    ui.colored_label(ui.visuals().error_fg_color, tr!("backup-wrong-word"));
+                }
+                verified = picked.len() == words.len();
+
+                if ui.button(tr!("backup-show-again")).clicked() {
+                    self.step = Step::Revealed;
+                }
+            }
+        }
+
+        if verified {
+            self.hide();
+        }
+        verified
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shuffles_every_index_once() {
+        let mut order = shuffle(24);
+        order.sort_unstable();
+        assert_eq!(order, (0..24).collect::<Vec<_>>());
+    }
+}
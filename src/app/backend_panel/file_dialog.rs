@@ -1,8 +1,8 @@
 #[cfg(not(target_arch = "wasm32"))]
 mod native;
 #[cfg(target_arch = "wasm32")]
-pub use multinode_web::file_dialog::FileDialog;
+pub use multinode_web::file_dialog::{open_file, save_file, FileDialog};
 
 // use as file_dialog
 #[cfg(not(target_arch = "wasm32"))]
-pub use native::FileDialog;
+pub use native::{open_file, pick_file, read_plugin, save_file, FileDialog};
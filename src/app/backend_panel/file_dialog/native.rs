@@ -1,3 +1,7 @@
+use std::path::{Path, PathBuf};
+
+use multinode_i18n::tr;
+
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)]
 #[derive(Default)]
@@ -10,34 +14,18 @@ impl FileDialog {
     pub fn file_dialog(
         &mut self,
         ui: &mut egui::Ui,
-        on_load_callback: impl Fn(String, Vec<u8>) + 'static,
+        on_load_callback: impl Fn(String, Vec<u8>, Option<Vec<u8>>) + 'static,
         //    platform: &mut crate::app::Platform
     ) -> Result<(), crate::Error> {
-        ui.label("Drag-and-drop files onto the window!");
-
-        if ui.button("Open file…").clicked() {
-            if let Some(path) = rfd::FileDialog::new().pick_file() {
-                self.picked_path = Some(path.file_stem().unwrap().to_string_lossy().to_string());
-                // load the file bytes
-                let bytes = std::fs::read(&path)
-                    .unwrap_or_else(|err| panic!("Failed to read file: {}", err));
-                self.dropped_files.push(egui::DroppedFile {
-                    bytes: None,
-                    path: None, // Some(path.display().to_string().into()),
-                    last_modified: None,
-                    name: self.picked_path.clone().unwrap(),
-                    mime: "".to_owned(),
-                });
-
-                // call platform load plugin
-                // platform.load_plugin(self.picked_path.clone().unwrap(), bytes);
-                on_load_callback(self.picked_path.clone().unwrap(), bytes);
-            }
+        ui.label(tr!("plugins-drop-files"));
+
+        if ui.button(tr!("plugins-open-file")).clicked() {
+            self.pick(on_load_callback)?;
         }
 
         if let Some(picked_path) = &self.picked_path {
             ui.vertical(|ui| {
-                ui.label("Picked file:");
+                ui.label(tr!("plugins-picked-file"));
                 ui.monospace(picked_path);
             });
         }
@@ -45,7 +33,7 @@ impl FileDialog {
         // Show dropped files (if any):
         if !self.dropped_files.is_empty() {
             ui.group(|ui| {
-                ui.label("Plugins:");
+                ui.label(tr!("plugins-dropped"));
 
                 for file in &self.dropped_files {
                     let mut info = if let Some(path) = &file.path {
@@ -81,4 +69,74 @@ impl FileDialog {
 
         Ok(())
     }
+
+    /// Asks the user for a plugin file, and hands it to `on_load_callback` with its signature
+    pub fn pick(
+        &mut self,
+        on_load_callback: impl Fn(String, Vec<u8>, Option<Vec<u8>>) + 'static,
+    ) -> Result<(), crate::Error> {
+        if let Some(path) = rfd::FileDialog::new().pick_file() {
+            let (name, bytes, signature) = read_plugin(&path).map_err(crate::Error::Io)?;
+            self.picked_path = Some(name.clone());
+            self.dropped_files.push(egui::DroppedFile {
+                bytes: None,
+                path: None, // Some(path.display().to_string().into()),
+                last_modified: None,
+                name: name.clone(),
+                mime: "".to_owned(),
+            });
+
+            // call platform load plugin
+            // platform.load_plugin(self.picked_path.clone().unwrap(), bytes);
+            on_load_callback(name, bytes, signature);
+        }
+        Ok(())
+    }
+}
+
+/// Reads the plugin at `path`, named after the file, with its signature if it has one
+pub fn read_plugin(path: &Path) -> std::io::Result<(String, Vec<u8>, Option<Vec<u8>>)> {
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let bytes = std::fs::read(path)?;
+
+    // a detached signature sits next to the plugin as `<file>.wasm.sig`
+    let mut sidecar = path.as_os_str().to_owned();
+    sidecar.push(".sig");
+    let signature = std::fs::read(sidecar).ok();
+
+    Ok((name, bytes, signature))
+}
+
+/// Asks the user where to save `bytes`, suggesting `file_name`
+pub fn save_file(file_name: &str, bytes: &[u8]) -> Result<(), crate::Error> {
+    if let Some(path) = rfd::FileDialog::new().set_file_name(file_name).save_file() {
+        std::fs::write(path, bytes).map_err(crate::Error::Io)?;
+    }
+    Ok(())
+}
+
+/// Asks the user for a file with one of `extensions`, returning its path
+pub fn pick_file(extensions: &[&str]) -> Option<PathBuf> {
+    rfd::FileDialog::new()
+        .add_filter("", extensions)
+        .pick_file()
+}
+
+/// Asks the user for a file with one of `extensions`, and hands its name and bytes to `on_open`
+pub fn open_file(
+    extensions: &[&str],
+    on_open: impl FnOnce(String, Vec<u8>) + 'static,
+) -> Result<(), crate::Error> {
+    if let Some(path) = pick_file(extensions) {
+        let bytes = std::fs::read(&path).map_err(crate::Error::Io)?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        on_open(name, bytes);
+    }
+    Ok(())
 }
@@ -0,0 +1,217 @@
+//! Identity manager
+//!
+//! Each identity is a labelled derivation path, so one seed backs them all and nothing secret is
+//! persisted. None of them is the node's libp2p identity: the node makes its own, as it can't
+//! be handed a key yet. Paths under
+//! [`PLUGIN_PURPOSE`](crate::app::wallet::PLUGIN_PURPOSE) are those of plugin keys, so identities
+//! can't use them.
+
+use std::collections::BTreeMap;
+
+use multinode_i18n::tr;
+
+use crate::app::wallet::{format_path, is_plugin_path, parse_path, Seed, WalletError};
+
+/// A labelled key derived from the wallet seed
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+struct Identity {
+    label: String,
+    path: Vec<u32>,
+}
+
+/// What's shown of an identity's key
+struct Derived {
+    /// Hex of the ed25519 public key
+    key: String,
+
+    peer_id: String,
+}
+
+impl Derived {
+    fn new(seed: &Seed, path: &[u32]) -> Self {
+        let public = seed.keypair(path).public();
+        let key = public
+            .clone()
+            .try_into_ed25519()
+            .map(|key| hex::encode(key.to_bytes()))
+            .unwrap_or_default();
+        Self {
+            key,
+            peer_id: public.to_peer_id().to_string(),
+        }
+    }
+}
+
+/// Parses the path of a new identity, which can't be a plugin key's
+fn identity_path(path: &str) -> Result<Vec<u32>, WalletError> {
+    let parsed = parse_path(path)?;
+    if is_plugin_path(&parsed) {
+        return Err(WalletError::ReservedPath(path.trim().to_owned()));
+    }
+    Ok(parsed)
+}
+
+/// The identities derived from the wallet seed
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Identities {
+    list: Vec<Identity>,
+
+    /// Label of the identity being added
+    #[serde(skip)]
+    new_label: String,
+
+    /// Path of the identity being added, or empty for the next unused one
+    #[serde(skip)]
+    new_path: String,
+
+    /// The keys shown, by path, derived once each while the wallet is unlocked
+    #[serde(skip)]
+    derived: BTreeMap<Vec<u32>, Derived>,
+}
+
+impl Default for Identities {
+    fn default() -> Self {
+        Self {
+            list: vec![Identity {
+                label: tr!("identities-main"),
+                path: vec![0, 0],
+            }],
+            new_label: String::new(),
+            new_path: String::new(),
+            derived: BTreeMap::new(),
+        }
+    }
+}
+
+/// A monospace value with a button to copy it
+fn copyable(ui: &mut egui::Ui, shown: &str, value: &str) {
+    ui.horizontal(|ui| {
+        ui.monospace(shown).on_hover_text(value);
+        if ui
+            .small_button("📋")
+            .on_hover_text(tr!("identities-copy"))
+            .clicked()
+        {
+            ui.output_mut(|o| o.copied_text = value.to_owned());
+        }
+    });
+}
+
+/// The start and end of a long value
+fn abbreviate(value: &str) -> String {
+    match (value.get(..8), value.get(value.len().saturating_sub(6)..)) {
+        (Some(start), Some(end)) if value.len() > 16 => format!("{start}…{end}"),
+        _ => value.to_owned(),
+    }
+}
+
+impl Identities {
+    /// Forgets the keys derived from the seed, as the wallet locked
+    pub fn forget(&mut self) {
+        self.derived.clear();
+    }
+
+    /// The next path nobody uses yet, `m/0'/n'`
+    fn next_path(&self) -> Vec<u32> {
+        let next = (0..)
+            .find(|n| !self.list.iter().any(|identity| identity.path == [0, *n]))
+            .unwrap_or_default();
+        vec![0, next]
+    }
+
+    /// Shows the identities while the wallet is unlocked with `seed`
+    pub fn ui(&mut self, ui: &mut egui::Ui, seed: &Seed) {
+        let mut removed = None;
+
+        egui::Grid::new("identities")
+            .num_columns(4)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong(tr!("identities-label"));
+                ui.strong(tr!("identities-public-key"));
+                ui.strong(tr!("identities-peer-id"));
+                ui.end_row();
+
+                for (i, identity) in self.list.iter_mut().enumerate() {
+                    ui.add(egui::TextEdit::singleline(&mut identity.label).desired_width(80.0))
+                        .on_hover_text(format_path(&identity.path));
+
+                    let derived = self
+                        .derived
+                        .entry(identity.path.clone())
+                        .or_insert_with(|| Derived::new(seed, &identity.path));
+                    copyable(ui, &abbreviate(&derived.key), &derived.key);
+                    copyable(ui, &abbreviate(&derived.peer_id), &derived.peer_id);
+
+                    if ui
+                        .button("🗑")
+                        .on_hover_text(tr!("identities-remove"))
+                        .clicked()
+                    {
+                        removed = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(i) = removed {
+            self.list.remove(i);
+        }
+
+        let next_path = self.next_path();
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.new_label)
+                    .hint_text(tr!("identities-label"))
+                    .desired_width(80.0),
+            );
+            ui.add(
+                egui::TextEdit::singleline(&mut self.new_path)
+                    .hint_text(format_path(&next_path))
+                    .desired_width(100.0),
+            );
+
+            let path = match self.new_path.trim() {
+                "" => Ok(next_path),
+                path => identity_path(path),
+            };
+            if let Err(e) = &path {
+                ui.colored_label(ui.visuals().error_fg_color, "⚠")
+                    .on_hover_text(e.to_string());
+            }
+
+            if ui
+                .add_enabled(
+                    !self.new_label.is_empty() && path.is_ok(),
+                    egui::Button::new("➕"),
+                )
+                .on_hover_text(tr!("identities-derive"))
+                .clicked()
+            {
+                if let Ok(path) = path {
+                    self.list.push(Identity {
+                        label: std::mem::take(&mut self.new_label),
+                        path,
+                    });
+                    self.new_path.clear();
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::wallet::PLUGIN_PURPOSE;
+
+    #[test]
+    fn plugin_paths_are_reserved() {
+        assert_eq!(identity_path("m/0'/3'"), Ok(vec![0, 3]));
+        assert_eq!(
+            identity_path(&format!("m/{PLUGIN_PURPOSE}'/7'")),
+            Err(WalletError::ReservedPath(format!("m/{PLUGIN_PURPOSE}'/7'")))
+        );
+    }
+}
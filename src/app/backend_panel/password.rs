@@ -1,53 +1,298 @@
 //! Source code example about creating a widget which uses `egui::Memory` to store UI state.
 //!
 //! This is meant to be read as a tutorial, hence the plethora of comments.
+//!
+//! The widget grew into the wallet's secure input: besides hiding the characters it can show an
+//! estimate of the password's strength, whether it is long enough, a warning while caps lock
+//! seems to be on, and a second field to confirm the password.
 
-/// Password entry field with ability to toggle character hiding.
+use multinode_i18n::tr;
+
+/// How strong a password is, from its estimated entropy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Strength {
+    VeryWeak,
+    Weak,
+    Fair,
+    Strong,
+    VeryStrong,
+}
+
+impl Strength {
+    /// The strength of a password with `bits` of entropy
+    pub fn from_bits(bits: f64) -> Self {
+        match bits {
+            b if b < 28.0 => Strength::VeryWeak,
+            b if b < 36.0 => Strength::Weak,
+            b if b < 60.0 => Strength::Fair,
+            b if b < 128.0 => Strength::Strong,
+            _ => Strength::VeryStrong,
+        }
+    }
+
+    fn label(self) -> String {
+        match self {
+            Strength::VeryWeak => tr!("password-very-weak"),
+            Strength::Weak => tr!("password-weak"),
+            Strength::Fair => tr!("password-fair"),
+            Strength::Strong => tr!("password-strong"),
+            Strength::VeryStrong => tr!("password-very-strong"),
+        }
+    }
+
+    /// How full the meter is
+    fn fraction(self) -> f32 {
+        (self as u8 + 1) as f32 / 5.0
+    }
+
+    fn color(self) -> egui::Color32 {
+        match self {
+            Strength::VeryWeak => egui::Color32::from_rgb(200, 50, 50),
+            Strength::Weak => egui::Color32::from_rgb(220, 120, 40),
+            Strength::Fair => egui::Color32::from_rgb(200, 180, 40),
+            Strength::Strong => egui::Color32::from_rgb(90, 170, 60),
+            Strength::VeryStrong => egui::Color32::from_rgb(40, 140, 80),
+        }
+    }
+}
+
+/// Estimates the password's entropy in bits.
+///
+/// Each character is worth the bits needed to pick it from the character classes the password
+/// uses (lowercase, uppercase, digits, symbols and anything else), except that characters which
+/// repeat or continue a sequence of the previous ones, like `aaa` or `abc`, are worth one bit.
+pub fn entropy_bits(password: &str) -> f64 {
+    /// A character class and how many characters are in it
+    type Class = (fn(&char) -> bool, u32);
+
+    let classes: [Class; 4] = [
+        (char::is_ascii_lowercase, 26),
+        (char::is_ascii_uppercase, 26),
+        (char::is_ascii_digit, 10),
+        (char::is_ascii_punctuation, 33),
+    ];
+
+    let mut pool: u32 = classes
+        .iter()
+        .filter(|(class, _)| password.chars().any(|c| class(&c)))
+        .map(|(_, size)| size)
+        .sum();
+    if password.chars().any(|c| c == ' ') {
+        pool += 1;
+    }
+    if !password.is_ascii() {
+        pool += 100;
+    }
+    if pool == 0 {
+        return 0.0;
+    }
+
+    let per_char = (pool as f64).log2();
+    let mut previous: Option<char> = None;
+    password
+        .chars()
+        .map(|c| {
+            let predictable = previous
+                .map(|p| (c as i64 - p as i64).abs() <= 1)
+                .unwrap_or(false);
+            previous = Some(c);
+            if predictable {
+                1.0
+            } else {
+                per_char
+            }
+        })
+        .sum()
+}
+
+/// Whether typing `typed` with shift in the state `shift` means caps lock is on, if it tells.
+///
+/// Only letters tell: uppercase without shift, or lowercase with it, mean caps lock is on.
+pub fn caps_lock_hint(typed: &str, shift: bool) -> Option<bool> {
+    typed
+        .chars()
+        .rev()
+        .find(|c| c.is_alphabetic() && (c.is_uppercase() || c.is_lowercase()))
+        .map(|c| c.is_uppercase() != shift)
+}
+
+/// Password entry field with ability to toggle character hiding, and optional strength meter,
+/// minimum length and confirmation.
 ///
 /// ## Example:
 /// ``` ignore
-/// password_ui(ui, &mut my_password);
+/// ui.add(Password::new(&mut new).confirm(&mut confirm).strength_meter(MIN_PASSWORD_LEN));
 /// ```
-#[allow(clippy::ptr_arg)] // false positive
-pub fn password_ui(ui: &mut egui::Ui, password: &mut String) -> egui::Response {
-    // This widget has its own state — show or hide password characters (`show_plaintext`).
-    // In this case we use a simple `bool`, but you can also declare your own type.
-    // It must implement at least `Clone` and be `'static`.
-    // If you use the `persistence` feature, it also must implement `serde::{Deserialize, Serialize}`.
-
-    // Generate an id for the state
-    let state_id = ui.id().with("show_plaintext");
-
-    // Get state for this widget.
-    // You should get state by value, not by reference to avoid borrowing of [`Memory`].
-    let mut show_plaintext = ui.data_mut(|d| d.get_temp::<bool>(state_id).unwrap_or(false));
-
-    // Process ui, change a local copy of the state
-    // We want TextEdit to fill entire space, and have button after that, so in that case we can
-    // change direction to right_to_left.
-    let result = ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-        // Toggle the `show_plaintext` bool with a button:
-        let response = ui
-            .add(egui::SelectableLabel::new(show_plaintext, "👁"))
-            .on_hover_text("Show/hide password");
-
-        if response.clicked() {
-            show_plaintext = !show_plaintext;
+pub struct Password<'a> {
+    password: &'a mut String,
+
+    /// Second field the password must be typed into again
+    confirm: Option<&'a mut String>,
+
+    /// Minimum length, if the strength meter is shown
+    meter: Option<usize>,
+}
+
+impl<'a> Password<'a> {
+    pub fn new(password: &'a mut String) -> Self {
+        Self {
+            password,
+            confirm: None,
+            meter: None,
         }
+    }
 
-        // Show the password field:
-        ui.add_sized(
-            ui.available_size(),
-            egui::TextEdit::singleline(password).password(!show_plaintext),
-        );
-    });
+    /// Adds a field to type the password again, showing whether both match
+    pub fn confirm(mut self, confirm: &'a mut String) -> Self {
+        self.confirm = Some(confirm);
+        self
+    }
 
-    // Store the (possibly changed) state:
-    ui.data_mut(|d| d.insert_temp(state_id, show_plaintext));
+    /// Shows how strong the password is, and whether it has at least `min_len` characters
+    pub fn strength_meter(mut self, min_len: usize) -> Self {
+        self.meter = Some(min_len);
+        self
+    }
+}
 
-    // All done! Return the interaction response so the user can check what happened
-    // (hovered, clicked, …) and maybe show a tooltip:
-    result.response
+/// A password field filling the width, after the controls already in `ui`
+fn field(ui: &mut egui::Ui, text: &mut String, show_plaintext: bool, hint: &str) -> egui::Response {
+    ui.add(
+        egui::TextEdit::singleline(text)
+            .password(!show_plaintext)
+            .hint_text(hint)
+            .desired_width(ui.available_width()),
+    )
+}
+
+impl egui::Widget for Password<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        // This widget has its own state — show or hide password characters (`show_plaintext`).
+        // In this case we use a simple `bool`, but you can also declare your own type.
+        // It must implement at least `Clone` and be `'static`.
+        // If you use the `persistence` feature, it also must implement `serde::{Deserialize, Serialize}`.
+
+        // Generate an id for the state
+        let state_id = ui.id().with("show_plaintext");
+        let caps_id = ui.id().with("caps_lock");
+
+        // Get state for this widget.
+        // You should get state by value, not by reference to avoid borrowing of [`Memory`].
+        let mut show_plaintext = ui.data_mut(|d| d.get_temp::<bool>(state_id).unwrap_or(false));
+        let mut caps_lock = ui.data_mut(|d| d.get_temp::<bool>(caps_id).unwrap_or(false));
+
+        let Password {
+            password,
+            confirm,
+            meter,
+        } = self;
+
+        ui.vertical(|ui| {
+            // We want TextEdit to fill entire space, and have button after that, so in that case
+            // we can change direction to right_to_left.
+            let inner = ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                // Toggle the `show_plaintext` bool with a button:
+                let response = ui
+                    .add(egui::SelectableLabel::new(show_plaintext, "👁"))
+                    .on_hover_text(tr!("password-show-hide"));
+
+                if response.clicked() {
+                    show_plaintext = !show_plaintext;
+                }
+
+                // Show the password field:
+                field(ui, password, show_plaintext, "")
+            });
+            let mut response = inner.inner;
+            let mut focused = response.has_focus();
+
+            let mut confirmed = None;
+            if let Some(confirm) = confirm {
+                let confirm_response = field(ui, confirm, show_plaintext, &tr!("password-confirm"));
+                focused |= confirm_response.has_focus();
+                if !confirm.is_empty() {
+                    confirmed = Some(*confirm == *password);
+                }
+                response = response.union(confirm_response);
+            }
+
+            // Letters typed into the focused field tell whether caps lock is on
+            if focused {
+                ui.input(|i| {
+                    for event in &i.events {
+                        if let egui::Event::Text(typed) = event {
+                            if let Some(on) = caps_lock_hint(typed, i.modifiers.shift) {
+                                caps_lock = on;
+                            }
+                        }
+                    }
+                });
+            }
+
+            if let Some(min_len) = meter {
+                ui.horizontal(|ui| {
+                    let len = password.chars().count();
+                    if len >= min_len {
+                        ui.label(tr!("password-long-enough", min = min_len));
+                    } else {
+                        ui.colored_label(
+                            ui.visuals().warn_fg_color,
+                            tr!("password-too-short", len = len, min = min_len),
+                        );
+                    }
+
+                    let strength = Strength::from_bits(entropy_bits(password));
+                    let shown = if password.is_empty() {
+                        0.0
+                    } else {
+                        strength.fraction()
+                    };
+                    ui.add(
+                        egui::ProgressBar::new(shown)
+                            .fill(strength.color())
+                            .text(strength.label())
+                            .desired_width(ui.available_width()),
+                    );
+                });
+            }
+
+            match confirmed {
+                Some(true) => {
+                    ui.label(tr!("password-match"));
+                }
+                Some(false) => {
+                    ui.colored_label(ui.visuals().error_fg_color, tr!("password-mismatch"));
+                }
+                None => {}
+            }
+
+            if focused && caps_lock {
+                ui.colored_label(ui.visuals().warn_fg_color, tr!("password-caps-lock"));
+            }
+
+            // Store the (possibly changed) state:
+            ui.data_mut(|d| {
+                d.insert_temp(state_id, show_plaintext);
+                d.insert_temp(caps_id, caps_lock);
+            });
+
+            // All done! Return the interaction response so the user can check what happened
+            // (hovered, clicked, …) and maybe show a tooltip:
+            response
+        })
+        .inner
+    }
+}
+
+/// Password entry field with ability to toggle character hiding.
+///
+/// ## Example:
+/// ``` ignore
+/// password_ui(ui, &mut my_password);
+/// ```
+#[allow(clippy::ptr_arg)] // false positive
+pub fn password_ui(ui: &mut egui::Ui, password: &mut String) -> egui::Response {
+    ui.add(Password::new(password))
 }
 
 // A wrapper that allows the more idiomatic usage pattern: `ui.add(…)`
@@ -60,3 +305,59 @@ pub fn password_ui(ui: &mut egui::Ui, password: &mut String) -> egui::Response {
 pub fn password(password: &mut String) -> impl egui::Widget + '_ {
     move |ui: &mut egui::Ui| password_ui(ui, password)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_entropy() {
+        assert_eq!(entropy_bits(""), 0.0);
+
+        // 8 lowercase letters pick from 26 each
+        let eight = 8.0 * 26f64.log2();
+        assert!((entropy_bits("qwzmxkvp") - eight).abs() < 1e-9);
+
+        // Repeats and sequences are nearly free
+        assert!(entropy_bits("aaaaaaaa") < 12.0);
+        assert!(entropy_bits("abcdefgh") < 12.0);
+        assert!(entropy_bits("12345678") < 12.0);
+
+        // More character classes and more length are both stronger
+        assert!(entropy_bits("Qw3!zm$X") > entropy_bits("qwzmxkvp"));
+        assert!(entropy_bits("qwzmxkvpqwzm") > entropy_bits("qwzmxkvp"));
+    }
+
+    #[test]
+    fn grades_strength() {
+        assert_eq!(Strength::from_bits(entropy_bits("")), Strength::VeryWeak);
+        assert_eq!(
+            Strength::from_bits(entropy_bits("aaaaaaaa")),
+            Strength::VeryWeak
+        );
+        assert_eq!(Strength::from_bits(entropy_bits("qwzmxkv")), Strength::Weak);
+        assert_eq!(
+            Strength::from_bits(entropy_bits("Qw3!zm$X")),
+            Strength::Fair
+        );
+        assert_eq!(
+            Strength::from_bits(entropy_bits("Tr0ub4dor&3")),
+            Strength::Strong
+        );
+        assert_eq!(
+            Strength::from_bits(entropy_bits("a quick brown Fox jumps over 13 lazy dogs!")),
+            Strength::VeryStrong
+        );
+        assert!(Strength::Weak < Strength::Strong);
+    }
+
+    #[test]
+    fn hints_caps_lock() {
+        assert_eq!(caps_lock_hint("A", false), Some(true));
+        assert_eq!(caps_lock_hint("a", true), Some(true));
+        assert_eq!(caps_lock_hint("a", false), Some(false));
+        assert_eq!(caps_lock_hint("A", true), Some(false));
+        assert_eq!(caps_lock_hint("1", false), None);
+        assert_eq!(caps_lock_hint("!", true), None);
+    }
+}
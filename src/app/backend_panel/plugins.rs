@@ -0,0 +1,102 @@
+//! Plugin settings panel
+//!
+//! Lists every plugin the node knows about, along with its status and editable resource limits.
+
+use std::collections::BTreeMap;
+
+use multinode_i18n::tr;
+
+use crate::app::platform::{Platform, PluginStatus, ResourceLimits};
+
+/// Settings for a single plugin
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct PluginSettings {
+    /// Resource limits applied when the plugin is loaded and called
+    pub limits: ResourceLimits,
+}
+
+/// Persisted settings for every plugin, by plugin name
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Plugins {
+    settings: BTreeMap<String, PluginSettings>,
+}
+
+impl Plugins {
+    /// Hands every plugin's limits to the platform, replacing those it had
+    pub fn sync(&self, platform: &Platform) {
+        let limits = self
+            .settings
+            .iter()
+            .map(|(name, settings)| (name.clone(), settings.limits))
+            .collect();
+        platform.replace_limits(limits);
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, platform: &Platform) {
+        let statuses: BTreeMap<String, PluginStatus> = platform.plugins().into_iter().collect();
+
+        // Every loaded plugin gets settings, so its limits can be edited
+        for name in statuses.keys() {
+            self.settings.entry(name.clone()).or_default();
+        }
+
+        for (name, settings) in self.settings.iter_mut() {
+            let status = statuses.get(name);
+
+            egui::CollapsingHeader::new(name)
+                .id_salt(("plugin_settings", name))
+                .show(ui, |ui| {
+                    match status {
+                        Some(status) => ui.label(tr!("plugin-status", status = status)),
+                        None => ui.weak(tr!("plugin-not-loaded")),
+                    };
+
+                    if let Some(mut open) = platform.panel_open(name) {
+                        if ui.toggle_value(&mut open, tr!("plugin-panel")).changed() {
+                            platform.set_panel_open(name, open);
+                        }
+                    }
+
+                    let limits = &mut settings.limits;
+                    let mut changed = false;
+
+                    egui::Grid::new(("plugin_limits", name))
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            ui.label(tr!("plugin-fuel"));
+                            changed |= ui
+                                .add(egui::DragValue::new(&mut limits.fuel).speed(10_000))
+                                .changed();
+                            ui.end_row();
+
+                            ui.label(tr!("plugin-memory"));
+                            changed |= ui
+                                .add(egui::DragValue::new(&mut limits.memory_mib).range(1..=4096))
+                                .changed();
+                            ui.end_row();
+
+                            ui.label(tr!("plugin-timeout"));
+                            changed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut limits.timeout_ms)
+                                        .range(1..=600_000)
+                                        .speed(10),
+                                )
+                                .changed();
+                            ui.end_row();
+                        });
+
+                    if ui.button(tr!("plugin-reset-limits")).clicked() {
+                        *limits = ResourceLimits::default();
+                        changed = true;
+                    }
+
+                    if changed {
+                        platform.set_limits(name.clone(), *limits);
+                    }
+                });
+        }
+    }
+}
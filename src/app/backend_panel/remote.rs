@@ -0,0 +1,371 @@
+//! Load plugins from a URL or content identifier (CID).
+//!
+//! HTTP(S) URLs are fetched directly. The node doesn't fetch blocks from its peers for the app
+//! yet, so CIDs fall back to an IPFS HTTP gateway, which the panel labels as such. It hands back
+//! a CAR file whose every block is checked against its CID before the plugin is put back
+//! together from them (see [`car`]), so the gateway does not need to be trusted. An optional
+//! expected SHA-256 digest is checked for either source.
+
+use std::sync::{Arc, Mutex};
+
+use multinode_i18n::tr;
+use sha2::{Digest as _, Sha256};
+
+mod car;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native;
+
+#[cfg(not(target_arch = "wasm32"))]
+use native::fetch;
+
+#[cfg(target_arch = "wasm32")]
+use multinode_web::fetch::fetch;
+
+/// Download callbacks run on a background thread natively, so they must be `Send` there
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSend: Send {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send> MaybeSend for T {}
+
+/// On the web downloads run on the browser's event loop, so callbacks need not be `Send`
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSend {}
+#[cfg(target_arch = "wasm32")]
+impl<T> MaybeSend for T {}
+
+/// Multicodec of a CID which addresses raw bytes
+const RAW_CODEC: u64 = 0x55;
+
+/// Multicodec of a UnixFS DAG node, implied by CIDv0
+const DAG_PB_CODEC: u64 = 0x70;
+
+/// Multihash code of SHA2-256
+const SHA2_256: u64 = 0x12;
+
+/// The parts of a content identifier needed to fetch and verify a plugin
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cid {
+    /// The CID as typed, which gateways accept as is
+    text: String,
+    codec: u64,
+    hash_code: u64,
+    digest: Vec<u8>,
+}
+
+impl Cid {
+    /// Parses a CIDv0 (`Qm…`) or a multibase encoded CIDv1
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let invalid = |e: &dyn std::fmt::Display| format!("invalid CID: {e}");
+
+        let (codec, multihash) = if text.len() == 46 && text.starts_with("Qm") {
+            let multihash = multibase::Base::Base58Btc
+                .decode(text)
+                .map_err(|e| invalid(&e))?;
+            (DAG_PB_CODEC, multihash)
+        } else {
+            let (_, bytes) = multibase::decode(text).map_err(|e| invalid(&e))?;
+            let (version, rest) = unsigned_varint::decode::u64(&bytes).map_err(|e| invalid(&e))?;
+            if version != 1 {
+                return Err(invalid(&format!("unsupported version {version}")));
+            }
+            let (codec, rest) = unsigned_varint::decode::u64(rest).map_err(|e| invalid(&e))?;
+            (codec, rest.to_vec())
+        };
+
+        let (hash_code, rest) =
+            unsigned_varint::decode::u64(&multihash).map_err(|e| invalid(&e))?;
+        let (len, digest) = unsigned_varint::decode::usize(rest).map_err(|e| invalid(&e))?;
+        if digest.len() != len {
+            return Err(invalid(&"truncated multihash"));
+        }
+
+        Ok(Self {
+            text: text.to_owned(),
+            codec,
+            hash_code,
+            digest: digest.to_vec(),
+        })
+    }
+}
+
+impl std::fmt::Display for Cid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+/// Where a plugin is loaded from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Url(String),
+    Cid(Cid),
+}
+
+impl Source {
+    /// Parses a URL or CID, ignoring surrounding whitespace and an `ipfs://` prefix
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+
+        if input.starts_with("http://") || input.starts_with("https://") {
+            return Ok(Source::Url(input.to_owned()));
+        }
+
+        let cid = input.strip_prefix("ipfs://").unwrap_or(input);
+        Cid::parse(cid)
+            .map(Source::Cid)
+            .map_err(|_| tr!("remote-invalid-source", source = input))
+    }
+
+    /// URL to fetch the plugin from, asking `gateway` for the CAR file of CIDs
+    pub fn url(&self, gateway: &str) -> String {
+        match self {
+            Source::Url(url) => url.clone(),
+            Source::Cid(cid) => format!("{}/{cid}?format=car", gateway.trim_end_matches('/')),
+        }
+    }
+
+    /// Name to load the plugin under: the file stem of a URL, or the CID
+    pub fn name(&self) -> String {
+        match self {
+            Source::Url(url) => url
+                .split(['?', '#'])
+                .next()
+                .and_then(|path| path.rsplit('/').find(|segment| !segment.is_empty()))
+                .map(|file| file.strip_suffix(".wasm").unwrap_or(file).to_owned())
+                .unwrap_or_else(|| url.clone()),
+            Source::Cid(cid) => cid.to_string(),
+        }
+    }
+
+    /// The plugin in the downloaded `bytes`: as is from URLs, and put back together from the
+    /// verified blocks of the CAR file from CIDs
+    pub fn unpack(&self, bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+        match self {
+            Source::Url(_) => Ok(bytes),
+            Source::Cid(cid) => car::unpack(cid, &bytes).map_err(|e| format!("CID {cid}: {e}")),
+        }
+    }
+}
+
+/// Checks `bytes` against an expected hex SHA-256 digest, optionally prefixed with `sha256:`
+pub fn verify_digest(expected: &str, bytes: &[u8]) -> Result<(), String> {
+    let expected = expected.trim();
+    let expected = expected.strip_prefix("sha256:").unwrap_or(expected);
+    let expected = hex::decode(expected)
+        .map_err(|_| tr!("remote-invalid-digest", digest = format!("{expected:?}")))?;
+
+    let actual = Sha256::digest(bytes);
+    if actual.as_slice() == expected.as_slice() {
+        Ok(())
+    } else {
+        Err(tr!("remote-digest-mismatch", actual = hex::encode(actual)))
+    }
+}
+
+/// Progress of the current download
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Download {
+    #[default]
+    Idle,
+    InProgress {
+        received: u64,
+        total: Option<u64>,
+    },
+    Loaded(String),
+    Failed(String),
+}
+
+/// Panel state for loading plugins by URL or CID
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct RemotePlugin {
+    /// URL or CID typed by the user
+    source: String,
+
+    /// Optional expected SHA-256 digest
+    digest: String,
+
+    /// IPFS HTTP gateway CIDs fall back to, as they can't be fetched from peers yet
+    gateway: String,
+
+    #[serde(skip)]
+    download: Arc<Mutex<Download>>,
+}
+
+impl Default for RemotePlugin {
+    fn default() -> Self {
+        Self {
+            source: String::new(),
+            digest: String::new(),
+            gateway: "https://ipfs.io/ipfs/".to_owned(),
+            download: Default::default(),
+        }
+    }
+}
+
+impl RemotePlugin {
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        on_load_callback: impl FnOnce(String, Vec<u8>) + MaybeSend + 'static,
+    ) {
+        ui.label(tr!("remote-title"));
+        ui.add(egui::TextEdit::singleline(&mut self.source).hint_text(tr!("remote-source-hint")));
+        ui.add(egui::TextEdit::singleline(&mut self.digest).hint_text(tr!("remote-digest-hint")));
+        ui.collapsing(tr!("remote-gateway"), |ui| {
+            ui.weak(tr!("remote-gateway-fallback"));
+            ui.text_edit_singleline(&mut self.gateway);
+        });
+
+        let download = self.download.lock().unwrap().clone();
+        let in_progress = matches!(download, Download::InProgress { .. });
+        let source = Source::parse(&self.source);
+
+        let fetch_clicked = ui
+            .add_enabled(
+                !in_progress && source.is_ok(),
+                egui::Button::new(tr!("remote-fetch")),
+            )
+            .clicked();
+
+        if let (Err(e), false) = (&source, self.source.trim().is_empty()) {
+            ui.colored_label(ui.visuals().warn_fg_color, e);
+        }
+
+        match &download {
+            Download::Idle => {}
+            Download::InProgress { received, total } => {
+                let progress = match total {
+                    Some(total) if *total > 0 => {
+                        egui::ProgressBar::new(*received as f32 / *total as f32).text(tr!(
+                            "remote-progress",
+                            received = received / 1024,
+                            total = total / 1024
+                        ))
+                    }
+                    _ => egui::ProgressBar::new(0.0)
+                        .animate(true)
+                        .text(tr!("remote-received", received = received / 1024)),
+                };
+                ui.add(progress);
+            }
+            Download::Loaded(name) => {
+                ui.label(tr!("remote-fetched", name = name));
+            }
+            Download::Failed(e) => {
+                ui.colored_label(ui.visuals().error_fg_color, tr!("remote-failed", error = e));
+            }
+        }
+
+        if let (true, Ok(source)) = (fetch_clicked, source) {
+            self.fetch(ui.ctx().clone(), source, on_load_callback);
+        }
+    }
+
+    /// Downloads the plugin, verifies it and hands it to `on_load_callback`
+    fn fetch(
+        &self,
+        ctx: egui::Context,
+        source: Source,
+        on_load_callback: impl FnOnce(String, Vec<u8>) + MaybeSend + 'static,
+    ) {
+        let url = source.url(&self.gateway);
+        let digest = self.digest.trim().to_owned();
+
+        *self.download.lock().unwrap() = Download::InProgress {
+            received: 0,
+            total: None,
+        };
+
+        let progress = {
+            let download = self.download.clone();
+            let ctx = ctx.clone();
+            move |received, total| {
+                *download.lock().unwrap() = Download::InProgress { received, total };
+                ctx.request_repaint();
+            }
+        };
+
+        let download = self.download.clone();
+        let on_done = {
+            let url = url.clone();
+            move |result: Result<Vec<u8>, String>| {
+                let verified = result.and_then(|bytes| {
+                    let bytes = source.unpack(bytes)?;
+                    if !digest.is_empty() {
+                        verify_digest(&digest, &bytes)?;
+                    }
+                    Ok(bytes)
+                });
+
+                *download.lock().unwrap() = match verified {
+                    Ok(bytes) => {
+                        let name = source.name();
+                        on_load_callback(name.clone(), bytes);
+                        Download::Loaded(name)
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to fetch plugin from {url}: {e}");
+                        Download::Failed(e)
+                    }
+                };
+                ctx.request_repaint();
+            }
+        };
+
+        fetch(url.clone(), progress, on_done);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HELLO_SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    #[test]
+    fn parses_sources() {
+        assert_eq!(
+            Source::parse(" https://example.com/p/hello.wasm?v=1 ")
+                .unwrap()
+                .name(),
+            "hello"
+        );
+
+        let cid = "bafkreibm6jg3ux5qumhcn2b3flc3tyu6dmlb4xa7u5bf44yegnrjhc4yeq";
+        let source = Source::parse(&format!("ipfs://{cid}")).unwrap();
+        assert_eq!(source.name(), cid);
+        assert_eq!(
+            source.url("https://ipfs.io/ipfs/"),
+            format!("https://ipfs.io/ipfs/{cid}?format=car")
+        );
+
+        let v0 = "QmWATWQ7fVPP2EFGu71UkfnqhYXDYH566qy47CnJDgvs8u";
+        assert_eq!(Source::parse(v0).unwrap().name(), v0);
+
+        assert!(Source::parse("not a source").is_err());
+    }
+
+    #[test]
+    fn passes_urls_through() {
+        let source = Source::parse("https://example.com/hello.wasm").unwrap();
+        assert_eq!(source.unpack(b"hello".to_vec()), Ok(b"hello".to_vec()));
+
+        // A gateway handing back the file rather than its blocks
+        let source =
+            Source::parse("bafkreibm6jg3ux5qumhcn2b3flc3tyu6dmlb4xa7u5bf44yegnrjhc4yeq").unwrap();
+        assert!(source.unpack(b"hello".to_vec()).is_err());
+    }
+
+    #[test]
+    fn verifies_digests() {
+        assert_eq!(verify_digest(HELLO_SHA256, b"hello"), Ok(()));
+        assert_eq!(
+            verify_digest(&format!("sha256:{HELLO_SHA256}"), b"hello"),
+            Ok(())
+        );
+        assert!(verify_digest(HELLO_SHA256, b"tampered").is_err());
+        assert!(verify_digest("xyz", b"hello").is_err());
+    }
+}
@@ -0,0 +1,294 @@
+//! Verified plugins from IPFS gateways
+//!
+//! Plugins fetched by CID are asked of the gateway as a CAR file: the blocks of the plugin's
+//! DAG, each with its own CID. Every block is checked against its CID, and the plugin is put
+//! back together from the blocks its root links to, so the gateway doesn't need to be trusted
+//! whichever version or codec the CID has.
+//!
+//! Raw blocks are the bytes themselves. UnixFS files are dag-pb nodes, protobufs holding some of
+//! the file's bytes and links to the blocks with the rest, in order.
+
+use std::collections::BTreeMap;
+
+use sha2::{Digest as _, Sha256};
+
+use super::{Cid, DAG_PB_CODEC, RAW_CODEC, SHA2_256};
+
+/// How deep a DAG may link, far beyond what a plugin's file needs
+const MAX_DEPTH: usize = 32;
+
+/// How many links may be followed, as blocks can be linked to over and over
+const MAX_LINKS: usize = 1 << 20;
+
+/// How big a plugin may be put back together, the most the app downloads
+const MAX_SIZE: usize = 256 * 1024 * 1024;
+
+/// Type of a UnixFS node holding raw bytes
+const UNIXFS_RAW: u64 = 0;
+
+/// Type of a UnixFS node holding a file
+const UNIXFS_FILE: u64 = 2;
+
+/// A protobuf field's value, of the wire types dag-pb and UnixFS use
+enum Field<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+}
+
+fn varint(bytes: &[u8]) -> Result<(u64, &[u8]), String> {
+    unsigned_varint::decode::u64(bytes).map_err(|e| format!("invalid varint: {e}"))
+}
+
+/// The fields of a protobuf message, by number
+fn fields(mut bytes: &[u8]) -> Result<Vec<(u64, Field<'_>)>, String> {
+    let mut fields = Vec::new();
+    while !bytes.is_empty() {
+        let (key, rest) = varint(bytes)?;
+        let (field, rest) = match key & 0b111 {
+            0 => {
+                let (value, rest) = varint(rest)?;
+                (Field::Varint(value), rest)
+            }
+            2 => {
+                let (len, rest) = varint(rest)?;
+                let len = usize::try_from(len).map_err(|e| e.to_string())?;
+                if rest.len() < len {
+                    return Err("truncated protobuf".to_owned());
+                }
+                let (value, rest) = rest.split_at(len);
+                (Field::Bytes(value), rest)
+            }
+            wire => return Err(format!("unexpected protobuf wire type {wire}")),
+        };
+        fields.push((key >> 3, field));
+        bytes = rest;
+    }
+    Ok(fields)
+}
+
+impl Cid {
+    /// Reads a binary CID off the front of `bytes`, returning what follows it
+    fn read(bytes: &[u8]) -> Result<(Self, &[u8]), String> {
+        // A CIDv0 is a bare SHA2-256 multihash
+        let (codec, multihash) = if bytes.starts_with(&[SHA2_256 as u8, 32]) {
+            (DAG_PB_CODEC, bytes)
+        } else {
+            let (version, rest) = varint(bytes)?;
+            if version != 1 {
+                return Err(format!("unsupported CID version {version}"));
+            }
+            varint(rest)?
+        };
+        let (hash_code, rest) = varint(multihash)?;
+        let (len, rest) = varint(rest)?;
+        let len = usize::try_from(len).map_err(|e| e.to_string())?;
+        if rest.len() < len {
+            return Err("truncated CID".to_owned());
+        }
+        let (digest, rest) = rest.split_at(len);
+        let cid = Self {
+            text: String::new(),
+            codec,
+            hash_code,
+            digest: digest.to_vec(),
+        };
+        Ok((cid, rest))
+    }
+
+    /// Checks `block` against the CID's hash
+    fn verify(&self, block: &[u8]) -> Result<(), String> {
+        if self.hash_code != SHA2_256 {
+            return Err(format!("unsupported hash 0x{:x}", self.hash_code));
+        }
+        if Sha256::digest(block).as_slice() != self.digest {
+            return Err("a block does not match its CID".to_owned());
+        }
+        Ok(())
+    }
+}
+
+/// The verified blocks of a CAR file, by digest
+struct Blocks<'a>(BTreeMap<Vec<u8>, &'a [u8]>);
+
+impl<'a> Blocks<'a> {
+    /// Reads the blocks of a CARv1 file, checking each against its CID
+    fn read(car: &'a [u8]) -> Result<Self, String> {
+        // The header names the roots, but the root asked for is the one that matters
+        let (len, rest) = varint(car)?;
+        let mut rest = usize::try_from(len)
+            .ok()
+            .and_then(|len| rest.get(len..))
+            .ok_or("truncated CAR header")?;
+
+        let mut blocks = BTreeMap::new();
+        while !rest.is_empty() {
+            let (len, section) = varint(rest)?;
+            let len = usize::try_from(len).map_err(|e| e.to_string())?;
+            if section.len() < len {
+                return Err("truncated CAR section".to_owned());
+            }
+            let (section, next) = section.split_at(len);
+            let (cid, block) = Cid::read(section)?;
+            cid.verify(block)?;
+            blocks.insert(cid.digest, block);
+            rest = next;
+        }
+        Ok(Self(blocks))
+    }
+
+    /// Appends the bytes of the file at `cid` to `file`, counting the links followed in `links`
+    fn file(
+        &self,
+        cid: &Cid,
+        file: &mut Vec<u8>,
+        links: &mut usize,
+        depth: usize,
+    ) -> Result<(), String> {
+        *links += 1;
+        if depth > MAX_DEPTH || *links > MAX_LINKS {
+            return Err("the plugin's DAG is too big".to_owned());
+        }
+        if cid.hash_code != SHA2_256 {
+            return Err(format!("unsupported hash 0x{:x}", cid.hash_code));
+        }
+        let block = self
+            .0
+            .get(cid.digest.as_slice())
+            .ok_or("the gateway left out a block of the plugin")?;
+
+        match cid.codec {
+            RAW_CODEC => file.extend_from_slice(block),
+            DAG_PB_CODEC => {
+                let mut children = Vec::new();
+                let mut data = None;
+                for (number, field) in fields(block)? {
+                    match (number, field) {
+                        (1, Field::Bytes(bytes)) => data = Some(bytes),
+                        (2, Field::Bytes(link)) => children.push(link),
+                        _ => {}
+                    }
+                }
+
+                let mut kind = None;
+                for (number, field) in fields(data.unwrap_or_default())? {
+                    match (number, field) {
+                        (1, Field::Varint(value)) => kind = Some(value),
+                        (2, Field::Bytes(bytes)) => file.extend_from_slice(bytes),
+                        _ => {}
+                    }
+                }
+                if !matches!(kind, Some(UNIXFS_FILE | UNIXFS_RAW)) {
+                    return Err("the CID is not of a file".to_owned());
+                }
+
+                for link in children {
+                    let hash = fields(link)?.into_iter().find_map(|field| match field {
+                        (1, Field::Bytes(hash)) => Some(hash),
+                        _ => None,
+                    });
+                    let (child, _) = Cid::read(hash.ok_or("a link has no CID")?)?;
+                    self.file(&child, file, links, depth + 1)?;
+                }
+            }
+            codec => return Err(format!("unsupported codec 0x{codec:x}")),
+        }
+        if file.len() > MAX_SIZE {
+            return Err("the plugin is too big".to_owned());
+        }
+        Ok(())
+    }
+}
+
+/// The plugin at `root`, put back together from the verified blocks of `car`
+pub(super) fn unpack(root: &Cid, car: &[u8]) -> Result<Vec<u8>, String> {
+    let blocks = Blocks::read(car)?;
+    let mut file = Vec::new();
+    blocks.file(root, &mut file, &mut 0, 0)?;
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(number: u64, bytes: &[u8]) -> Vec<u8> {
+        let mut buf = unsigned_varint::encode::u64_buffer();
+        let mut field = unsigned_varint::encode::u64(number << 3 | 2, &mut buf).to_vec();
+        field.extend(unsigned_varint::encode::u64(bytes.len() as u64, &mut buf));
+        field.extend(bytes);
+        field
+    }
+
+    /// A dag-pb UnixFS file node with `data` and links to `children`
+    fn file_node(data: &[u8], children: &[Vec<u8>]) -> Vec<u8> {
+        let unixfs = [vec![1 << 3, UNIXFS_FILE as u8], field(2, data)].concat();
+        let links = children.iter().flat_map(|cid| field(2, &field(1, cid)));
+        links.chain(field(1, &unixfs)).collect()
+    }
+
+    fn multihash(block: &[u8]) -> Vec<u8> {
+        [&[SHA2_256 as u8, 32][..], &Sha256::digest(block)].concat()
+    }
+
+    fn cid_v1(codec: u8, block: &[u8]) -> Vec<u8> {
+        [&[1, codec][..], &multihash(block)].concat()
+    }
+
+    fn car(blocks: &[(Vec<u8>, &[u8])]) -> Vec<u8> {
+        // The header's contents are skipped
+        let mut car = vec![2, 0xa0, 0xa0];
+        for (cid, block) in blocks {
+            car.push((cid.len() + block.len()) as u8);
+            car.extend(cid);
+            car.extend(*block);
+        }
+        car
+    }
+
+    #[test]
+    fn unpacks_unixfs_files() {
+        let (first, second) = (b"\0asm".as_slice(), b" plugin".as_slice());
+        let leaves = [
+            cid_v1(RAW_CODEC as u8, first),
+            cid_v1(RAW_CODEC as u8, second),
+        ];
+        let root = file_node(b"", &leaves);
+        let root_cid = multihash(&root);
+        let car = car(&[
+            (root_cid.clone(), &root),
+            (leaves[0].clone(), first),
+            (leaves[1].clone(), second),
+        ]);
+
+        // CIDv0 addresses the root node, not the file
+        let text = multibase::Base::Base58Btc.encode(&root_cid);
+        let cid = Cid::parse(&text).unwrap();
+        assert_eq!(unpack(&cid, &car).unwrap(), b"\0asm plugin");
+
+        // Gateways can't leave blocks out, or swap them
+        let missing = car_without_last(&car, second.len() + leaves[1].len() + 1);
+        assert!(unpack(&cid, &missing).is_err());
+        let mut tampered = car.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(unpack(&cid, &tampered).is_err());
+    }
+
+    fn car_without_last(car: &[u8], len: usize) -> Vec<u8> {
+        car[..car.len() - len].to_vec()
+    }
+
+    #[test]
+    fn unpacks_raw_blocks() {
+        let block = b"hello".as_slice();
+        let cid = cid_v1(RAW_CODEC as u8, block);
+        let text = multibase::encode(multibase::Base::Base32Lower, &cid);
+        let root = Cid::parse(&text).unwrap();
+        assert_eq!(unpack(&root, &car(&[(cid, block)])).unwrap(), b"hello");
+
+        // Inline data of the root node alone
+        let node = file_node(b"inline", &[]);
+        let cid = cid_v1(DAG_PB_CODEC as u8, &node);
+        let root = Cid::parse(&multibase::encode(multibase::Base::Base32Lower, &cid)).unwrap();
+        assert_eq!(unpack(&root, &car(&[(cid, &node)])).unwrap(), b"inline");
+    }
+}
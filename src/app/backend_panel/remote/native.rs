@@ -0,0 +1,104 @@
+use std::io::Read;
+
+/// Largest plugin that will be downloaded
+const MAX_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Downloads `url` on a blocking thread.
+///
+/// `on_progress` is called with the bytes received so far and the total, if the server sent
+/// one. `on_done` is called once with the downloaded bytes or an error.
+pub fn fetch(
+    url: String,
+    mut on_progress: impl FnMut(u64, Option<u64>) + Send + 'static,
+    on_done: impl FnOnce(Result<Vec<u8>, String>) + Send + 'static,
+) {
+    tokio::task::spawn_blocking(move || {
+        on_done(download(&url, &mut on_progress));
+    });
+}
+
+fn download(url: &str, on_progress: &mut impl FnMut(u64, Option<u64>)) -> Result<Vec<u8>, String> {
+    let response = ureq::get(url).call().map_err(|e| e.to_string())?;
+    let total = response
+        .header("Content-Length")
+        .and_then(|len| len.parse::<u64>().ok());
+
+    if total.is_some_and(|total| total > MAX_SIZE) {
+        return Err(format!(
+            "plugin is larger than {} MiB",
+            MAX_SIZE / 1024 / 1024
+        ));
+    }
+
+    let mut reader = response.into_reader().take(MAX_SIZE + 1);
+    let mut bytes = Vec::with_capacity(total.unwrap_or_default() as usize);
+    let mut chunk = [0; 64 * 1024];
+
+    loop {
+        let n = reader.read(&mut chunk).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        bytes.extend_from_slice(&chunk[..n]);
+        if bytes.len() as u64 > MAX_SIZE {
+            return Err(format!(
+                "plugin is larger than {} MiB",
+                MAX_SIZE / 1024 / 1024
+            ));
+        }
+        on_progress(bytes.len() as u64, total);
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpListener;
+
+    /// Serves `body` once over HTTP on a local port, returning its URL
+    fn serve(body: &'static [u8]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).unwrap();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(body).unwrap();
+        });
+
+        format!("http://{addr}/plugin.wasm")
+    }
+
+    #[test]
+    fn downloads_with_progress() {
+        let url = serve(b"\0asm plugin bytes");
+        let mut progress = Vec::new();
+
+        let bytes = download(&url, &mut |received, total| {
+            progress.push((received, total))
+        })
+        .unwrap();
+
+        assert_eq!(bytes, b"\0asm plugin bytes");
+        assert_eq!(progress.last(), Some(&(17, Some(17))));
+    }
+
+    #[test]
+    fn reports_unreachable_hosts() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/plugin.wasm", listener.local_addr().unwrap());
+        drop(listener);
+
+        assert!(download(&url, &mut |_, _| {}).is_err());
+    }
+}
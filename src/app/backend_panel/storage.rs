@@ -0,0 +1,221 @@
+//! Storage inspector
+//!
+//! Browses each plugin's key-value storage, and lets its keys be edited, exported and cleared.
+//! Values which are readable text are shown as is; anything else is shown as `hex:` followed by
+//! the hex encoded bytes, and typed values with that prefix are decoded the same way.
+
+use std::collections::BTreeMap;
+
+use multinode_i18n::tr;
+
+use super::file_dialog;
+use crate::app::platform::Platform;
+
+const HEX_PREFIX: &str = "hex:";
+
+/// Shows a value as text if it is readable, or as prefixed hex otherwise
+pub(super) fn encode(value: &[u8]) -> String {
+    match std::str::from_utf8(value) {
+        Ok(text)
+            if !text.starts_with(HEX_PREFIX)
+                && !text
+                    .chars()
+                    .any(|c| c.is_control() && c != '\n' && c != '\t') =>
+        {
+            text.to_owned()
+        }
+        _ => format!("{HEX_PREFIX}{}", hex::encode(value)),
+    }
+}
+
+/// Parses a value typed as text or prefixed hex
+fn decode(text: &str) -> Result<Vec<u8>, String> {
+    match text.strip_prefix(HEX_PREFIX) {
+        Some(hex) => hex::decode(hex.trim()).map_err(|e| tr!("storage-invalid-hex", error = e)),
+        None => Ok(text.as_bytes().to_vec()),
+    }
+}
+
+/// A plugin's keys as exported to a file
+#[derive(serde::Serialize)]
+struct Export<'a> {
+    plugin: &'a str,
+    entries: BTreeMap<&'a str, String>,
+}
+
+/// State of the storage inspector
+#[derive(Default)]
+pub struct StorageInspector {
+    /// Edited values not yet saved, by plugin and key
+    edits: BTreeMap<(String, String), String>,
+
+    /// Key and value being added, by plugin
+    new: BTreeMap<String, (String, String)>,
+
+    /// Plugin whose keys are about to be cleared, awaiting confirmation
+    confirm_clear: Option<String>,
+
+    /// Why the last value saved couldn't be, by plugin
+    errors: BTreeMap<String, String>,
+}
+
+impl StorageInspector {
+    pub fn ui(&mut self, ui: &mut egui::Ui, platform: &Platform) {
+        let plugins = platform.storage_plugins();
+        if plugins.is_empty() {
+            ui.weak(tr!("storage-empty"));
+        }
+
+        for plugin in plugins {
+            let entries = platform.storage_entries(&plugin);
+
+            egui::CollapsingHeader::new(tr!(
+                "storage-plugin",
+                plugin = plugin,
+                count = entries.len()
+            ))
+            .id_salt(("plugin_storage", &plugin))
+            .show(ui, |ui| self.plugin_ui(ui, platform, &plugin, &entries));
+        }
+    }
+
+    fn plugin_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        platform: &Platform,
+        plugin: &str,
+        entries: &BTreeMap<String, Vec<u8>>,
+    ) {
+        egui::Grid::new(("plugin_storage_keys", plugin))
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                for (key, value) in entries {
+                    ui.monospace(key);
+
+                    // Unedited values follow the store, so changes by the plugin show up
+                    let id = (plugin.to_owned(), key.clone());
+                    let stored = encode(value);
+                    let mut text = self.edits.get(&id).cloned().unwrap_or(stored.clone());
+                    if ui.text_edit_singleline(&mut text).changed() {
+                        self.edits.insert(id.clone(), text.clone());
+                    }
+
+                    ui.horizontal(|ui| {
+                        let decoded = decode(&text);
+                        let changed = text != stored;
+
+                        if let Err(e) = &decoded {
+                            ui.colored_label(ui.visuals().error_fg_color, "⚠")
+                                .on_hover_text(e);
+                        }
+                        if ui
+                            .add_enabled(changed && decoded.is_ok(), egui::Button::new("💾"))
+                            .on_hover_text(tr!("storage-save"))
+                            .clicked()
+                        {
+                            if let Ok(value) = decoded {
+                                match platform.storage_set(plugin, key.clone(), value) {
+                                    Ok(()) => {
+                                        self.edits.remove(&id);
+                                        self.errors.remove(plugin);
+                                    }
+                                    Err(e) => {
+                                        self.errors.insert(plugin.to_owned(), e.to_string());
+                                    }
+                                }
+                            }
+                        }
+                        if ui
+                            .button("🗑")
+                            .on_hover_text(tr!("storage-delete"))
+                            .clicked()
+                        {
+                            platform.storage_delete(plugin, key);
+                            self.edits.remove(&id);
+                        }
+                    });
+                    ui.end_row();
+                }
+
+                let (key, value) = self.new.entry(plugin.to_owned()).or_default();
+                ui.add(egui::TextEdit::singleline(key).hint_text(tr!("storage-new-key")));
+                ui.add(egui::TextEdit::singleline(value).hint_text(tr!("storage-new-value")));
+                let decoded = decode(value);
+                if ui
+                    .add_enabled(!key.is_empty() && decoded.is_ok(), egui::Button::new("➕"))
+                    .on_hover_text(tr!("storage-add"))
+                    .clicked()
+                {
+                    if let Ok(decoded) = decoded {
+                        match platform.storage_set(plugin, key.clone(), decoded) {
+                            Ok(()) => {
+                                key.clear();
+                                value.clear();
+                                self.errors.remove(plugin);
+                            }
+                            Err(e) => {
+                                self.errors.insert(plugin.to_owned(), e.to_string());
+                            }
+                        }
+                    }
+                }
+                ui.end_row();
+            });
+
+        if let Some(e) = self.errors.get(plugin) {
+            ui.colored_label(ui.visuals().error_fg_color, e);
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button(tr!("storage-export")).clicked() {
+                let export = Export {
+                    plugin,
+                    entries: entries
+                        .iter()
+                        .map(|(key, value)| (key.as_str(), encode(value)))
+                        .collect(),
+                };
+                let json = serde_json::to_vec_pretty(&export).unwrap_or_default();
+                if let Err(e) = file_dialog::save_file(&format!("{plugin}.storage.json"), &json) {
+                    tracing::error!("Failed to export storage of plugin {plugin}: {e:?}");
+                }
+            }
+
+            if self.confirm_clear.as_deref() == Some(plugin) {
+                ui.label(tr!("storage-clear-question", count = entries.len()));
+                if ui.button(tr!("storage-clear-confirm")).clicked() {
+                    platform.storage_clear(plugin);
+                    self.edits.retain(|(edited, _), _| edited != plugin);
+                    self.confirm_clear = None;
+                }
+                if ui.button(tr!("storage-cancel")).clicked() {
+                    self.confirm_clear = None;
+                }
+            } else if ui.button(tr!("storage-clear")).clicked() {
+                self.confirm_clear = Some(plugin.to_owned());
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_round_trip_as_text_or_hex() {
+        for value in [
+            &b"hello"[..],
+            &[0, 159, 146, 150],
+            b"hex:looks like hex",
+            b"",
+        ] {
+            assert_eq!(decode(&encode(value)).unwrap(), value);
+        }
+
+        assert_eq!(encode(b"hello"), "hello");
+        assert_eq!(encode(&[0, 255]), "hex:00ff");
+        assert!(decode("hex:zz").is_err());
+    }
+}
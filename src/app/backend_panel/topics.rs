@@ -0,0 +1,58 @@
+//! Topic monitor
+//!
+//! Shows every topic on the plugin message bus, with its subscribers, how many messages have
+//! been published, and previews of the most recent ones.
+
+use multinode_i18n::tr;
+
+use super::storage::encode;
+use crate::app::platform::{Platform, Topic};
+
+/// Longest payload preview, in characters
+const PREVIEW: usize = 48;
+
+fn preview(payload: &[u8]) -> String {
+    let text = encode(payload);
+    match text.char_indices().nth(PREVIEW) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
+
+pub fn ui(ui: &mut egui::Ui, platform: &Platform) {
+    let topics = platform.topics();
+    if topics.is_empty() {
+        ui.weak(tr!("messages-none"));
+        return;
+    }
+
+    for (name, topic) in &topics {
+        let Topic {
+            subscribers,
+            count,
+            recent,
+        } = topic;
+
+        egui::CollapsingHeader::new(tr!("messages-topic", topic = name, count = *count))
+            .id_salt(("bus_topic", name))
+            .show(ui, |ui| {
+                let subscribers = subscribers.iter().cloned().collect::<Vec<_>>();
+                ui.label(tr!(
+                    "messages-subscribers",
+                    plugins = subscribers.join(", ")
+                ));
+
+                egui::Grid::new(("bus_topic_messages", name))
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for message in recent.iter().rev() {
+                            ui.label(&message.publisher);
+                            ui.monospace(preview(&message.payload))
+                                .on_hover_text(encode(&message.payload));
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+}
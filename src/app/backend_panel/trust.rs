@@ -0,0 +1,433 @@
+//! Plugin signature verification against trusted publishers.
+//!
+//! A plugin is signed with a publisher's ed25519 key over its wasm bytes. The signature is
+//! either embedded in a [SIGNATURE_SECTION] custom section, in which case it signs the wasm
+//! with that section removed, or stored in a sidecar file picked alongside the plugin.
+//!
+//! Both carry the same 96 bytes, raw or hex encoded: the publisher's 32 byte public key
+//! followed by the 64 byte signature.
+
+use std::sync::{Arc, Mutex};
+
+use ed25519_dalek::{Signature, VerifyingKey};
+use multinode_i18n::tr;
+
+/// Name of the custom section holding an embedded signature
+pub const SIGNATURE_SECTION: &str = "peerpiper-signature";
+
+/// Length of a public key followed by a signature
+const SIGNATURE_LEN: usize = 32 + 64;
+
+/// What to do with plugins which are unsigned or signed by an untrusted publisher.
+///
+/// Plugins with an invalid signature are always blocked, since they have been tampered with.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Policy {
+    /// Load the plugin, but warn about it
+    #[default]
+    Warn,
+
+    /// Refuse to load the plugin
+    Block,
+}
+
+/// A publisher whose plugins are trusted
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Publisher {
+    /// Label shown in the UI
+    pub name: String,
+
+    /// Hex encoded ed25519 public key
+    pub key: String,
+}
+
+/// Outcome of checking a plugin's signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// Signed by a trusted publisher
+    Trusted(String),
+
+    /// No signature was found
+    Unsigned,
+
+    /// Validly signed, but by a publisher who isn't trusted
+    Untrusted(String),
+
+    /// The signature doesn't match the plugin
+    Invalid(String),
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Trusted(name) => write!(f, "signed by {name}"),
+            Verdict::Unsigned => write!(f, "unsigned"),
+            Verdict::Untrusted(key) => write!(f, "signed by untrusted key {key}"),
+            Verdict::Invalid(e) => write!(f, "invalid signature: {e}"),
+        }
+    }
+}
+
+impl Verdict {
+    /// The verdict in the user's language, for the UI rather than the logs
+    fn label(&self) -> String {
+        match self {
+            Verdict::Trusted(name) => tr!("trust-signed-by", name = name),
+            Verdict::Unsigned => tr!("trust-unsigned"),
+            Verdict::Untrusted(key) => tr!("trust-untrusted", key = key),
+            Verdict::Invalid(e) => tr!("trust-invalid", error = e),
+        }
+    }
+}
+
+/// Splits an embedded signature out of the wasm, returning the signature and the wasm
+/// without the signature section.
+///
+/// Modules and components share the same section framing: an 8 byte preamble, then sections
+/// made of an id byte, a LEB128 size, and the contents. Custom sections have id 0 and start
+/// with their name.
+pub fn split_signature(wasm: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    fn leb128(bytes: &[u8], pos: &mut usize) -> Option<usize> {
+        let mut value = 0usize;
+        for shift in (0..35).step_by(7) {
+            let byte = *bytes.get(*pos)?;
+            *pos += 1;
+            value |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    let mut pos = 8;
+    while pos < wasm.len() {
+        let start = pos;
+        let id = wasm[pos];
+        pos += 1;
+        let size = leb128(wasm, &mut pos)?;
+        let end = pos.checked_add(size).filter(|end| *end <= wasm.len())?;
+
+        if id == 0 {
+            let mut name_pos = pos;
+            let name_len = leb128(wasm, &mut name_pos)?;
+            let name = wasm.get(name_pos..name_pos.checked_add(name_len)?)?;
+
+            if name == SIGNATURE_SECTION.as_bytes() {
+                let signature = wasm[name_pos + name_len..end].to_vec();
+                let unsigned = [&wasm[..start], &wasm[end..]].concat();
+                return Some((signature, unsigned));
+            }
+        }
+
+        pos = end;
+    }
+
+    None
+}
+
+/// Decodes a raw or hex encoded key and signature
+fn decode_signature(bytes: &[u8]) -> Result<(VerifyingKey, Signature), String> {
+    let raw = if bytes.len() == SIGNATURE_LEN {
+        bytes.to_vec()
+    } else {
+        let text = std::str::from_utf8(bytes).map_err(|_| "not raw or hex encoded")?;
+        hex::decode(text.trim()).map_err(|_| "not raw or hex encoded")?
+    };
+
+    if raw.len() != SIGNATURE_LEN {
+        return Err(format!("expected {SIGNATURE_LEN} bytes, got {}", raw.len()));
+    }
+
+    let key = VerifyingKey::from_bytes(raw[..32].try_into().unwrap()).map_err(|e| e.to_string())?;
+    let signature = Signature::from_slice(&raw[32..]).map_err(|e| e.to_string())?;
+    Ok((key, signature))
+}
+
+/// The key of the publisher who signed the plugin, once the signature is checked, or `None` if
+/// it's unsigned.
+///
+/// A `sidecar` signature takes precedence over an embedded one.
+fn signed_by(wasm: &[u8], sidecar: Option<&[u8]>) -> Result<Option<VerifyingKey>, String> {
+    let (signature, signed) = match (sidecar, split_signature(wasm)) {
+        (Some(sidecar), _) => (sidecar.to_vec(), wasm.to_vec()),
+        (None, Some((embedded, unsigned))) => (embedded, unsigned),
+        (None, None) => return Ok(None),
+    };
+
+    let (key, signature) = decode_signature(&signature)?;
+    key.verify_strict(&signed, &signature)
+        .map_err(|e| e.to_string())?;
+    Ok(Some(key))
+}
+
+/// Hex encoded key of the publisher who validly signed the plugin, trusted or not
+#[cfg(not(target_arch = "wasm32"))]
+pub fn publisher_key(wasm: &[u8], sidecar: Option<&[u8]>) -> Option<String> {
+    let key = signed_by(wasm, sidecar).ok()??;
+    Some(hex::encode(key.as_bytes()))
+}
+
+/// Checks the plugin's signature against the trusted `publishers`
+pub fn verify(publishers: &[Publisher], wasm: &[u8], sidecar: Option<&[u8]>) -> Verdict {
+    let key = match signed_by(wasm, sidecar) {
+        Ok(Some(key)) => hex::encode(key.as_bytes()),
+        Ok(None) => return Verdict::Unsigned,
+        Err(e) => return Verdict::Invalid(e),
+    };
+
+    match publishers
+        .iter()
+        .find(|publisher| publisher.key.trim().eq_ignore_ascii_case(&key))
+    {
+        Some(publisher) => Verdict::Trusted(publisher.name.clone()),
+        None => Verdict::Untrusted(key),
+    }
+}
+
+/// A snapshot of the trust settings, for checking plugins as they are loaded
+#[derive(Clone)]
+pub struct Checker {
+    policy: Policy,
+    publishers: Vec<Publisher>,
+    last: Arc<Mutex<Option<(String, Verdict, bool)>>>,
+}
+
+impl Checker {
+    /// Checks the named plugin and records the verdict. Returns whether it may be loaded.
+    pub fn allow(&self, name: &str, wasm: &[u8], sidecar: Option<&[u8]>) -> bool {
+        let verdict = verify(&self.publishers, wasm, sidecar);
+        let allowed = match &verdict {
+            Verdict::Trusted(_) => true,
+            Verdict::Invalid(_) => false,
+            Verdict::Unsigned | Verdict::Untrusted(_) => self.policy == Policy::Warn,
+        };
+
+        match (&verdict, allowed) {
+            (Verdict::Trusted(_), _) => tracing::info!("Plugin {name} is {verdict}"),
+            (_, true) => tracing::warn!("Loading plugin {name}, which is {verdict}"),
+            (_, false) => tracing::error!("Blocked plugin {name}, which is {verdict}"),
+        }
+
+        *self.last.lock().unwrap() = Some((name.to_owned(), verdict, allowed));
+        allowed
+    }
+}
+
+/// Persisted trust settings
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Trust {
+    policy: Policy,
+    publishers: Vec<Publisher>,
+
+    /// Publisher being added
+    #[serde(skip)]
+    new: Publisher,
+
+    /// Last checked plugin, its verdict, and whether it was loaded
+    #[serde(skip)]
+    last: Arc<Mutex<Option<(String, Verdict, bool)>>>,
+}
+
+impl Default for Trust {
+    fn default() -> Self {
+        Self {
+            policy: Policy::default(),
+            publishers: Vec::new(),
+            new: Publisher {
+                name: String::new(),
+                key: String::new(),
+            },
+            last: Default::default(),
+        }
+    }
+}
+
+impl Trust {
+    /// A [Checker] using the current settings
+    pub fn checker(&self) -> Checker {
+        Checker {
+            policy: self.policy,
+            publishers: self.publishers.clone(),
+            last: self.last.clone(),
+        }
+    }
+
+    /// Shows the settings, returning whether they changed
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let before = (self.policy, self.publishers.clone());
+
+        ui.horizontal(|ui| {
+            ui.label(tr!("trust-policy"));
+            ui.selectable_value(&mut self.policy, Policy::Warn, tr!("trust-warn"));
+            ui.selectable_value(&mut self.policy, Policy::Block, tr!("trust-block"));
+        });
+
+        let last = self.last.lock().unwrap().clone();
+        if let Some((name, verdict, allowed)) = last {
+            let (name, verdict_text) = (name.as_str(), verdict.label());
+            match (&verdict, allowed) {
+                (Verdict::Trusted(_), _) => ui.label(tr!(
+                    "trust-last-trusted",
+                    name = name,
+                    verdict = verdict_text
+                )),
+                (_, true) => ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    tr!("trust-last-warned", name = name, verdict = verdict_text),
+                ),
+                (_, false) => ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    tr!("trust-last-blocked", name = name, verdict = verdict_text),
+                ),
+            };
+
+            if let Verdict::Untrusted(key) = verdict {
+                if ui.button(tr!("trust-this-publisher")).clicked() {
+                    self.new.key = key;
+                }
+            }
+        }
+
+        ui.collapsing(tr!("trust-publishers"), |ui| {
+            let mut remove = None;
+            for (i, publisher) in self.publishers.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui
+                        .small_button("🗑")
+                        .on_hover_text(tr!("trust-remove"))
+                        .clicked()
+                    {
+                        remove = Some(i);
+                    }
+                    ui.label(&publisher.name);
+                    ui.monospace(&publisher.key).on_hover_text(&publisher.key);
+                });
+            }
+            if let Some(i) = remove {
+                self.publishers.remove(i);
+            }
+
+            ui.add(
+                egui::TextEdit::singleline(&mut self.new.name).hint_text(tr!("trust-name-hint")),
+            );
+            ui.add(egui::TextEdit::singleline(&mut self.new.key).hint_text(tr!("trust-key-hint")));
+
+            let key = hex::decode(self.new.key.trim())
+                .ok()
+                .and_then(|key| <[u8; 32]>::try_from(key).ok())
+                .filter(|key| VerifyingKey::from_bytes(key).is_ok());
+
+            if ui
+                .add_enabled(
+                    key.is_some() && !self.new.name.trim().is_empty(),
+                    egui::Button::new(tr!("trust-add")),
+                )
+                .clicked()
+            {
+                self.publishers.push(Publisher {
+                    name: self.new.name.trim().to_owned(),
+                    key: self.new.key.trim().to_lowercase(),
+                });
+                self.new.name.clear();
+                self.new.key.clear();
+            }
+        });
+
+        (self.policy, &self.publishers) != (before.0, &before.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    const WASM: &[u8] = b"\0asm\x01\0\0\0";
+
+    fn publisher(key: &SigningKey) -> Publisher {
+        Publisher {
+            name: "alice".into(),
+            key: hex::encode(key.verifying_key().as_bytes()),
+        }
+    }
+
+    fn signature(key: &SigningKey, wasm: &[u8]) -> Vec<u8> {
+        [
+            key.verifying_key().as_bytes().as_slice(),
+            &key.sign(wasm).to_bytes(),
+        ]
+        .concat()
+    }
+
+    /// Appends a signature custom section to the wasm
+    fn embed(key: &SigningKey, wasm: &[u8]) -> Vec<u8> {
+        let contents = [
+            &[SIGNATURE_SECTION.len() as u8],
+            SIGNATURE_SECTION.as_bytes(),
+            &signature(key, wasm),
+        ]
+        .concat();
+        [wasm, &[0, contents.len() as u8], &contents].concat()
+    }
+
+    #[test]
+    fn embedded_signature_from_trusted_publisher() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let signed = embed(&key, WASM);
+
+        assert_eq!(split_signature(&signed).unwrap().1, WASM);
+        assert_eq!(
+            verify(&[publisher(&key)], &signed, None),
+            Verdict::Trusted("alice".into())
+        );
+    }
+
+    #[test]
+    fn sidecar_signature_raw_or_hex() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let sidecar = signature(&key, WASM);
+
+        assert_eq!(
+            verify(&[publisher(&key)], WASM, Some(&sidecar)),
+            Verdict::Trusted("alice".into())
+        );
+        assert_eq!(
+            verify(
+                &[publisher(&key)],
+                WASM,
+                Some(hex::encode(&sidecar).as_bytes())
+            ),
+            Verdict::Trusted("alice".into())
+        );
+    }
+
+    #[test]
+    fn untrusted_unsigned_and_tampered() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let other = SigningKey::from_bytes(&[9; 32]);
+
+        assert!(matches!(
+            verify(&[publisher(&other)], &embed(&key, WASM), None),
+            Verdict::Untrusted(_)
+        ));
+        assert_eq!(verify(&[publisher(&key)], WASM, None), Verdict::Unsigned);
+
+        let mut tampered = embed(&key, WASM);
+        tampered[4] = 2;
+        assert!(matches!(
+            verify(&[publisher(&key)], &tampered, None),
+            Verdict::Invalid(_)
+        ));
+    }
+
+    #[test]
+    fn policy_decides_untrusted_plugins() {
+        let mut trust = Trust::default();
+        assert!(trust.checker().allow("p", WASM, None));
+
+        trust.policy = Policy::Block;
+        assert!(!trust.checker().allow("p", WASM, None));
+    }
+}
@@ -0,0 +1,452 @@
+//! Wallet section of the backend panel
+//!
+//! Creates, recovers, unlocks and locks the wallet, changes its password, backs up its seed
+//! phrase, manages the identities derived from it, and asks the user to approve each plugin's
+//! request to sign. The unlocked wallet locks itself once the user has left the app idle for a
+//! while. Plugins the user chose to always allow are remembered by their publisher
+//! and name, or by the hash of their code if unsigned, and listed here to be revoked.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use multinode_i18n::tr;
+use zeroize::Zeroizing;
+
+use super::{backup, identities, password, storage};
+use crate::app::platform::Platform;
+use crate::app::wallet::{Seed, SignRequest, Vault, MIN_PASSWORD_LEN};
+
+/// How many bytes of a payload the signing dialog previews
+const PREVIEW_LEN: usize = 256;
+
+/// Minutes without input after which the unlocked wallet locks itself, or 0 to never
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+struct AutoLock(u32);
+
+impl Default for AutoLock {
+    fn default() -> Self {
+        Self(15)
+    }
+}
+
+impl AutoLock {
+    /// Seconds left before locking, after `idle` seconds without input, or `None` if never
+    fn left(self, idle: f64) -> Option<f64> {
+        (self.0 > 0).then(|| f64::from(self.0) * 60.0 - idle)
+    }
+}
+
+/// Passwords typed to change the wallet's password
+#[derive(Default)]
+struct ChangePassword {
+    old: String,
+    new: String,
+    confirm: String,
+
+    /// Outcome of the last attempt
+    result: Option<Result<(), String>>,
+}
+
+impl ChangePassword {
+    fn ui(&mut self, ui: &mut egui::Ui, vault: &mut Vault, platform: &Platform) {
+        ui.label(tr!("password-old"));
+        ui.push_id("old_password", |ui| {
+            ui.add(password::password(&mut self.old))
+        });
+        ui.label(tr!("password-new"));
+        ui.push_id("new_password", |ui| {
+            ui.add(
+                password::Password::new(&mut self.new)
+                    .confirm(&mut self.confirm)
+                    .strength_meter(MIN_PASSWORD_LEN),
+            )
+        });
+        let mismatch = self.new != self.confirm;
+
+        let ready = !self.old.is_empty() && !self.new.is_empty() && !mismatch;
+        if ui
+            .add_enabled(ready, egui::Button::new(tr!("password-change")))
+            .clicked()
+        {
+            let result = vault
+                .change_password(&self.old, &self.new)
+                .map_err(|e| e.to_string())
+                .and_then(|changed| {
+                    save(platform, &changed)?;
+                    *vault = changed;
+                    Ok(())
+                });
+            if result.is_ok() {
+                *self = Self::default();
+            }
+            self.result = Some(result);
+        }
+
+        match &self.result {
+            Some(Ok(())) => {
+                ui.label(tr!("password-changed"));
+            }
+            Some(Err(e)) => {
+                ui.colored_label(ui.visuals().error_fg_color, e);
+            }
+            None => {}
+        }
+    }
+}
+
+/// Saves `vault` with the platform, see [`Platform::save_vault`]
+fn save(platform: &Platform, vault: &Vault) -> Result<(), String> {
+    let saved = serde_json::to_string(vault)
+        .map_err(|e| e.to_string())
+        .and_then(|vault| platform.save_vault(&vault).map_err(|e| e.to_string()));
+    saved.map_err(|error| tr!("wallet-save-failed", error = error))
+}
+
+/// State of the wallet section
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct WalletPanel {
+    /// The encrypted seed. The platform saves it as soon as it changes, and that copy wins.
+    vault: Vault,
+
+    /// Plugins which may sign without asking: the names they were allowed under, by the id
+    /// their key goes by
+    always_allow: BTreeMap<String, String>,
+
+    /// Labelled keys derived from the seed, one of which is the node's
+    identities: identities::Identities,
+
+    /// Whether the user verified their backup of the seed phrase
+    backed_up: bool,
+
+    auto_lock: AutoLock,
+
+    /// When the user last gave any input, in [egui::InputState::time]
+    #[serde(skip)]
+    last_input: f64,
+
+    #[serde(skip)]
+    backup: backup::Backup,
+
+    /// Whether a new wallet is recovered from a seed phrase rather than generated
+    #[serde(skip)]
+    recovering: bool,
+
+    /// The seed phrase being typed to recover the wallet
+    #[serde(skip)]
+    phrase: Zeroizing<String>,
+
+    #[serde(skip)]
+    change_password: ChangePassword,
+
+    #[serde(skip)]
+    password: String,
+
+    /// The password typed again, when creating the wallet
+    #[serde(skip)]
+    confirm: String,
+
+    /// The seed while the wallet is unlocked
+    #[serde(skip)]
+    seed: Option<Seed>,
+
+    /// Why the last attempt to create or unlock the wallet failed
+    #[serde(skip)]
+    error: Option<String>,
+
+    /// Whether the saved vault and the always allowed plugins were synced with the platform
+    /// since starting
+    #[serde(skip)]
+    synced: bool,
+
+    /// Whether to always allow the plugin of the request being shown
+    #[serde(skip)]
+    remember: bool,
+}
+
+impl WalletPanel {
+    pub fn ui(&mut self, ui: &mut egui::Ui, platform: &Platform) {
+        self.sync(platform);
+
+        if self.seed.is_some() {
+            self.unlocked_ui(ui, platform);
+            return;
+        }
+
+        let creating = self.vault.is_empty();
+        if creating {
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.recovering, false, tr!("wallet-create"));
+                ui.selectable_value(&mut self.recovering, true, tr!("wallet-recover"));
+            });
+            if self.recovering {
+                ui.add(
+                    egui::TextEdit::multiline(&mut *self.phrase)
+                        .hint_text(tr!("wallet-phrase-hint"))
+                        .desired_rows(3),
+                );
+            }
+            ui.add(
+                password::Password::new(&mut self.password)
+                    .confirm(&mut self.confirm)
+                    .strength_meter(MIN_PASSWORD_LEN),
+            );
+        } else {
+            ui.label(tr!("wallet-unlock"));
+            ui.add(password::password(&mut self.password));
+        }
+
+        let result = if creating {
+            let label = if self.recovering {
+                tr!("wallet-recover-button")
+            } else {
+                tr!("wallet-create-button")
+            };
+            let confirmed = self.password == self.confirm;
+            ui.add_enabled(confirmed, egui::Button::new(label))
+                .clicked()
+                .then(|| {
+                    let seed = if self.recovering {
+                        Seed::from_mnemonic(&self.phrase)
+                    } else {
+                        Ok(Seed::generate())
+                    };
+                    let seed = seed.map_err(|e| e.to_string())?;
+                    let vault = Vault::seal(&seed, &self.password).map_err(|e| e.to_string())?;
+                    save(platform, &vault)?;
+                    self.vault = vault;
+                    // A recovered phrase is evidently backed up already
+                    self.backed_up = self.recovering;
+                    self.phrase = Default::default();
+                    Ok(seed)
+                })
+        } else {
+            ui.button(tr!("wallet-unlock-button"))
+                .clicked()
+                .then(|| self.vault.unlock(&self.password).map_err(|e| e.to_string()))
+        };
+
+        match result {
+            Some(Ok(seed)) => {
+                self.password.clear();
+                self.confirm.clear();
+                self.error = None;
+                platform.set_wallet(Some(seed.clone()));
+                self.seed = Some(seed);
+            }
+            Some(Err(e)) => self.error = Some(e),
+            None => {}
+        }
+
+        if let Some(error) = &self.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+    }
+
+    fn unlocked_ui(&mut self, ui: &mut egui::Ui, platform: &Platform) {
+        ui.horizontal(|ui| {
+            ui.label(tr!("wallet-unlocked"));
+            if ui.button(tr!("wallet-lock")).clicked() {
+                self.lock(platform);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr!("wallet-auto-lock"));
+            ui.add(
+                egui::DragValue::new(&mut self.auto_lock.0)
+                    .range(0..=24 * 60)
+                    .custom_formatter(|minutes, _| match minutes as u32 {
+                        0 => tr!("wallet-auto-lock-never"),
+                        minutes => tr!("wallet-auto-lock-minutes", minutes = minutes),
+                    }),
+            );
+        });
+
+        if let Some(seed) = &self.seed {
+            let title = if self.backed_up {
+                tr!("wallet-seed-phrase")
+            } else {
+                tr!("wallet-not-backed-up")
+            };
+            ui.collapsing(title, |ui| {
+                if self.backup.ui(ui, seed) {
+                    self.backed_up = true;
+                }
+            });
+
+            ui.collapsing(tr!("wallet-identities"), |ui| self.identities.ui(ui, seed));
+        }
+
+        ui.collapsing(tr!("wallet-change-password"), |ui| {
+            self.change_password.ui(ui, &mut self.vault, platform)
+        });
+
+        if self.always_allow.is_empty() {
+            return;
+        }
+        ui.label(tr!("wallet-always-allowed"));
+        let mut revoked = None;
+        for (id, plugin) in &self.always_allow {
+            ui.horizontal(|ui| {
+                ui.monospace(plugin).on_hover_text(id);
+                if ui
+                    .button("🗑")
+                    .on_hover_text(tr!("wallet-ask-every-time"))
+                    .clicked()
+                {
+                    revoked = Some(id.clone());
+                }
+            });
+        }
+        if let Some(id) = revoked {
+            self.always_allow.remove(&id);
+            self.sync_always_allow(platform);
+        }
+    }
+
+    /// Forgets the seed and everything shown from it
+    pub fn lock(&mut self, platform: &Platform) {
+        if self.seed.take().is_some() {
+            self.backup.hide();
+            self.identities.forget();
+            self.change_password = Default::default();
+            platform.set_wallet(None::<Seed>);
+        }
+    }
+
+    /// Locks the unlocked wallet once the user has left the app idle for long enough. Returns
+    /// whether it locked just now.
+    pub fn auto_lock(&mut self, ctx: &egui::Context, platform: &Platform) -> bool {
+        let (now, input) = ctx.input(|i| (i.time, !i.events.is_empty()));
+        if input {
+            self.last_input = now;
+        }
+
+        if self.seed.is_none() {
+            return false;
+        }
+        match self.auto_lock.left(now - self.last_input) {
+            Some(left) if left <= 0.0 => {
+                self.lock(platform);
+                true
+            }
+            Some(left) => {
+                // Frames stop while nothing happens, so ask for the one which locks
+                ctx.request_repaint_after(Duration::from_secs_f64(left));
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Syncs with the platform again, as it was restarted
+    pub fn resync(&mut self) {
+        self.synced = false;
+    }
+
+    /// Takes the vault the platform saved, and hands it the persisted always allowed plugins,
+    /// once
+    fn sync(&mut self, platform: &Platform) {
+        if self.synced {
+            return;
+        }
+        match platform
+            .saved_vault()
+            .map(|saved| serde_json::from_str(&saved))
+        {
+            Some(Ok(saved)) => self.vault = saved,
+            Some(Err(e)) => {
+                tracing::error!("Failed to read the saved wallet, keeping the last: {e}")
+            }
+            // Only saved with the app state, before the platform saved it
+            None if !self.vault.is_empty() => {
+                if let Err(e) = save(platform, &self.vault) {
+                    tracing::error!("{e}");
+                }
+            }
+            None => {}
+        }
+        self.sync_always_allow(platform);
+        self.synced = true;
+    }
+
+    fn sync_always_allow(&self, platform: &Platform) {
+        platform.set_always_allow(self.always_allow.keys().cloned().collect());
+    }
+
+    /// Shows the oldest request to sign, if any, for the user to approve or deny
+    pub fn show_sign_request(&mut self, ctx: &egui::Context, platform: &Platform) {
+        self.sync(platform);
+
+        let request: Option<SignRequest> = platform.sign_request();
+        let Some(SignRequest {
+            plugin,
+            id,
+            payload,
+        }) = request
+        else {
+            return;
+        };
+
+        let mut answer = None;
+        egui::Window::new(tr!("sign-title"))
+            .id(egui::Id::new("sign_request"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr!("sign-plugin"));
+                    ui.strong(&plugin).on_hover_text(&id);
+                    ui.label(tr!("sign-asks"));
+                });
+
+                let mut preview = storage::encode(&payload[..payload.len().min(PREVIEW_LEN)]);
+                if payload.len() > PREVIEW_LEN {
+                    preview.push('…');
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(160.0)
+                    .show(ui, |ui| {
+                        ui.add(
+                            egui::TextEdit::multiline(&mut preview.as_str())
+                                .code_editor()
+                                .desired_width(f32::INFINITY),
+                        );
+                    });
+                ui.weak(tr!("sign-bytes", count = payload.len()));
+
+                ui.checkbox(&mut self.remember, tr!("sign-always-allow"));
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("sign-approve")).clicked() {
+                        answer = Some(true);
+                    }
+                    if ui.button(tr!("sign-deny")).clicked() {
+                        answer = Some(false);
+                    }
+                });
+            });
+
+        if let Some(approve) = answer {
+            if approve && self.remember {
+                self.always_allow.insert(id, plugin);
+                self.sync_always_allow(platform);
+            }
+            self.remember = false;
+            platform.answer_sign_request(approve);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_locks_after_idling() {
+        let auto_lock = AutoLock::default();
+        assert_eq!(auto_lock.left(0.0), Some(15.0 * 60.0));
+        assert_eq!(auto_lock.left(15.0 * 60.0), Some(0.0));
+        assert_eq!(AutoLock(0).left(f64::MAX), None);
+    }
+}
@@ -0,0 +1,303 @@
+//! Keyboard shortcuts and the command palette
+//!
+//! Everything the app can do from the keyboard is a [`Command`]. Built-in commands may have a
+//! shortcut, and Ctrl+K (⌘K on Mac) opens a palette which fuzzy-searches every command by its
+//! title, including the ones loaded plugins contribute: their panels, and the actions they list.
+
+use std::cmp::Reverse;
+
+use multinode_i18n::tr;
+
+use super::views::View;
+use super::IS_WEB;
+
+/// Id of the node log's search field, for the command focusing it
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) const LOG_SEARCH: &str = "log_search";
+
+/// Most commands the palette lists at once
+const MAX_SHOWN: usize = 12;
+
+/// Something the user can do from the keyboard
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Palette,
+    ShowView(View),
+    Settings,
+    OpenPluginFile,
+    /// Only a native node keeps its log
+    #[cfg(not(target_arch = "wasm32"))]
+    FocusLogSearch,
+    LockWallet,
+    ResetLayout,
+    ExportSettings,
+    ImportSettings,
+    ResetSettings,
+    Quit,
+
+    /// Opens the named plugin's panel. Only native plugins contribute commands.
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    OpenPanel {
+        name: String,
+        title: String,
+    },
+
+    /// Shows the named plugin in the detail view
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    SelectPlugin(String),
+
+    /// Runs the action `id` the named plugin lists
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    RunAction {
+        plugin: String,
+        id: String,
+        title: String,
+    },
+}
+
+impl Command {
+    /// The commands the app always has
+    pub fn builtin() -> Vec<Command> {
+        let mut commands = vec![Command::Palette];
+        commands.extend(
+            [
+                View::Wallet,
+                View::Plugins,
+                View::Node,
+                View::Log,
+                View::Peers,
+            ]
+            .map(Command::ShowView),
+        );
+        commands.extend([
+            Command::Settings,
+            Command::OpenPluginFile,
+            #[cfg(not(target_arch = "wasm32"))]
+            Command::FocusLogSearch,
+            Command::LockWallet,
+            Command::ResetLayout,
+            Command::ExportSettings,
+            Command::ImportSettings,
+            Command::ResetSettings,
+        ]);
+        if !IS_WEB {
+            commands.push(Command::Quit);
+        }
+        commands
+    }
+
+    pub fn title(&self) -> String {
+        match self {
+            Command::Palette => tr!("command-palette"),
+            Command::ShowView(view) => tr!("command-show-view", view = view.title(&[])),
+            Command::Settings => tr!("command-settings"),
+            Command::OpenPluginFile => tr!("command-open-plugin-file"),
+            #[cfg(not(target_arch = "wasm32"))]
+            Command::FocusLogSearch => tr!("command-focus-log-search"),
+            Command::LockWallet => tr!("command-lock-wallet"),
+            Command::ResetLayout => tr!("command-reset-layout"),
+            Command::ExportSettings => tr!("command-export-settings"),
+            Command::ImportSettings => tr!("command-import-settings"),
+            Command::ResetSettings => tr!("command-reset-settings"),
+            Command::Quit => tr!("command-quit"),
+            Command::OpenPanel { title, .. } => tr!("command-open-panel", title = title),
+            Command::SelectPlugin(name) => tr!("command-select-plugin", name = name),
+            Command::RunAction { plugin, title, .. } => {
+                tr!("command-run-action", plugin = plugin, title = title)
+            }
+        }
+    }
+
+    /// The keys running the command, if any
+    pub fn shortcut(&self) -> Option<egui::KeyboardShortcut> {
+        use egui::Key;
+
+        let key = match self {
+            Command::Palette => Key::K,
+            Command::ShowView(View::Wallet) => Key::Num1,
+            Command::ShowView(View::Plugins) => Key::Num2,
+            Command::ShowView(View::Node) => Key::Num3,
+            Command::ShowView(View::Log) => Key::Num4,
+            Command::ShowView(View::Peers) => Key::Num5,
+            Command::Settings => Key::Comma,
+            Command::OpenPluginFile => Key::O,
+            #[cfg(not(target_arch = "wasm32"))]
+            Command::FocusLogSearch => Key::F,
+            Command::LockWallet => Key::L,
+            Command::Quit => Key::Q,
+            _ => return None,
+        };
+        Some(egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, key))
+    }
+}
+
+/// The command whose shortcut was pressed, if any. The shortcut is consumed, so widgets don't
+/// also see it.
+pub fn pressed(ctx: &egui::Context, commands: &[Command]) -> Option<Command> {
+    ctx.input_mut(|i| {
+        commands
+            .iter()
+            .find(|command| {
+                command
+                    .shortcut()
+                    .is_some_and(|shortcut| i.consume_shortcut(&shortcut))
+            })
+            .cloned()
+    })
+}
+
+/// How well `query` matches `text`, if all its characters appear in `text` in order, ignoring
+/// case and spaces. Characters starting a word or following the previous match score higher.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut start = 0;
+
+    let query = query.to_lowercase();
+    for (i, c) in query.chars().filter(|c| !c.is_whitespace()).enumerate() {
+        let found = start + text[start..].iter().position(|&t| t == c)?;
+        score += 1;
+        if i > 0 && found == start {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        start = found + 1;
+    }
+    Some(score)
+}
+
+/// The command palette
+#[derive(Default)]
+pub struct Palette {
+    open: bool,
+
+    /// What the user typed
+    query: String,
+
+    /// Index of the highlighted command among the matches
+    selected: usize,
+}
+
+impl Palette {
+    pub fn toggle(&mut self) {
+        *self = Self {
+            open: !self.open,
+            ..Default::default()
+        };
+    }
+
+    /// Shows the palette, if open. Returns the command the user picked.
+    pub fn show(&mut self, ctx: &egui::Context, commands: &[Command]) -> Option<Command> {
+        if !self.open {
+            return None;
+        }
+
+        let mut matches: Vec<(u32, String, &Command)> = commands
+            .iter()
+            .filter(|command| **command != Command::Palette)
+            .filter_map(|command| {
+                let title = command.title();
+                fuzzy_score(&self.query, &title).map(|score| (score, title, command))
+            })
+            .collect();
+        matches.sort_by_key(|(score, title, _)| (Reverse(*score), title.len()));
+        matches.truncate(MAX_SHOWN);
+
+        // Taken before the text field sees them, so Enter doesn't just leave the field
+        let (up, down, enter, escape) = ctx.input_mut(|i| {
+            use egui::{Key, Modifiers};
+            (
+                i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                i.consume_key(Modifiers::NONE, Key::ArrowDown),
+                i.consume_key(Modifiers::NONE, Key::Enter),
+                i.consume_key(Modifiers::NONE, Key::Escape),
+            )
+        });
+        if down {
+            self.selected += 1;
+        }
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        self.selected = self.selected.min(matches.len().saturating_sub(1));
+
+        let mut chosen = None;
+        egui::Window::new(tr!("command-palette"))
+            .id(egui::Id::new("command_palette"))
+            .title_bar(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 60.0))
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text(tr!("palette-hint"))
+                        .desired_width(400.0),
+                );
+                response.request_focus();
+                if response.changed() {
+                    self.selected = 0;
+                }
+
+                if matches.is_empty() {
+                    ui.weak(tr!("palette-no-match"));
+                }
+                for (i, (_, title, command)) in matches.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.selectable_label(i == self.selected, title).clicked() {
+                            chosen = Some((*command).clone());
+                        }
+                        if let Some(shortcut) = command.shortcut() {
+                            ui.with_layout(
+                                egui::Layout::right_to_left(egui::Align::Center),
+                                |ui| ui.weak(ctx.format_shortcut(&shortcut)),
+                            );
+                        }
+                    });
+                }
+            });
+
+        if enter {
+            chosen = matches
+                .get(self.selected)
+                .map(|(_, _, command)| (*command).clone());
+        }
+        if escape || chosen.is_some() {
+            self.open = false;
+        }
+        chosen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_fuzzy_matches() {
+        assert_eq!(fuzzy_score("", "Lock wallet"), Some(0));
+        assert!(fuzzy_score("lw", "Lock wallet").is_some());
+        assert!(fuzzy_score("LOCK", "Lock wallet").is_some());
+        assert_eq!(fuzzy_score("tl", "Lock wallet"), None);
+        assert_eq!(fuzzy_score("xyz", "Lock wallet"), None);
+
+        // Word starts and runs beat scattered characters
+        assert!(fuzzy_score("lock", "Lock wallet") > fuzzy_score("lock", "Load a clock"));
+        assert!(fuzzy_score("ow", "Open wallet") > fuzzy_score("ow", "Show log"));
+    }
+
+    #[test]
+    fn shortcuts_are_unique() {
+        let shortcuts: Vec<_> = Command::builtin()
+            .iter()
+            .filter_map(Command::shortcut)
+            .collect();
+        for (i, shortcut) in shortcuts.iter().enumerate() {
+            assert!(
+                !shortcuts[i + 1..].contains(shortcut),
+                "{shortcut:?} is taken twice"
+            );
+        }
+    }
+}
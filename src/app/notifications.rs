@@ -0,0 +1,245 @@
+//! Notifications of node events
+//!
+//! Important events, like a plugin crashing or the wallet locking itself, raise a notification
+//! while the window isn't focused: from the OS natively, and through the browser's Notifications
+//! API on the web. The [`Watcher`] notices them by comparing the node with how it was on the
+//! last frame, and each [`Category`] of them can be turned off in the settings.
+//!
+//! Only a native node runs plugins, so plugins crashing or asking to sign only notify there.
+//! Peers connecting have a category too, but the node doesn't tell the app about its peers yet,
+//! so its toggle is greyed out and says so.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::BTreeSet;
+
+use multinode_i18n::tr;
+
+#[cfg(not(target_arch = "wasm32"))]
+use super::wallet::SignRequest;
+use super::Platform;
+
+/// A kind of event raising notifications
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    /// A plugin failed or was stopped for going over its limits
+    #[cfg(not(target_arch = "wasm32"))]
+    PluginCrashed,
+
+    /// The wallet locked itself, as the app was left idle
+    WalletLocked,
+
+    /// A plugin asks to sign with the wallet, waiting for the user to approve
+    #[cfg(not(target_arch = "wasm32"))]
+    SignRequest,
+
+    /// A peer connected to the node
+    PeerConnected,
+}
+
+impl Category {
+    const ALL: &'static [Category] = &[
+        #[cfg(not(target_arch = "wasm32"))]
+        Category::PluginCrashed,
+        Category::WalletLocked,
+        #[cfg(not(target_arch = "wasm32"))]
+        Category::SignRequest,
+        Category::PeerConnected,
+    ];
+
+    /// What the toggle of the category says
+    fn label(self) -> String {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Category::PluginCrashed => tr!("settings-notify-plugin-crashed"),
+            Category::WalletLocked => tr!("settings-notify-wallet-locked"),
+            #[cfg(not(target_arch = "wasm32"))]
+            Category::SignRequest => tr!("settings-notify-sign-request"),
+            Category::PeerConnected => tr!("settings-notify-peer-connected"),
+        }
+    }
+
+    /// Whether the app learns of the category's events. The node keeps its peers to itself.
+    fn available(self) -> bool {
+        self != Category::PeerConnected
+    }
+}
+
+/// Which categories raise notifications, all of them until turned off. The settings are shared
+/// with the web app, which keeps those of native only categories as they are.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub struct Notifications {
+    plugin_crashed: bool,
+    wallet_locked: bool,
+    sign_request: bool,
+    peer_connected: bool,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Self {
+            plugin_crashed: true,
+            wallet_locked: true,
+            sign_request: true,
+            peer_connected: true,
+        }
+    }
+}
+
+impl Notifications {
+    /// Whether the category raises notifications
+    pub fn enabled(&self, category: Category) -> bool {
+        match category {
+            #[cfg(not(target_arch = "wasm32"))]
+            Category::PluginCrashed => self.plugin_crashed,
+            Category::WalletLocked => self.wallet_locked,
+            #[cfg(not(target_arch = "wasm32"))]
+            Category::SignRequest => self.sign_request,
+            Category::PeerConnected => self.peer_connected,
+        }
+    }
+
+    fn toggle(&mut self, category: Category) -> &mut bool {
+        match category {
+            #[cfg(not(target_arch = "wasm32"))]
+            Category::PluginCrashed => &mut self.plugin_crashed,
+            Category::WalletLocked => &mut self.wallet_locked,
+            #[cfg(not(target_arch = "wasm32"))]
+            Category::SignRequest => &mut self.sign_request,
+            Category::PeerConnected => &mut self.peer_connected,
+        }
+    }
+
+    /// The toggles of the categories, and on the web, whether the browser lets the app notify
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.label(tr!("settings-notifications"));
+        ui.indent("notifications", |ui| {
+            for &category in Category::ALL {
+                let label = category.label();
+                ui.add_enabled(
+                    category.available(),
+                    egui::Checkbox::new(self.toggle(category), label),
+                )
+                .on_disabled_hover_text(tr!("settings-notify-unavailable"));
+            }
+
+            #[cfg(target_arch = "wasm32")]
+            permission_ui(ui);
+        });
+    }
+}
+
+/// Asks the browser to let the app notify, until it's allowed or refused
+#[cfg(target_arch = "wasm32")]
+fn permission_ui(ui: &mut egui::Ui) {
+    use multinode_web::notify::{self, Permission};
+
+    match notify::permission() {
+        Permission::Ask => {
+            if ui.button(tr!("settings-notify-allow")).clicked() {
+                let ctx = ui.ctx().clone();
+                notify::request_permission(move || ctx.request_repaint());
+            }
+        }
+        Permission::Denied => {
+            ui.weak(tr!("settings-notify-denied"));
+        }
+        Permission::Unsupported => {
+            ui.weak(tr!("settings-notify-unsupported"));
+        }
+        Permission::Granted => {}
+    }
+}
+
+/// Notices node events, and raises notifications of them
+#[derive(Default)]
+pub struct Watcher {
+    /// Plugins which crashed, so each crash is only notified once
+    #[cfg(not(target_arch = "wasm32"))]
+    crashed: BTreeSet<String>,
+
+    /// Whether a request to sign was waiting on the last frame
+    #[cfg(not(target_arch = "wasm32"))]
+    signing: bool,
+}
+
+impl Watcher {
+    /// Compares the node with how it was on the last frame, notifying of what changed, and of
+    /// the wallet if it `auto_locked` this frame
+    pub fn update(
+        &mut self,
+        ctx: &egui::Context,
+        platform: &Platform,
+        settings: &Notifications,
+        auto_locked: bool,
+    ) {
+        // The user sees what happens while they're looking
+        let away = !ctx.input(|i| i.viewport().focused.unwrap_or(true));
+        let notify = |category: Category, title: String, body: String| {
+            if away && settings.enabled(category) {
+                platform.notify(&title, &body);
+            }
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            use super::platform::PluginStatus;
+
+            let mut crashed = BTreeSet::new();
+            for (name, status) in platform.plugins() {
+                if let PluginStatus::Failed(_) | PluginStatus::LimitExceeded(_) = status {
+                    if !self.crashed.contains(&name) {
+                        let body = tr!("notify-plugin-crashed", name = name, reason = status);
+                        notify(
+                            Category::PluginCrashed,
+                            tr!("notify-plugin-crashed-title"),
+                            body,
+                        );
+                    }
+                    crashed.insert(name);
+                }
+            }
+            self.crashed = crashed;
+
+            let request: Option<SignRequest> = platform.sign_request();
+            if let (Some(request), false) = (&request, self.signing) {
+                let body = tr!("notify-sign-request", plugin = request.plugin);
+                notify(
+                    Category::SignRequest,
+                    tr!("notify-sign-request-title"),
+                    body,
+                );
+            }
+            self.signing = request.is_some();
+        }
+
+        if auto_locked {
+            let body = tr!("notify-wallet-locked");
+            notify(
+                Category::WalletLocked,
+                tr!("notify-wallet-locked-title"),
+                body,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn categories_are_on_until_turned_off() {
+        let mut notifications = Notifications::default();
+        for &category in Category::ALL {
+            assert!(notifications.enabled(category), "{category:?}");
+        }
+
+        *notifications.toggle(Category::PeerConnected) = false;
+        let saved = serde_json::to_string(&notifications).unwrap();
+        let restored: Notifications = serde_json::from_str(&saved).unwrap();
+        assert!(!restored.enabled(Category::PeerConnected));
+        assert!(restored.enabled(Category::WalletLocked));
+    }
+}
@@ -0,0 +1,301 @@
+//! Saving and restoring the app state
+//!
+//! The [`State`] is saved as JSON, tagged with the [`VERSION`] of its layout. When it's restored,
+//! state saved in an older layout goes through [`MIGRATIONS`] one version at a time, so renamed
+//! or restructured fields keep their data rather than falling back to their defaults.
+//!
+//! Saved state which can't be restored, because it's corrupt or from a newer version of the app,
+//! is copied to [`BACKUP_KEY`] before the app starts over and overwrites it.
+//!
+//! The state can also be [`export`]ed for the user to [`import`] on another machine, leaving out
+//! the wallets, which stay on the machine they were made on.
+
+use serde_json::{json, Value};
+
+use super::State;
+
+/// Key of the saved state, the concat of eframe's key and the crate name
+pub const APP_KEY: &str = concat!("eframe-app-", env!("CARGO_PKG_NAME"));
+
+/// Key of the last saved state which couldn't be restored
+pub const BACKUP_KEY: &str = concat!("eframe-app-", env!("CARGO_PKG_NAME"), "-backup");
+
+/// Version of the layout the state is saved in
+pub const VERSION: u64 = 2;
+
+/// Where the parts of the state which aren't exported are, with `*` standing for any key: each
+/// profile's wallet, with its encrypted seed, the identities derived from it and the plugins
+/// allowed to sign with it
+const PRIVATE: [&str; 1] = ["/profiles/by_name/*/backend_panel/wallet"];
+
+/// Upgrades from each version to the next, starting with version 1
+const MIGRATIONS: [fn(&mut Value); VERSION as usize - 1] = [v1_to_v2];
+
+/// Why saved state couldn't be restored
+#[derive(Debug)]
+pub enum Error {
+    /// It is neither JSON nor the RON version 1 was saved in
+    Syntax(String),
+
+    /// It was saved by a newer version of the app
+    Newer(u64),
+
+    /// It doesn't have the layout of its version
+    Layout(serde_json::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Syntax(e) => write!(f, "unreadable state: {e}"),
+            Error::Newer(version) => write!(f, "state from newer version {version}"),
+            Error::Layout(e) => write!(f, "unexpected state layout: {e}"),
+        }
+    }
+}
+
+/// Restores the state saved in `storage`, if there is any. Saved state which can't be restored
+/// is returned with the error, to be handed to [`back_up`].
+pub fn load(storage: &dyn eframe::Storage) -> Result<Option<State>, (String, Error)> {
+    let Some(saved) = storage
+        .get_string(APP_KEY)
+        .filter(|saved| !saved.is_empty())
+    else {
+        return Ok(None);
+    };
+    from_str(&saved).map(Some).map_err(|e| (saved, e))
+}
+
+/// Saves `state` to `storage`
+pub fn save(storage: &mut dyn eframe::Storage, state: &State) {
+    match to_string(state) {
+        Ok(saved) => storage.set_string(APP_KEY, saved),
+        Err(e) => tracing::error!("Failed to save app state: {e}"),
+    }
+}
+
+/// Clears the saved state, so the app starts over with the defaults. eframe can't remove an
+/// entry, so it's left empty.
+pub fn clear(storage: &mut dyn eframe::Storage) {
+    storage.set_string(APP_KEY, String::new());
+}
+
+/// Keeps saved state which couldn't be restored, so it isn't lost when the state is next saved
+pub fn back_up(storage: &mut dyn eframe::Storage, saved: String) {
+    tracing::warn!("Backing up the unrestorable app state to {BACKUP_KEY}");
+    storage.set_string(BACKUP_KEY, saved);
+}
+
+/// Restores state saved by [`to_string`], or by an older version of the app
+pub fn from_str(saved: &str) -> Result<State, Error> {
+    let value = match serde_json::from_str(saved) {
+        Ok(value) => value,
+        Err(_) => from_ron(saved).map_err(Error::Syntax)?,
+    };
+    serde_json::from_value(migrate(value)?).map_err(Error::Layout)
+}
+
+/// Reads state eframe saved as RON, before it was versioned. It goes through RON's own value,
+/// which keeps structs, lists and options but not which variant an enum was, as that was all
+/// the state had back then.
+fn from_ron(saved: &str) -> Result<Value, String> {
+    let value: ron::Value = ron::from_str(saved).map_err(|e| e.to_string())?;
+    serde_json::to_value(value).map_err(|e| e.to_string())
+}
+
+/// The state, in the current layout
+pub fn to_string(state: &State) -> Result<String, serde_json::Error> {
+    serde_json::to_string(&to_value(state)?)
+}
+
+/// The state without its private parts, for the user to take to another machine
+pub fn export(state: &State) -> Result<String, serde_json::Error> {
+    let mut value = to_value(state)?;
+    remove_private(&mut value);
+    serde_json::to_string_pretty(&value)
+}
+
+/// Restores state exported here or on another machine. Any private parts it has are ignored,
+/// and left at their defaults.
+pub fn import(exported: &[u8]) -> Result<State, Error> {
+    let value = serde_json::from_slice(exported).map_err(|e| Error::Syntax(e.to_string()))?;
+    let mut value = migrate(value)?;
+    remove_private(&mut value);
+    serde_json::from_value(value).map_err(Error::Layout)
+}
+
+/// The state, tagged with its version
+fn to_value(state: &State) -> Result<Value, serde_json::Error> {
+    let mut value = serde_json::to_value(state)?;
+    value["version"] = VERSION.into();
+    Ok(value)
+}
+
+fn remove_private(value: &mut Value) {
+    for pointer in PRIVATE {
+        let path: Vec<&str> = pointer.split('/').skip(1).collect();
+        remove(value, &path);
+    }
+}
+
+/// Removes what's at `path` in `value`
+fn remove(value: &mut Value, path: &[&str]) {
+    let Some(object) = value.as_object_mut() else {
+        return;
+    };
+    match path {
+        [key] => {
+            object.remove(*key);
+        }
+        ["*", rest @ ..] => object.values_mut().for_each(|child| remove(child, rest)),
+        [key, rest @ ..] => {
+            if let Some(child) = object.get_mut(*key) {
+                remove(child, rest);
+            }
+        }
+        [] => {}
+    }
+}
+
+/// Brings saved state up to the current layout
+fn migrate(mut value: Value) -> Result<Value, Error> {
+    // Version 1 predates the version being saved
+    let version = value.get("version").and_then(Value::as_u64).unwrap_or(1);
+    if version > VERSION {
+        return Err(Error::Newer(version));
+    }
+    for migration in &MIGRATIONS[version.max(1) as usize - 1..] {
+        migration(&mut value);
+    }
+    Ok(value)
+}
+
+/// Version 1 was the whole app as eframe saved it, with the state under `state` next to an
+/// example `label`, and a single node whose backend panel becomes the default profile's.
+/// Version 2 is the state alone.
+fn v1_to_v2(value: &mut Value) {
+    let mut state = value
+        .get_mut("state")
+        .map(Value::take)
+        .unwrap_or_else(|| json!({}));
+    if let Some(state) = state.as_object_mut() {
+        let backend_panel = state.remove("backend_panel").unwrap_or_else(|| json!({}));
+        state.insert(
+            "profiles".to_owned(),
+            json!({
+                "active": "default",
+                "by_name": { "default": { "backend_panel": backend_panel } },
+            }),
+        );
+    }
+    *value = state;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What eframe saved before the state had a version
+    const V1_RON: &str = r#"(state:(backend_panel:(open:false,password:"default password",file_dialog:(dropped_files:[],picked_path:Some("ping")))),label:"/dnsaddr/peerpiper.io/")"#;
+
+    fn restored(saved: &str) -> Value {
+        serde_json::to_value(from_str(saved).unwrap()).unwrap()
+    }
+
+    /// State as it's saved now, with a second profile
+    fn current() -> String {
+        let mut value = to_value(&State::default()).unwrap();
+        let profiles = &mut value["profiles"];
+        profiles["by_name"]["alice"] = profiles["by_name"]["default"].clone();
+        profiles["active"] = "alice".into();
+        value.to_string()
+    }
+
+    #[test]
+    fn migrates_version_1() {
+        let state = restored(V1_RON);
+        assert_eq!(state["profiles"]["active"], "default");
+        let backend_panel = &state["profiles"]["by_name"]["default"]["backend_panel"];
+        assert_eq!(backend_panel["file_dialog"]["picked_path"], "ping");
+        assert_eq!(backend_panel["wallet"]["always_allow"], json!({}));
+    }
+
+    #[test]
+    fn round_trips() {
+        let saved = to_string(&from_str(&current()).unwrap()).unwrap();
+        let value: Value = serde_json::from_str(&saved).unwrap();
+        assert_eq!(value["version"], VERSION);
+        assert_eq!(value["profiles"]["active"], "alice");
+
+        let mut state = restored(&saved);
+        state["version"] = VERSION.into();
+        assert_eq!(state, value);
+    }
+
+    #[test]
+    fn exports_all_but_the_wallets() {
+        let exported = export(&from_str(&current()).unwrap()).unwrap();
+        let value: Value = serde_json::from_str(&exported).unwrap();
+        assert_eq!(value["version"], VERSION);
+        for profile in ["default", "alice"] {
+            let backend_panel = &value["profiles"]["by_name"][profile]["backend_panel"];
+            assert!(backend_panel.get("trust").is_some());
+            assert!(backend_panel.get("wallet").is_none());
+        }
+
+        let state = serde_json::to_value(import(exported.as_bytes()).unwrap()).unwrap();
+        assert_eq!(state["profiles"]["active"], "alice");
+        assert_eq!(state["views"], value["views"]);
+    }
+
+    #[test]
+    fn imports_no_wallet() {
+        let wallet = json!({ "always_allow": { "evil": "evil" } });
+        let exported = json!({
+            "version": VERSION,
+            "profiles": { "by_name": { "default": { "backend_panel": { "wallet": wallet } } } },
+        });
+        let state = serde_json::to_value(import(exported.to_string().as_bytes()).unwrap()).unwrap();
+        let backend_panel = &state["profiles"]["by_name"]["default"]["backend_panel"];
+        assert_eq!(backend_panel["wallet"]["always_allow"], json!({}));
+    }
+
+    #[test]
+    fn refuses_what_it_cannot_restore() {
+        assert!(matches!(from_str("{ not json"), Err(Error::Syntax(_))));
+        assert!(matches!(
+            from_str(r#"{"version": 99}"#),
+            Err(Error::Newer(99))
+        ));
+        assert!(matches!(
+            from_str(&json!({ "version": VERSION, "views": { "windows": 3 } }).to_string()),
+            Err(Error::Layout(_))
+        ));
+    }
+
+    /// Storage kept in memory, as eframe keeps it in a file or the browser
+    #[derive(Default)]
+    struct Memory(std::collections::HashMap<String, String>);
+
+    impl eframe::Storage for Memory {
+        fn get_string(&self, key: &str) -> Option<String> {
+            self.0.get(key).cloned()
+        }
+
+        fn set_string(&mut self, key: &str, value: String) {
+            self.0.insert(key.to_owned(), value);
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    #[test]
+    fn cleared_state_is_not_restored() {
+        let mut storage = Memory::default();
+        save(&mut storage, &State::default());
+        assert!(matches!(load(&storage), Ok(Some(_))));
+
+        clear(&mut storage);
+        assert!(matches!(load(&storage), Ok(None)));
+    }
+}
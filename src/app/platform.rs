@@ -12,3 +12,6 @@ use native as platform;
 use multinode_web::platform;
 
 pub(crate) use platform::Platform;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use native::{bus::Topic, limits::ResourceLimits, PluginStatus};
@@ -3,10 +3,32 @@
 //! For example, a native node will only be available here. Whereas the browser needs to connect
 //! to a remote node, which is handled in the `web` module.
 
+mod actions;
+pub(crate) mod bus;
+mod detail;
+mod exports;
+pub(crate) mod limits;
+mod panel;
+mod runtime;
+mod signer;
+mod storage;
+mod vault;
+
+use crate::app::backend_panel::trust;
+use crate::app::commands::{Command, LOG_SEARCH};
+use bus::Bus;
+use detail::PluginDetail;
+use limits::{LimitViolation, ResourceLimits};
+use multinode_i18n::tr;
+use panel::PluginPanel;
 use peerpiper_plugins::tokio::{PluggablePiper, PluginLoader};
+use runtime::{CallError, Imports, Plugin};
+use signer::Signer;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
 use std::sync::{Arc, Mutex};
-
-// use peerpiper_plugins::{PluggablePiper};
+use storage::KvStore;
+use vault::VaultFile;
 
 /// Track whether the Context has been set
 #[derive(Debug, Default)]
@@ -35,6 +57,36 @@ impl ContextSet {
     }
 }
 
+/// Status of a plugin loaded into the node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PluginStatus {
+    /// The plugin is being loaded
+    Loading,
+
+    /// The plugin loaded successfully
+    Running,
+
+    /// The plugin was stopped because it exceeded one of its [ResourceLimits]
+    LimitExceeded(LimitViolation),
+
+    /// The plugin failed to load, or crashed
+    Failed(String),
+}
+
+impl fmt::Display for PluginStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PluginStatus::Loading => f.write_str(&tr!("plugin-loading")),
+            PluginStatus::Running => f.write_str(&tr!("plugin-running")),
+            PluginStatus::LimitExceeded(violation) => write!(f, "⛔ {violation}"),
+            PluginStatus::Failed(e) => write!(f, "❌ {e}"),
+        }
+    }
+}
+
+/// Whether the named plugin may load, given its wasm and sidecar signature
+pub(crate) type TrustCheck = Box<dyn Fn(&str, &[u8], Option<&[u8]>) -> bool + Send>;
+
 #[derive(Clone)]
 pub(crate) struct Platform {
     log: Arc<Mutex<Vec<String>>>,
@@ -42,11 +94,87 @@ pub(crate) struct Platform {
     /// Clone of the [egui::Context] so that the platform can trigger repaints
     ctx: Arc<Mutex<ContextSet>>,
 
+    /// Hands plugins to the node, which runs them
     loader: PluginLoader,
+
+    /// Resource limits for each plugin, by plugin name
+    limits: Arc<Mutex<BTreeMap<String, ResourceLimits>>>,
+
+    /// Decides which plugins may load, from their wasm and sidecar signature. Until the trust
+    /// settings are handed over, none may.
+    trust: Arc<Mutex<Option<TrustCheck>>>,
+
+    /// Status of each plugin, by plugin name
+    plugins: Arc<Mutex<BTreeMap<String, PluginStatus>>>,
+
+    /// The app's instance of each running plugin, by plugin name, see [runtime]
+    running: Arc<Mutex<BTreeMap<String, Plugin>>>,
+
+    /// State of the plugin detail view
+    detail: Arc<Mutex<PluginDetail>>,
+
+    /// UI panels contributed by plugins, by plugin name
+    panels: Arc<Mutex<BTreeMap<String, PluginPanel>>>,
+
+    /// Key-value storage of every plugin, namespaced by plugin name
+    storage: KvStore,
+
+    /// The profile's wallet, saved as soon as it changes
+    vault: VaultFile,
+
+    /// Messages between plugins
+    bus: Bus,
+
+    /// Signs for plugins with the unlocked wallet
+    signer: Signer,
+
+    /// The thread running the node, until it's closed
+    node: Arc<Mutex<Node>>,
+}
+
+/// The thread running [PluggablePiper]. It stops once told to, or once every [Platform]
+/// holding it is dropped.
+struct Node {
+    shutdown: Option<tokio::sync::oneshot::Sender<()>>,
+    thread: Option<std::thread::JoinHandle<()>>,
 }
 
-impl Default for Platform {
-    fn default() -> Self {
+impl Node {
+    /// Tells the thread to stop, and waits for it
+    fn stop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                tracing::error!("The node's thread panicked");
+            }
+        }
+    }
+}
+
+/// Where the profile keeps its data: in a directory of its own, or, for the default profile,
+/// where it did before there were profiles
+fn profile_dir(profile: Option<&str>) -> Option<std::path::PathBuf> {
+    let dir = eframe::storage_dir(crate::APP_NAME)?;
+    Some(match profile {
+        Some(profile) => dir.join("profiles").join(profile),
+        None => dir,
+    })
+}
+
+impl Platform {
+    /// Starts a node with the data of the named profile, or of the default profile if `None`
+    pub(crate) fn new(profile: Option<&str>) -> Self {
+        let dir = profile_dir(profile);
+        Self::start(
+            KvStore::open(dir.as_ref().map(|dir| dir.join("plugins"))),
+            VaultFile::new(dir.map(|dir| dir.join("vault.json"))),
+        )
+    }
+
+    /// Starts a node keeping plugin data in `storage`, and the wallet in `vault`
+    fn start(storage: KvStore, vault: VaultFile) -> Self {
         let log = Arc::new(Mutex::new(Vec::new()));
         let ctx: Arc<Mutex<ContextSet>> = Arc::new(Mutex::new(ContextSet::new()));
 
@@ -55,7 +183,7 @@ impl Default for Platform {
         let log_clone = log.clone();
         let ctx_clone = ctx.clone();
 
-        // task for listening on plugin events and updating the log accoringly
+        // task for listening on plugin events and updating the log accordingly
         tokio::task::spawn(async move {
             while let Some(event) = plugin_evts.recv().await {
                 log_clone.lock().unwrap().push(event);
@@ -63,36 +191,328 @@ impl Default for Platform {
             }
         });
 
-        // Execute the runtime in its own thread.
-        tokio::task::spawn(async move {
-            pluggable.run(command_receiver).await.unwrap_or_else(|e| {
-                tracing::error!("Failed to run PluggablePiper: {:?}", e);
+        // Execute the plugin runtime on its own OS thread, so a plugin that blocks or spins
+        // cannot starve the tokio runtime which drives the UI repaint tasks.
+        let (shutdown, stopped) = tokio::sync::oneshot::channel();
+        let thread = std::thread::spawn(move || {
+            let rt = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("Failed to build plugin runtime");
+
+            rt.block_on(async move {
+                tokio::select! {
+                    result = pluggable.run(command_receiver) => {
+                        result.unwrap_or_else(|e| {
+                            tracing::error!("Failed to run PluggablePiper: {:?}", e);
+                        });
+                    }
+                    _ = stopped => {}
+                }
             });
         });
 
-        Self { log, ctx, loader }
-    }
-}
+        // Repaint the topic monitor whenever plugins use the bus
+        let ctx_clone = ctx.clone();
+        let bus = Bus::new(move || ctx_clone.lock().unwrap().request_repaint());
+
+        // Repaint so the confirmation dialog shows as soon as a plugin asks to sign
+        let ctx_clone = ctx.clone();
+        let signer = Signer::new(move || ctx_clone.lock().unwrap().request_repaint());
 
-impl Drop for Platform {
-    fn drop(&mut self) {
-        // Kill the server process using thread_handle
-        self.close();
+        Self {
+            log,
+            ctx,
+            loader,
+            limits: Default::default(),
+            trust: Default::default(),
+            plugins: Default::default(),
+            running: Default::default(),
+            detail: Default::default(),
+            panels: Default::default(),
+            storage,
+            vault,
+            bus,
+            signer,
+            node: Arc::new(Mutex::new(Node {
+                shutdown: Some(shutdown),
+                thread: Some(thread),
+            })),
+        }
     }
 }
 
 impl Platform {
-    /// Load a plugin into the Platform
-    pub fn load_plugin(&self, name: String, wasm: Vec<u8>) {
-        // call self.loader.load_plugin(name, wasm).await from this sync function using tokio
+    /// Load a plugin into the node, if the trust settings allow it. The app's own instance of
+    /// it, within the [ResourceLimits] set for its name, is started first.
+    pub fn load_plugin(&self, name: String, wasm: Vec<u8>, signature: Option<Vec<u8>>) {
+        let allowed = match &*self.trust.lock().unwrap() {
+            Some(check) => check(&name, &wasm, signature.as_deref()),
+            None => {
+                tracing::error!("Blocked plugin {name}, as there are no trust settings yet");
+                false
+            }
+        };
+        if !allowed {
+            return;
+        }
+
+        let limits = self.limits(&name);
+
+        if let Err(violation) = limits.check_memory(&wasm) {
+            self.limit_exceeded(name, violation);
+            return;
+        }
+
+        self.set_status(name.clone(), PluginStatus::Loading);
+
+        let publisher = trust::publisher_key(&wasm, signature.as_deref());
+        let id = signer::plugin_id(&name, publisher.as_deref(), &wasm);
+        let imports = self.imports(&name, &id);
+        let on_crash = self.on_crash(name.clone());
+        let platform = self.clone();
         let mut loader = self.loader.clone();
         tokio::task::spawn(async move {
-            if let Err(e) = loader.load_plugin(name, &wasm).await {
-                tracing::error!("Failed to load plugin: {:?}", e);
+            let plugin = name.clone();
+            let app_wasm = wasm.clone();
+            let started = tokio::task::spawn_blocking(move || {
+                Plugin::start(&plugin, app_wasm, limits, imports, on_crash)
+            })
+            .await
+            .unwrap_or_else(|e| Err(CallError::Failed(e.to_string())));
+
+            let started = match started {
+                Ok(started) => Some(started),
+                Err(e @ CallError::Unsupported(_)) => {
+                    tracing::info!("Plugin {name} runs in the node only: {e}");
+                    let note = format!("ℹ Plugin {name}: {e}");
+                    platform.log.lock().unwrap().push(note);
+                    None
+                }
+                Err(CallError::Limit(violation)) => {
+                    platform.limit_exceeded(name, violation);
+                    return;
+                }
+                Err(CallError::Failed(e)) => {
+                    tracing::error!("Failed to load plugin {name}: {e}");
+                    platform.set_status(name, PluginStatus::Failed(e));
+                    return;
+                }
+            };
+
+            if let Err(e) = loader.load_plugin(name.clone(), &wasm).await {
+                let e = format!("{e:?}");
+                tracing::error!("Failed to load plugin {name} into the node: {e}");
+                if let Some(started) = started {
+                    tokio::task::spawn_blocking(move || started.plugin.stop());
+                }
+                platform.set_status(name, PluginStatus::Failed(e));
+                return;
+            }
+
+            let (plugin, panel) = match started {
+                Some(started) => (Some(started.plugin), started.panel),
+                None => (None, None),
+            };
+            let mut running = platform.running.lock().unwrap();
+            // A reloaded plugin replaces the instance running before
+            let replaced = match plugin {
+                Some(plugin) => running.insert(name.clone(), plugin),
+                None => running.remove(&name),
+            };
+            drop(running);
+            if let Some(replaced) = replaced {
+                tokio::task::spawn_blocking(move || replaced.stop());
             }
+            let mut panels = platform.panels.lock().unwrap();
+            match panel {
+                Some(panel) => panels.insert(name.clone(), panel),
+                None => panels.remove(&name),
+            };
+            drop(panels);
+            platform.set_status(name, PluginStatus::Running);
         });
     }
 
+    /// Reports the named plugin crashing, or going over its limits, in its status and the
+    /// node log.
+    ///
+    /// It's run by the plugin's own thread, so it holds none of the plugin's handles.
+    fn on_crash(&self, name: String) -> impl Fn(CallError) + Send + Sync + 'static {
+        let log = self.log.clone();
+        let plugins = self.plugins.clone();
+        let ctx = self.ctx.clone();
+        move |e| {
+            match e {
+                CallError::Limit(violation) => {
+                    tracing::warn!("Plugin {name}: {violation}");
+                    log.lock()
+                        .unwrap()
+                        .push(format!("⛔ Plugin {name}: {violation}"));
+                    plugins
+                        .lock()
+                        .unwrap()
+                        .insert(name.clone(), PluginStatus::LimitExceeded(violation));
+                }
+                e => {
+                    let e = e.to_string();
+                    tracing::warn!("Plugin {name} failed: {e}");
+                    log.lock().unwrap().push(format!("❌ Plugin {name}: {e}"));
+                    plugins
+                        .lock()
+                        .unwrap()
+                        .insert(name.clone(), PluginStatus::Failed(e));
+                }
+            }
+            ctx.lock().unwrap().request_repaint();
+        }
+    }
+
+    /// The [ResourceLimits] for the named plugin
+    pub(crate) fn limits(&self, name: &str) -> ResourceLimits {
+        self.limits
+            .lock()
+            .unwrap()
+            .get(name)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Replaces what decides which plugins may load
+    pub(crate) fn set_trust(
+        &self,
+        check: impl Fn(&str, &[u8], Option<&[u8]>) -> bool + Send + 'static,
+    ) {
+        *self.trust.lock().unwrap() = Some(Box::new(check));
+    }
+
+    /// Sets the [ResourceLimits] used the next time the named plugin is loaded or called
+    pub(crate) fn set_limits(&self, name: String, limits: ResourceLimits) {
+        if let Some(plugin) = self.running.lock().unwrap().get(&name) {
+            plugin.set_limits(limits);
+        }
+        self.limits.lock().unwrap().insert(name, limits);
+    }
+
+    /// Replaces the [ResourceLimits] of every plugin. Plugins without limits get the defaults.
+    pub(crate) fn replace_limits(&self, limits: BTreeMap<String, ResourceLimits>) {
+        for (name, plugin) in self.running.lock().unwrap().iter() {
+            plugin.set_limits(limits.get(name).copied().unwrap_or_default());
+        }
+        *self.limits.lock().unwrap() = limits;
+    }
+
+    /// Snapshot of every plugin's name and [PluginStatus]
+    pub(crate) fn plugins(&self) -> Vec<(String, PluginStatus)> {
+        self.plugins
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, status)| (name.clone(), status.clone()))
+            .collect()
+    }
+
+    /// State behind the host imports of the named plugin, with the given id, see
+    /// [`signer::plugin_id`]
+    fn imports(&self, name: &str, id: &str) -> Imports {
+        Imports {
+            kv: self.storage.namespace(name),
+            pubsub: self.bus.client(name),
+            signer: self.signer.client(name, id),
+        }
+    }
+
+    /// Snapshot of every bus topic, by name
+    pub(crate) fn topics(&self) -> BTreeMap<String, bus::Topic> {
+        self.bus.topics()
+    }
+
+    /// Whether the named plugin's panel tab is open, or `None` if it has no panel
+    pub(crate) fn panel_open(&self, name: &str) -> Option<bool> {
+        self.panels
+            .lock()
+            .unwrap()
+            .get(name)
+            .map(|panel| panel.open)
+    }
+
+    /// Opens or closes the named plugin's panel tab
+    pub(crate) fn set_panel_open(&self, name: &str, open: bool) {
+        if let Some(panel) = self.panels.lock().unwrap().get_mut(name) {
+            panel.open = open;
+        }
+    }
+
+    /// Names of the plugins with stored keys, loaded or not
+    pub(crate) fn storage_plugins(&self) -> Vec<String> {
+        self.storage.plugins()
+    }
+
+    /// Snapshot of the named plugin's stored keys and values
+    pub(crate) fn storage_entries(&self, name: &str) -> BTreeMap<String, Vec<u8>> {
+        self.storage.entries(name)
+    }
+
+    /// Stores `value` under `key` for the named plugin, within its storage quota
+    pub(crate) fn storage_set(
+        &self,
+        name: &str,
+        key: String,
+        value: Vec<u8>,
+    ) -> Result<(), storage::QuotaExceeded> {
+        self.storage.set(name, key, value)
+    }
+
+    /// Removes `key` from the named plugin's storage
+    pub(crate) fn storage_delete(&self, name: &str, key: &str) {
+        self.storage.delete(name, key);
+    }
+
+    /// Removes every key from the named plugin's storage
+    pub(crate) fn storage_clear(&self, name: &str) {
+        self.storage.clear(name);
+    }
+
+    /// The wallet's vault as last saved, see [`vault`]
+    pub(crate) fn saved_vault(&self) -> Option<String> {
+        self.vault.load()
+    }
+
+    /// Saves the wallet's vault, returning once it's on disk
+    pub(crate) fn save_vault(&self, vault: &str) -> std::io::Result<()> {
+        self.vault.save(vault)
+    }
+
+    /// Lets plugins sign with keys derived from `seed`, or stops them with `None`
+    pub(crate) fn set_wallet(&self, seed: Option<crate::app::wallet::Seed>) {
+        self.signer.set_seed(seed);
+    }
+
+    /// Sets the ids of the plugins which may sign without asking, see [`signer::plugin_id`]
+    pub(crate) fn set_always_allow(&self, ids: BTreeSet<String>) {
+        self.signer.set_always_allow(ids);
+    }
+
+    /// The oldest request to sign, awaiting the user
+    pub(crate) fn sign_request(&self) -> Option<crate::app::wallet::SignRequest> {
+        self.signer.pending()
+    }
+
+    /// Approves or denies the oldest request to sign
+    pub(crate) fn answer_sign_request(&self, approve: bool) {
+        self.signer.answer(approve);
+    }
+
+    fn set_status(&self, name: String, status: PluginStatus) {
+        self.plugins.lock().unwrap().insert(name, status);
+        self.ctx.lock().unwrap().request_repaint();
+    }
+
+    /// Records a [LimitViolation] in the plugin status and the node log
+    fn limit_exceeded(&self, name: String, violation: LimitViolation) {
+        self.on_crash(name)(CallError::Limit(violation));
+    }
+
     /// Returns whether the ctx is set or not
     pub(crate) fn egui_ctx(&self) -> bool {
         self.ctx.lock().unwrap().set
@@ -104,25 +524,248 @@ impl Platform {
         self.ctx.lock().unwrap().set = true;
     }
 
-    // This is where you would put platform-specific methods
-    pub(crate) fn close(&mut self) {}
+    /// Stops the node: denies the requests to sign, then stops every plugin and the node's
+    /// thread, waiting for them. Plugins finish the call they're in first.
+    pub(crate) fn close(&self) {
+        self.signer.set_seed(None);
+        let running = std::mem::take(&mut *self.running.lock().unwrap());
+        for plugin in running.into_values() {
+            plugin.stop();
+        }
+        self.panels.lock().unwrap().clear();
+        self.plugins.lock().unwrap().clear();
+        self.node.lock().unwrap().stop();
+    }
+
+    /// Shows a notification from the OS
+    pub(crate) fn notify(&self, title: &str, body: &str) {
+        let mut notification = notify_rust::Notification::new();
+        notification
+            .appname(crate::APP_NAME)
+            .summary(title)
+            .body(body);
+        // Showing waits on the OS, on Linux over D-Bus, so it's done off the UI thread
+        std::thread::spawn(move || {
+            if let Err(e) = notification.show() {
+                tracing::error!("Failed to show notification: {e}");
+            }
+        });
+    }
+
+    /// Native builds are updated by reinstalling them, so there's never a build to reload into
+    pub(crate) fn update_available(&self) -> bool {
+        false
+    }
+
+    pub(crate) fn apply_update(&self) {}
 
     /// Platform specific UI to show
-    pub(crate) fn show(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) {
-        // Bottom Up inner panel
-        egui::TopBottomPanel::bottom("log")
-            .resizable(true)
-            .show_inside(ui, |ui| {
-                ui.collapsing("Node Log", |ui| {
-                    // SCROLLABLE SECTION for the log
-                    egui::ScrollArea::vertical().show(ui, |ui| {
-                        ui.vertical(|ui| {
-                            for line in self.log.lock().unwrap().iter().rev() {
-                                ui.label(line);
-                            }
-                        });
-                    });
-                });
+    pub(crate) fn show(&self, _ctx: &egui::Context, ui: &mut egui::Ui) {
+        let ctx = self.ctx.clone();
+        let repaint = move || ctx.lock().unwrap().request_repaint();
+
+        // The plugin detail view
+        let running = self.running.lock().unwrap();
+        self.detail.lock().unwrap().ui(ui, &running, repaint);
+    }
+
+    /// The node log, newest first, filtered by what is typed in its search field
+    pub(crate) fn log_ui(&self, ui: &mut egui::Ui) {
+        let search_id = egui::Id::new(LOG_SEARCH);
+        let text_id = search_id.with("text");
+        let mut search = ui.data_mut(|d| d.get_temp::<String>(text_id).unwrap_or_default());
+        ui.add(
+            egui::TextEdit::singleline(&mut search)
+                .id(search_id)
+                .hint_text(tr!("log-search"))
+                .desired_width(f32::INFINITY),
+        );
+        let search_lower = search.to_lowercase();
+        ui.data_mut(|d| d.insert_temp(text_id, search));
+
+        // SCROLLABLE SECTION for the log
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.vertical(|ui| {
+                for line in self.log.lock().unwrap().iter().rev() {
+                    if line.to_lowercase().contains(&search_lower) {
+                        ui.label(line);
+                    }
+                }
             });
+        });
+    }
+
+    /// The node's peers. The node makes its own identity, which it doesn't share with the app.
+    pub(crate) fn peers_ui(&self, ui: &mut egui::Ui) {
+        ui.weak(tr!("peers-own-identity"));
+        ui.separator();
+        ui.weak(tr!("peers-none"));
+    }
+
+    /// Names and titles of the plugin-contributed UI panels which are open
+    pub(crate) fn panels(&self) -> Vec<(String, String)> {
+        self.panels
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, panel)| panel.open)
+            .map(|(name, panel)| (name.clone(), panel.title()))
+            .collect()
+    }
+
+    /// Commands loaded plugins contribute to the command palette: opening each one's panel,
+    /// showing each one with exports in the detail view, and the actions they list
+    pub(crate) fn plugin_commands(&self) -> Vec<Command> {
+        let mut commands: Vec<Command> = self
+            .panels
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, panel)| Command::OpenPanel {
+                name: name.clone(),
+                title: panel.title(),
+            })
+            .collect();
+        commands.extend(
+            self.running
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(_, plugin)| !plugin.funcs.is_empty())
+                .map(|(name, _)| Command::SelectPlugin(name.clone())),
+        );
+        commands.extend(
+            self.running
+                .lock()
+                .unwrap()
+                .iter()
+                .flat_map(|(name, plugin)| {
+                    plugin.actions.iter().map(|action| Command::RunAction {
+                        plugin: name.clone(),
+                        id: action.id.clone(),
+                        title: action.title.clone(),
+                    })
+                }),
+        );
+        commands
+    }
+
+    /// Runs the action `id` of the named plugin, then renders its panel again, if it has one
+    pub(crate) fn run_action(&self, name: &str, id: &str) {
+        let running = self.running.lock().unwrap();
+        let Some(plugin) = running.get(name) else {
+            return;
+        };
+        let ctx = self.ctx.clone();
+        let repaint = move || ctx.lock().unwrap().request_repaint();
+        if let Err(e) = actions::run(plugin, id.to_owned(), repaint.clone()) {
+            tracing::warn!("Failed to run action {id} of plugin {name}: {e}");
+        }
+        if let Some(panel) = self.panels.lock().unwrap().get(name) {
+            panel.refresh(plugin, repaint);
+        }
+    }
+
+    /// Shows the named plugin in the detail view
+    pub(crate) fn select_plugin(&self, name: &str) {
+        self.detail.lock().unwrap().select(name);
+    }
+
+    /// Shows the named plugin's UI panel. Returns false if it has none.
+    pub(crate) fn panel_ui(&self, name: &str, ui: &mut egui::Ui) -> bool {
+        let Some(panel) = self.panels.lock().unwrap().get(name).cloned() else {
+            return false;
+        };
+        let running = self.running.lock().unwrap();
+        let Some(plugin) = running.get(name) else {
+            return false;
+        };
+
+        let ctx = self.ctx.clone();
+        panel.ui(ui, plugin, move || ctx.lock().unwrap().request_repaint());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    /// Subscribes to `greetings`, storing each payload under the `last` key
+    const PONG: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/pong.wat"
+    ));
+
+    #[test]
+    fn loads_only_allowed_plugins() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let _entered = runtime.enter();
+        let platform = Platform::start(KvStore::default(), VaultFile::default());
+        let pong = wat::parse_str(PONG).unwrap();
+
+        // Nothing loads before the trust settings are handed over
+        platform.load_plugin("pong".into(), pong.clone(), None);
+        platform.set_trust(|name, _, signature| name == "pong" && signature.is_some());
+        platform.load_plugin("pong".into(), pong, None);
+        assert!(platform.plugins().is_empty());
+        platform.close();
+    }
+
+    #[test]
+    fn plugins_importing_from_the_node_only_run_there() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let _entered = runtime.enter();
+        let platform = Platform::start(KvStore::default(), VaultFile::default());
+        let wasm = wat::parse_str(
+            r#"(component (import "peerpiper:node/host" (instance (export "ping" (func)))))"#,
+        )
+        .unwrap();
+
+        platform.set_trust(|_, _, _| true);
+        platform.load_plugin("node-only".into(), wasm, None);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while platform.plugins() != [("node-only".to_owned(), PluginStatus::Running)] {
+            assert!(Instant::now() < deadline, "the plugin didn't load");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        assert!(platform.running.lock().unwrap().is_empty());
+        let log = platform.log.lock().unwrap().join("\n");
+        assert!(log.contains("peerpiper:node/host"), "{log}");
+        drop(log);
+        platform.close();
+    }
+
+    #[test]
+    fn closing_stops_the_node() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let _entered = runtime.enter();
+        let storage = KvStore::default();
+        let platform = Platform::start(storage.clone(), VaultFile::default());
+
+        platform.set_trust(|_, _, _| true);
+        platform.load_plugin("pong".into(), wat::parse_str(PONG).unwrap(), None);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while platform.plugins() != [("pong".to_owned(), PluginStatus::Running)] {
+            assert!(Instant::now() < deadline, "pong didn't start");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        platform.close();
+        assert!(platform.node.lock().unwrap().thread.is_none());
+        assert!(platform.running.lock().unwrap().is_empty());
+        assert!(platform.plugins().is_empty());
+
+        // The plugin no longer gets its messages
+        let message = bus::Message {
+            topic: "greetings".into(),
+            publisher: "test".into(),
+            payload: b"hi".to_vec(),
+        };
+        platform.bus.publish(message);
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(storage.get("pong", "last"), None);
     }
 }
@@ -0,0 +1,129 @@
+//! Plugin-contributed actions.
+//!
+//! A plugin which exports the `peerpiper:ui/actions` interface from `wit/ui.wit` lists actions
+//! of its own once loaded. The command palette offers them next to the app's commands, and
+//! picking one runs it in the plugin.
+
+use super::runtime::{CallError, Loaded, Plugin};
+
+wasmtime::component::bindgen!({
+    path: "wit",
+    world: "actions-plugin",
+});
+
+use exports::peerpiper::ui::actions::Action;
+
+/// An action a plugin lists
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PluginAction {
+    /// Identifies the action to the plugin
+    pub(crate) id: String,
+    pub(crate) title: String,
+}
+
+/// The actions the plugin lists, if it exports any
+pub(super) fn list(loaded: &mut Loaded) -> Result<Vec<PluginAction>, CallError> {
+    if loaded.actions.is_none() {
+        return Ok(Vec::new());
+    }
+    let actions = loaded.call(|loaded| {
+        let Loaded { store, actions, .. } = loaded;
+        match actions {
+            Some(actions) => actions.peerpiper_ui_actions().call_actions(store),
+            None => Ok(Vec::new()),
+        }
+    })?;
+    Ok(actions
+        .into_iter()
+        .map(|Action { id, title }| PluginAction { id, title })
+        .collect())
+}
+
+/// Runs the action `id` in `plugin`, after the jobs sent before it. `on_done` is called from the
+/// plugin's thread once it ran. A failing action crashes the plugin, which is told as any crash.
+pub(crate) fn run(
+    plugin: &Plugin,
+    id: String,
+    on_done: impl FnOnce() + Send + 'static,
+) -> Result<(), CallError> {
+    plugin.run(move |loaded| {
+        let ran = loaded.call(|loaded| {
+            let Loaded { store, actions, .. } = loaded;
+            match actions {
+                Some(actions) => actions.peerpiper_ui_actions().call_run(store, &id),
+                None => Ok(()),
+            }
+        });
+        if let Err(e) = ran {
+            tracing::warn!("Plugin action {id} failed: {e}");
+        }
+        on_done();
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::limits::ResourceLimits;
+    use super::super::runtime::Imports;
+    use super::*;
+    use std::sync::{mpsc, Arc, Mutex};
+
+    /// Lists the `greet` action, and traps on any other
+    const GREET: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/actions.wat"
+    ));
+
+    /// Starts the plugin, keeping the last crash
+    fn start(wat: &str) -> (Plugin, Arc<Mutex<Option<CallError>>>) {
+        let crashed = Arc::new(Mutex::new(None));
+        let on_crash = {
+            let crashed = crashed.clone();
+            move |e| *crashed.lock().unwrap() = Some(e)
+        };
+        let wasm = wat::parse_str(wat).unwrap();
+        let started = Plugin::start(
+            "greeter",
+            wasm,
+            ResourceLimits::default(),
+            Imports::default(),
+            on_crash,
+        )
+        .unwrap();
+        (started.plugin, crashed)
+    }
+
+    /// Runs the action `id`, waiting until it ran
+    fn run_and_wait(plugin: &Plugin, id: &str) {
+        let (done, ran) = mpsc::channel();
+        run(plugin, id.to_owned(), move || done.send(()).unwrap()).unwrap();
+        ran.recv().unwrap();
+    }
+
+    #[test]
+    fn lists_and_runs_actions() {
+        let (plugin, crashed) = start(GREET);
+        assert_eq!(
+            plugin.actions,
+            vec![PluginAction {
+                id: "greet".into(),
+                title: "Say hello".into()
+            }]
+        );
+
+        run_and_wait(&plugin, "greet");
+        assert!(crashed.lock().unwrap().is_none());
+
+        run_and_wait(&plugin, "grate");
+        assert!(matches!(
+            *crashed.lock().unwrap(),
+            Some(CallError::Failed(_))
+        ));
+    }
+
+    #[test]
+    fn plugins_without_actions_have_none() {
+        let (plugin, _) = start("(component)");
+        assert!(plugin.actions.is_empty());
+    }
+}
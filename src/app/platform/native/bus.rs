@@ -0,0 +1,408 @@
+//! Plugin-to-plugin messaging.
+//!
+//! Plugins publish to named topics through the `peerpiper:bus/pubsub` import from
+//! `wit/deps/bus/pubsub.wit`, and the host delivers each message to the plugins subscribed to
+//! the topic. Plugins which export `peerpiper:bus/subscriber` receive their messages in order,
+//! in the same instance which runs the rest of the plugin, see [runtime](super::runtime).
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::sync::{Arc, Mutex, Weak};
+
+use super::runtime::{CallError, Loaded};
+
+wasmtime::component::bindgen!({
+    path: "wit/deps/bus",
+    world: "bus-plugin",
+});
+
+pub use peerpiper::bus::pubsub;
+
+/// How many recent messages each topic keeps for the monitor
+const RECENT: usize = 20;
+
+/// Where the bus delivers a plugin's messages. Returns false if the plugin couldn't take the
+/// message, as too many were waiting.
+pub(crate) type Mailbox = dyn Fn(Message) -> bool + Send + Sync;
+
+/// A message published to a topic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Message {
+    pub(crate) topic: String,
+    pub(crate) publisher: String,
+    pub(crate) payload: Vec<u8>,
+}
+
+/// Activity on a topic, for the monitor
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Topic {
+    /// Names of the subscribed plugins
+    pub(crate) subscribers: BTreeSet<String>,
+
+    /// Number of messages published
+    pub(crate) count: u64,
+
+    /// The most recent messages, newest last
+    pub(crate) recent: VecDeque<Message>,
+}
+
+#[derive(Default)]
+struct Inner {
+    topics: BTreeMap<String, Topic>,
+
+    /// Where to deliver each subscribed plugin's messages, by plugin name. Held weakly, as
+    /// plugins hold the bus through their imports.
+    mailboxes: BTreeMap<String, Weak<Mailbox>>,
+}
+
+impl Inner {
+    /// Removes the plugin from the subscribers of every topic
+    fn unsubscribe_all(&mut self, plugin: &str) {
+        for topic in self.topics.values_mut() {
+            topic.subscribers.remove(plugin);
+        }
+    }
+}
+
+/// The node's message bus, shared by every plugin
+#[derive(Clone, Default)]
+pub(crate) struct Bus {
+    inner: Arc<Mutex<Inner>>,
+
+    /// Called whenever a topic changes, so the monitor can repaint
+    on_change: Option<Arc<dyn Fn() + Send + Sync>>,
+}
+
+impl Bus {
+    pub(crate) fn new(on_change: impl Fn() + Send + Sync + 'static) -> Self {
+        Self {
+            inner: Default::default(),
+            on_change: Some(Arc::new(on_change)),
+        }
+    }
+
+    fn changed(&self) {
+        if let Some(on_change) = &self.on_change {
+            on_change();
+        }
+    }
+
+    /// The bus as seen by the named plugin
+    pub(crate) fn client(&self, plugin: &str) -> Client {
+        Client {
+            plugin: plugin.to_owned(),
+            bus: self.clone(),
+        }
+    }
+
+    /// Records the message and queues it for every subscriber of its topic
+    pub(crate) fn publish(&self, message: Message) {
+        let mut inner = self.inner.lock().unwrap();
+        let Inner { topics, mailboxes } = &mut *inner;
+        let topic = topics.entry(message.topic.clone()).or_default();
+
+        for subscriber in &topic.subscribers {
+            let Some(mailbox) = mailboxes.get(subscriber).and_then(Weak::upgrade) else {
+                continue;
+            };
+            if !mailbox(message.clone()) {
+                tracing::warn!(
+                    "Dropped a message on {} for busy {subscriber}",
+                    message.topic
+                );
+            }
+        }
+
+        topic.count += 1;
+        if topic.recent.len() == RECENT {
+            topic.recent.pop_front();
+        }
+        topic.recent.push_back(message);
+
+        drop(inner);
+        self.changed();
+    }
+
+    pub(crate) fn subscribe(&self, plugin: &str, topic: String) {
+        self.inner
+            .lock()
+            .unwrap()
+            .topics
+            .entry(topic)
+            .or_default()
+            .subscribers
+            .insert(plugin.to_owned());
+        self.changed();
+    }
+
+    pub(crate) fn unsubscribe(&self, plugin: &str, topic: &str) {
+        if let Some(topic) = self.inner.lock().unwrap().topics.get_mut(topic) {
+            topic.subscribers.remove(plugin);
+        }
+        self.changed();
+    }
+
+    /// Delivers the plugin's messages to `mailbox` from now on. A previous mailbox belongs to
+    /// the instance the plugin was reloaded over, whose subscriptions are dropped with it.
+    fn attach(&self, plugin: &str, mailbox: Weak<Mailbox>) {
+        let mut inner = self.inner.lock().unwrap();
+        let replaced = inner.mailboxes.insert(plugin.to_owned(), mailbox);
+        if replaced.is_some() {
+            inner.unsubscribe_all(plugin);
+        }
+        drop(inner);
+        self.changed();
+    }
+
+    /// Stops delivering the plugin's messages and drops its subscriptions, unless it was
+    /// attached again since with another mailbox
+    fn detach(&self, plugin: &str, mailbox: &Weak<Mailbox>) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner
+            .mailboxes
+            .get(plugin)
+            .is_some_and(|attached| attached.ptr_eq(mailbox))
+        {
+            inner.mailboxes.remove(plugin);
+            inner.unsubscribe_all(plugin);
+            drop(inner);
+            self.changed();
+        }
+    }
+
+    /// Snapshot of every topic, by name
+    pub(crate) fn topics(&self) -> BTreeMap<String, Topic> {
+        self.inner.lock().unwrap().topics.clone()
+    }
+}
+
+/// One plugin's view of the [Bus], given to its instances as the `pubsub` import
+#[derive(Clone, Default)]
+pub(crate) struct Client {
+    plugin: String,
+    bus: Bus,
+}
+
+impl Client {
+    /// Stops delivering messages to `mailbox`
+    pub(super) fn detach(&self, mailbox: &Weak<Mailbox>) {
+        self.bus.detach(&self.plugin, mailbox);
+    }
+}
+
+impl pubsub::Host for Client {
+    fn publish(&mut self, topic: String, payload: Vec<u8>) {
+        self.bus.publish(Message {
+            topic,
+            publisher: self.plugin.clone(),
+            payload,
+        });
+    }
+
+    fn subscribe(&mut self, topic: String) {
+        self.bus.subscribe(&self.plugin, topic);
+    }
+
+    fn unsubscribe(&mut self, topic: String) {
+        self.bus.unsubscribe(&self.plugin, &topic);
+    }
+}
+
+/// Starts delivering messages to the plugin, if it exports a subscriber.
+///
+/// The plugin's initial topics are subscribed to, then each message is handed to its
+/// `on-message` in the plugin's instance, through its mailbox.
+pub(super) fn subscribe(loaded: &mut Loaded, name: &str) -> Result<(), CallError> {
+    if loaded.subscriber.is_none() {
+        return Ok(());
+    }
+    let topics = loaded.call(|loaded| {
+        let Loaded {
+            store, subscriber, ..
+        } = loaded;
+        match subscriber {
+            Some(subscriber) => subscriber.peerpiper_bus_subscriber().call_topics(store),
+            None => Ok(Vec::new()),
+        }
+    })?;
+
+    let bus = loaded.bus().bus.clone();
+    bus.attach(name, loaded.mailbox.clone());
+    for topic in topics {
+        bus.subscribe(name, topic);
+    }
+    Ok(())
+}
+
+/// Hands `message` to the plugin's `on-message`
+pub(super) fn deliver(loaded: &mut Loaded, message: Message) {
+    // Failures are reported by the runtime, as nobody waits on the outcome here
+    let _ = loaded.call(|loaded| {
+        let Loaded {
+            store, subscriber, ..
+        } = loaded;
+        match subscriber {
+            Some(subscriber) => subscriber.peerpiper_bus_subscriber().call_on_message(
+                store,
+                &message.topic,
+                &message.publisher,
+                &message.payload,
+            ),
+            None => Ok(()),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::limits::ResourceLimits;
+    use super::super::runtime::{Imports, Plugin};
+    use super::super::storage::KvStore;
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    /// Publishes "hi" to the `greetings` topic from its exported `send` function
+    const PING: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/ping.wat"
+    ));
+
+    /// Subscribes to `greetings`, storing each payload under the `last` key
+    const PONG: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/pong.wat"
+    ));
+
+    fn imports(bus: &Bus, storage: &KvStore, plugin: &str) -> Imports {
+        Imports {
+            kv: storage.namespace(plugin),
+            pubsub: bus.client(plugin),
+            ..Default::default()
+        }
+    }
+
+    fn start(wat: &str, bus: &Bus, storage: &KvStore, plugin: &str) -> Plugin {
+        start_with(wat, bus, storage, plugin, |e| panic!("plugin failed: {e}"))
+    }
+
+    fn start_with(
+        wat: &str,
+        bus: &Bus,
+        storage: &KvStore,
+        plugin: &str,
+        on_crash: impl Fn(CallError) + Send + Sync + 'static,
+    ) -> Plugin {
+        let wasm = wat::parse_str(wat).unwrap();
+        let imports = imports(bus, storage, plugin);
+        Plugin::start(plugin, wasm, ResourceLimits::default(), imports, on_crash)
+            .unwrap()
+            .plugin
+    }
+
+    fn greet(bus: &Bus) {
+        pubsub::Host::publish(&mut bus.client("ping"), "greetings".into(), b"hi".to_vec());
+    }
+
+    #[test]
+    fn delivers_messages_between_plugins() {
+        let bus = Bus::default();
+        let storage = KvStore::default();
+
+        let _pong = start(PONG, &bus, &storage, "pong");
+        let ping = start(PING, &bus, &storage, "ping");
+        assert!(!bus.topics()["greetings"].subscribers.contains("ping"));
+
+        let send = ping.funcs.iter().find(|f| f.name() == "send").unwrap();
+        ping.call(send.clone(), vec![], |result| assert!(result.is_ok()))
+            .unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while storage.get("pong", "last").is_none() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(storage.get("pong", "last"), Some(b"hi".to_vec()));
+
+        let topic = &bus.topics()["greetings"];
+        assert_eq!(topic.count, 1);
+        assert!(topic.subscribers.contains("pong"));
+        assert_eq!(
+            topic.recent.back(),
+            Some(&Message {
+                topic: "greetings".into(),
+                publisher: "ping".into(),
+                payload: b"hi".to_vec(),
+            })
+        );
+    }
+
+    #[test]
+    fn crashed_plugins_get_nothing_more() {
+        let bus = Bus::default();
+        let storage = KvStore::default();
+        let (crashes, crashed) = mpsc::channel();
+        let trapping = PONG.replace("(call $set", "(unreachable) (call $set");
+        let _pong = start_with(&trapping, &bus, &storage, "pong", move |e| {
+            crashes.send(e).unwrap()
+        });
+
+        greet(&bus);
+        crashed.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(!bus.inner.lock().unwrap().mailboxes.contains_key("pong"));
+        assert!(bus.topics()["greetings"].subscribers.is_empty());
+
+        greet(&bus);
+        assert!(crashed.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+
+    #[test]
+    fn stopped_plugins_let_go_of_the_bus() {
+        let bus = Bus::default();
+        let storage = KvStore::default();
+        let pong = start(PONG, &bus, &storage, "pong");
+
+        pong.stop();
+        assert!(!bus.inner.lock().unwrap().mailboxes.contains_key("pong"));
+        assert!(bus.topics()["greetings"].subscribers.is_empty());
+        greet(&bus);
+        assert_eq!(storage.get("pong", "last"), None);
+    }
+
+    #[test]
+    fn reloaded_plugins_only_keep_their_new_subscriptions() {
+        let bus = Bus::default();
+        let storage = KvStore::default();
+        let pong = start(PONG, &bus, &storage, "pong");
+
+        // The new version listens to another topic, of the same length
+        let farewells = PONG.replace(
+            r#"(i32.const 32) "greetings""#,
+            r#"(i32.const 32) "farewells""#,
+        );
+        let reloaded = start(&farewells, &bus, &storage, "pong");
+        pong.stop();
+
+        let topics = bus.topics();
+        assert!(topics["greetings"].subscribers.is_empty());
+        assert!(topics["farewells"].subscribers.contains("pong"));
+        reloaded.stop();
+        assert!(bus.topics()["farewells"].subscribers.is_empty());
+    }
+
+    #[test]
+    fn unsubscribed_plugins_get_nothing() {
+        let bus = Bus::default();
+        let (sender, messages) = mpsc::channel();
+        let sender = Mutex::new(sender);
+        let mailbox: Arc<Mailbox> =
+            Arc::new(move |message| sender.lock().unwrap().send(message).is_ok());
+        bus.attach("pong", Arc::downgrade(&mailbox));
+
+        let mut client = bus.client("pong");
+        pubsub::Host::subscribe(&mut client, "a".into());
+        pubsub::Host::unsubscribe(&mut client, "a".into());
+        pubsub::Host::publish(&mut client, "a".into(), b"x".to_vec());
+
+        assert!(messages.try_recv().is_err());
+        assert_eq!(bus.topics()["a"].count, 1);
+    }
+}
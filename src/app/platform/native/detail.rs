@@ -0,0 +1,420 @@
+//! Plugin detail view.
+//!
+//! Shows the functions a plugin exports, with an input form generated from each function's WIT
+//! parameter types, and the typed result of calling it.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use multinode_i18n::tr;
+use wasmtime::component::{types::Type, Val};
+
+use super::exports::{format_val, type_name, ExportedFunc};
+use super::runtime::{CallError, Plugin};
+
+/// Form input for a single WIT value, mirroring the shape of its [Type]
+#[derive(Clone)]
+pub(crate) enum Input {
+    Bool(bool),
+    /// Integers and floats, kept as text until the call so partial input can be typed
+    Number(Type, String),
+    Char(String),
+    String(String),
+    List(Type, Vec<Input>),
+    Record(Vec<(String, Input)>),
+    Tuple(Vec<Input>),
+    Enum(Vec<String>, usize),
+    Option(Box<Input>, bool),
+    Variant(Vec<(String, Option<Type>)>, usize, Option<Box<Input>>),
+    Flags(Vec<(String, bool)>),
+    /// Types which can't be entered in a form, such as resources
+    Unsupported(String),
+}
+
+impl Input {
+    /// An empty input for `ty`
+    pub(crate) fn new(ty: &Type) -> Self {
+        match ty {
+            Type::Bool => Input::Bool(false),
+            Type::S8
+            | Type::U8
+            | Type::S16
+            | Type::U16
+            | Type::S32
+            | Type::U32
+            | Type::S64
+            | Type::U64
+            | Type::Float32
+            | Type::Float64 => Input::Number(ty.clone(), "0".into()),
+            Type::Char => Input::Char(String::new()),
+            Type::String => Input::String(String::new()),
+            Type::List(list) => Input::List(list.ty(), Vec::new()),
+            Type::Record(record) => Input::Record(
+                record
+                    .fields()
+                    .map(|field| (field.name.to_owned(), Input::new(&field.ty)))
+                    .collect(),
+            ),
+            Type::Tuple(tuple) => Input::Tuple(tuple.types().map(|ty| Input::new(&ty)).collect()),
+            Type::Enum(e) => Input::Enum(e.names().map(str::to_owned).collect(), 0),
+            Type::Option(option) => Input::Option(Box::new(Input::new(&option.ty())), false),
+            Type::Variant(variant) => {
+                let cases: Vec<_> = variant
+                    .cases()
+                    .map(|case| (case.name.to_owned(), case.ty))
+                    .collect();
+                let payload = Self::payload(&cases, 0);
+                Input::Variant(cases, 0, payload)
+            }
+            Type::Flags(flags) => {
+                Input::Flags(flags.names().map(|n| (n.to_owned(), false)).collect())
+            }
+            Type::Result(_) | Type::Own(_) | Type::Borrow(_) => Input::Unsupported(type_name(ty)),
+        }
+    }
+
+    fn payload(cases: &[(String, Option<Type>)], selected: usize) -> Option<Box<Input>> {
+        cases
+            .get(selected)
+            .and_then(|(_, ty)| ty.as_ref())
+            .map(|ty| Box::new(Input::new(ty)))
+    }
+
+    /// Converts the input to a [Val], or explains why it can't be
+    pub(crate) fn to_val(&self) -> Result<Val, String> {
+        fn parse<T: std::str::FromStr>(text: &str) -> Result<T, String> {
+            text.trim()
+                .parse()
+                .map_err(|_| tr!("detail-not-a-number", text = text))
+        }
+
+        Ok(match self {
+            Input::Bool(b) => Val::Bool(*b),
+            Input::Number(ty, text) => match ty {
+                Type::S8 => Val::S8(parse(text)?),
+                Type::U8 => Val::U8(parse(text)?),
+                Type::S16 => Val::S16(parse(text)?),
+                Type::U16 => Val::U16(parse(text)?),
+                Type::S32 => Val::S32(parse(text)?),
+                Type::U32 => Val::U32(parse(text)?),
+                Type::S64 => Val::S64(parse(text)?),
+                Type::U64 => Val::U64(parse(text)?),
+                Type::Float32 => Val::Float32(parse(text)?),
+                _ => Val::Float64(parse(text)?),
+            },
+            Input::Char(text) => {
+                let mut chars = text.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Val::Char(c),
+                    _ => return Err(tr!("detail-not-a-char", text = text)),
+                }
+            }
+            Input::String(text) => Val::String(text.clone()),
+            Input::List(_, items) => {
+                Val::List(items.iter().map(Input::to_val).collect::<Result<_, _>>()?)
+            }
+            Input::Record(fields) => Val::Record(
+                fields
+                    .iter()
+                    .map(|(name, input)| Ok((name.clone(), input.to_val()?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+            Input::Tuple(items) => {
+                Val::Tuple(items.iter().map(Input::to_val).collect::<Result<_, _>>()?)
+            }
+            Input::Enum(names, selected) => Val::Enum(names[*selected].clone()),
+            Input::Option(inner, some) => {
+                Val::Option(some.then(|| inner.to_val()).transpose()?.map(Box::new))
+            }
+            Input::Variant(cases, selected, payload) => Val::Variant(
+                cases[*selected].0.clone(),
+                payload
+                    .as_ref()
+                    .map(|input| input.to_val())
+                    .transpose()?
+                    .map(Box::new),
+            ),
+            Input::Flags(flags) => Val::Flags(
+                flags
+                    .iter()
+                    .filter(|(_, set)| *set)
+                    .map(|(name, _)| name.clone())
+                    .collect(),
+            ),
+            Input::Unsupported(name) => return Err(tr!("detail-not-enterable", name = name)),
+        })
+    }
+
+    /// Form for this input
+    pub(crate) fn ui(&mut self, ui: &mut egui::Ui) {
+        match self {
+            Input::Bool(b) => {
+                ui.checkbox(b, "");
+            }
+            Input::Number(ty, text) => {
+                let valid = Input::Number(ty.clone(), text.clone()).to_val().is_ok();
+                let edit = egui::TextEdit::singleline(text).desired_width(120.0);
+                let edit = if valid {
+                    edit
+                } else {
+                    edit.text_color(ui.visuals().error_fg_color)
+                };
+                ui.add(edit);
+            }
+            Input::Char(text) | Input::String(text) => {
+                ui.text_edit_singleline(text);
+            }
+            Input::List(ty, items) => {
+                ui.vertical(|ui| {
+                    let mut remove = None;
+                    for (i, item) in items.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            if ui.small_button("🗑").clicked() {
+                                remove = Some(i);
+                            }
+                            item.ui(ui);
+                        });
+                    }
+                    if let Some(i) = remove {
+                        items.remove(i);
+                    }
+                    if ui.small_button(tr!("detail-add-item")).clicked() {
+                        items.push(Input::new(ty));
+                    }
+                });
+            }
+            Input::Record(fields) => {
+                ui.vertical(|ui| {
+                    for (name, input) in fields.iter_mut() {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{name}:"));
+                            input.ui(ui);
+                        });
+                    }
+                });
+            }
+            Input::Tuple(items) => {
+                ui.horizontal(|ui| {
+                    for item in items.iter_mut() {
+                        item.ui(ui);
+                    }
+                });
+            }
+            Input::Enum(names, selected) => {
+                let id = ui.next_auto_id();
+                egui::ComboBox::from_id_salt(id)
+                    .show_index(ui, selected, names.len(), |i| names[i].clone());
+            }
+            Input::Option(inner, some) => {
+                ui.horizontal(|ui| {
+                    ui.checkbox(some, tr!("detail-some"));
+                    ui.add_enabled_ui(*some, |ui| inner.ui(ui));
+                });
+            }
+            Input::Variant(cases, selected, payload) => {
+                ui.horizontal(|ui| {
+                    let before = *selected;
+                    let id = ui.next_auto_id();
+                    egui::ComboBox::from_id_salt(id)
+                        .show_index(ui, selected, cases.len(), |i| cases[i].0.clone());
+                    if *selected != before {
+                        *payload = Input::payload(cases, *selected);
+                    }
+                    if let Some(payload) = payload {
+                        payload.ui(ui);
+                    }
+                });
+            }
+            Input::Flags(flags) => {
+                ui.horizontal_wrapped(|ui| {
+                    for (name, set) in flags.iter_mut() {
+                        ui.checkbox(set, name.as_str());
+                    }
+                });
+            }
+            Input::Unsupported(name) => {
+                ui.weak(tr!("detail-unsupported", name = name));
+            }
+        }
+    }
+}
+
+/// Form and last outcome for one exported function
+struct FuncForm {
+    func: ExportedFunc,
+    args: Vec<Input>,
+    /// Outcome of the last call, shared with the task running it
+    outcome: Arc<Mutex<Option<Outcome>>>,
+}
+
+enum Outcome {
+    Pending,
+    Done(Result<Vec<Val>, CallError>),
+}
+
+/// Detail view state for the selected plugin
+#[derive(Default)]
+pub(crate) struct PluginDetail {
+    /// The plugin being shown
+    selected: Option<String>,
+
+    /// Forms for the selected plugin's functions
+    forms: Vec<FuncForm>,
+}
+
+impl PluginDetail {
+    /// Shows the named plugin
+    pub(crate) fn select(&mut self, name: &str) {
+        self.selected = Some(name.to_owned());
+        self.forms.clear();
+    }
+
+    /// Shows the detail view.
+    ///
+    /// `plugins` are the running plugins, by name, and `on_done` is called from the plugin's
+    /// thread once each call completes.
+    pub(crate) fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        plugins: &BTreeMap<String, Plugin>,
+        on_done: impl Fn() + Send + Clone + 'static,
+    ) {
+        ui.heading(tr!("detail-title"));
+
+        if plugins.is_empty() {
+            ui.weak(tr!("detail-no-plugins"));
+            return;
+        }
+
+        let before = self.selected.clone();
+        egui::ComboBox::from_label(tr!("detail-plugin"))
+            .selected_text(
+                self.selected
+                    .clone()
+                    .unwrap_or_else(|| tr!("detail-select-plugin")),
+            )
+            .show_ui(ui, |ui| {
+                for name in plugins.keys() {
+                    ui.selectable_value(&mut self.selected, Some(name.clone()), name);
+                }
+            });
+
+        let Some((name, plugin)) = self
+            .selected
+            .as_ref()
+            .and_then(|name| plugins.get_key_value(name))
+        else {
+            return;
+        };
+
+        if self.selected != before || self.forms.is_empty() {
+            self.forms = plugin
+                .funcs
+                .iter()
+                .map(|func| FuncForm {
+                    args: func.params.iter().map(Input::new).collect(),
+                    func: func.clone(),
+                    outcome: Default::default(),
+                })
+                .collect();
+        }
+
+        if self.forms.is_empty() {
+            ui.weak(tr!("detail-no-functions"));
+            return;
+        }
+
+        egui::ScrollArea::vertical()
+            .id_salt("plugin_detail")
+            .show(ui, |ui| {
+                for form in self.forms.iter_mut() {
+                    let results = form
+                        .func
+                        .results
+                        .iter()
+                        .map(type_name)
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    let title = if results.is_empty() {
+                        form.func.name()
+                    } else {
+                        format!("{} → {results}", form.func.name())
+                    };
+
+                    egui::CollapsingHeader::new(title)
+                        .id_salt(("plugin_func", name, form.func.name()))
+                        .show(ui, |ui| {
+                            egui::Grid::new(("plugin_args", name, form.func.name()))
+                                .num_columns(2)
+                                .show(ui, |ui| {
+                                    for (i, (arg, ty)) in
+                                        form.args.iter_mut().zip(&form.func.params).enumerate()
+                                    {
+                                        ui.label(format!("arg{i}: {}", type_name(ty)));
+                                        arg.ui(ui);
+                                        ui.end_row();
+                                    }
+                                });
+
+                            let args: Result<Vec<Val>, String> =
+                                form.args.iter().map(Input::to_val).collect();
+                            let pending =
+                                matches!(*form.outcome.lock().unwrap(), Some(Outcome::Pending));
+
+                            ui.horizontal(|ui| {
+                                let call =
+                                    ui.add_enabled(!pending, egui::Button::new(tr!("detail-call")));
+                                if pending {
+                                    ui.spinner();
+                                }
+
+                                match args {
+                                    Err(e) => {
+                                        ui.colored_label(ui.visuals().error_fg_color, e);
+                                    }
+                                    Ok(args) if call.clicked() => {
+                                        *form.outcome.lock().unwrap() = Some(Outcome::Pending);
+                                        let outcome = form.outcome.clone();
+                                        let on_done = on_done.clone();
+                                        let sent = plugin.call(form.func.clone(), args, {
+                                            let outcome = outcome.clone();
+                                            move |result| {
+                                                *outcome.lock().unwrap() =
+                                                    Some(Outcome::Done(result));
+                                                on_done();
+                                            }
+                                        });
+                                        if let Err(e) = sent {
+                                            *outcome.lock().unwrap() = Some(Outcome::Done(Err(e)));
+                                        }
+                                    }
+                                    Ok(_) => {}
+                                }
+                            });
+
+                            if let Some(Outcome::Done(result)) = &*form.outcome.lock().unwrap() {
+                                match result {
+                                    Ok(vals) if vals.is_empty() => {
+                                        ui.label(tr!("detail-returned"));
+                                    }
+                                    Ok(vals) => {
+                                        for (val, ty) in vals.iter().zip(&form.func.results) {
+                                            ui.horizontal_wrapped(|ui| {
+                                                ui.weak(type_name(ty));
+                                                ui.monospace(format_val(val));
+                                            });
+                                        }
+                                    }
+                                    Err(e) => {
+                                        ui.colored_label(
+                                            ui.visuals().error_fg_color,
+                                            e.to_string(),
+                                        );
+                                    }
+                                }
+                            }
+                        });
+                }
+            });
+    }
+}
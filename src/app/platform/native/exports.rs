@@ -0,0 +1,461 @@
+//! Exported functions of a plugin component.
+//!
+//! Lists every function a plugin exports, along with its WIT parameter and result types, and
+//! calls them in the plugin's running instance, so plugins can be tried out interactively. Calls
+//! are metered by the plugin's [ResourceLimits](super::limits::ResourceLimits) like any other.
+
+use multinode_i18n::tr;
+use wasmtime::component::{types, Component, Val};
+use wasmtime::Engine;
+
+use super::runtime::{engine, CallError, Loaded, Plugin};
+
+/// A function exported by a plugin
+#[derive(Clone, Debug)]
+pub(crate) struct ExportedFunc {
+    /// Names of the exported instances leading to the function, ending with the function name
+    pub(crate) path: Vec<String>,
+
+    /// Parameter types, in order
+    pub(crate) params: Vec<types::Type>,
+
+    /// Result types, in order
+    pub(crate) results: Vec<types::Type>,
+}
+
+impl ExportedFunc {
+    /// Fully qualified name, such as `peerpiper:plugin/api#hello`
+    pub(crate) fn name(&self) -> String {
+        match self.path.split_last() {
+            Some((func, [])) => func.clone(),
+            Some((func, instances)) => format!("{}#{func}", instances.join("/")),
+            None => String::new(),
+        }
+    }
+}
+
+/// Every function the component exports, including those in exported interfaces
+pub(super) fn funcs(component: &Component) -> Vec<ExportedFunc> {
+    let engine = engine();
+    let mut funcs = Vec::new();
+    for (name, item) in component.component_type().exports(engine) {
+        collect(engine, vec![name.to_owned()], item, &mut funcs);
+    }
+    funcs
+}
+
+/// Calls `func` with `args` in the plugin's instance, within its limits
+pub(super) fn call(
+    loaded: &mut Loaded,
+    func: &ExportedFunc,
+    args: &[Val],
+) -> Result<Vec<Val>, CallError> {
+    let mut index = None;
+    for name in &func.path {
+        index = Some(
+            loaded
+                .instance
+                .get_export(&mut loaded.store, index.as_ref(), name)
+                .ok_or_else(|| CallError::Failed(tr!("plugin-no-export", name = name)))?,
+        );
+    }
+    let f = index
+        .and_then(|index| loaded.instance.get_func(&mut loaded.store, index))
+        .ok_or_else(|| CallError::Failed(tr!("plugin-not-a-function", name = func.name())))?;
+
+    // Arguments of the wrong type would be refused only once the instance was entered, which
+    // leaves it unusable
+    let typed = args.len() == func.params.len()
+        && args
+            .iter()
+            .zip(&func.params)
+            .all(|(arg, ty)| has_type(arg, ty));
+    if !typed {
+        let params = func.params.iter().map(type_name).collect::<Vec<_>>();
+        return Err(CallError::Failed(tr!(
+            "plugin-takes",
+            name = func.name(),
+            params = params.join(", ")
+        )));
+    }
+
+    loaded.call(|loaded| {
+        let mut results = vec![Val::Bool(false); func.results.len()];
+        f.call(&mut loaded.store, args, &mut results)?;
+        f.post_return(&mut loaded.store)?;
+        Ok(results)
+    })
+}
+
+impl Plugin {
+    /// Calls `func` with `args` after the jobs sent before it, handing the outcome to `on_done`.
+    ///
+    /// Fails without calling `on_done` if the plugin is too busy to take the call.
+    pub(crate) fn call(
+        &self,
+        func: ExportedFunc,
+        args: Vec<Val>,
+        on_done: impl FnOnce(Result<Vec<Val>, CallError>) + Send + 'static,
+    ) -> Result<(), CallError> {
+        self.run(move |loaded| on_done(call(loaded, &func, &args)))
+    }
+}
+
+/// Recursively collects the functions in an exported item
+fn collect(
+    engine: &Engine,
+    path: Vec<String>,
+    item: types::ComponentItem,
+    funcs: &mut Vec<ExportedFunc>,
+) {
+    match item {
+        types::ComponentItem::ComponentFunc(func) => funcs.push(ExportedFunc {
+            path,
+            params: func.params().collect(),
+            results: func.results().collect(),
+        }),
+        types::ComponentItem::ComponentInstance(instance) => {
+            for (name, item) in instance.exports(engine) {
+                let mut path = path.clone();
+                path.push(name.to_owned());
+                collect(engine, path, item, funcs);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// WIT name of a type, such as `list<string>`
+pub(crate) fn type_name(ty: &types::Type) -> String {
+    use types::Type;
+
+    match ty {
+        Type::Bool => "bool".into(),
+        Type::S8 => "s8".into(),
+        Type::U8 => "u8".into(),
+        Type::S16 => "s16".into(),
+        Type::U16 => "u16".into(),
+        Type::S32 => "s32".into(),
+        Type::U32 => "u32".into(),
+        Type::S64 => "s64".into(),
+        Type::U64 => "u64".into(),
+        Type::Float32 => "f32".into(),
+        Type::Float64 => "f64".into(),
+        Type::Char => "char".into(),
+        Type::String => "string".into(),
+        Type::List(list) => format!("list<{}>", type_name(&list.ty())),
+        Type::Record(record) => {
+            let fields = record
+                .fields()
+                .map(|field| format!("{}: {}", field.name, type_name(&field.ty)))
+                .collect::<Vec<_>>();
+            format!("record {{ {} }}", fields.join(", "))
+        }
+        Type::Tuple(tuple) => {
+            let types = tuple.types().map(|ty| type_name(&ty)).collect::<Vec<_>>();
+            format!("tuple<{}>", types.join(", "))
+        }
+        Type::Variant(variant) => {
+            let cases = variant.cases().map(|case| case.name).collect::<Vec<_>>();
+            format!("variant {{ {} }}", cases.join(", "))
+        }
+        Type::Enum(e) => format!("enum {{ {} }}", e.names().collect::<Vec<_>>().join(", ")),
+        Type::Option(option) => format!("option<{}>", type_name(&option.ty())),
+        Type::Result(result) => {
+            let name = |ty: Option<types::Type>| ty.map(|ty| type_name(&ty)).unwrap_or("_".into());
+            format!("result<{}, {}>", name(result.ok()), name(result.err()))
+        }
+        Type::Flags(flags) => format!(
+            "flags {{ {} }}",
+            flags.names().collect::<Vec<_>>().join(", ")
+        ),
+        Type::Own(_) => "own<resource>".into(),
+        Type::Borrow(_) => "borrow<resource>".into(),
+    }
+}
+
+/// Whether `val` is of type `ty`
+fn has_type(val: &Val, ty: &types::Type) -> bool {
+    use types::Type;
+
+    let payload = |val: &Option<Box<Val>>, ty: Option<Type>| match (val, ty) {
+        (None, None) => true,
+        (Some(val), Some(ty)) => has_type(val, &ty),
+        _ => false,
+    };
+
+    match (val, ty) {
+        (Val::Bool(_), Type::Bool)
+        | (Val::S8(_), Type::S8)
+        | (Val::U8(_), Type::U8)
+        | (Val::S16(_), Type::S16)
+        | (Val::U16(_), Type::U16)
+        | (Val::S32(_), Type::S32)
+        | (Val::U32(_), Type::U32)
+        | (Val::S64(_), Type::S64)
+        | (Val::U64(_), Type::U64)
+        | (Val::Float32(_), Type::Float32)
+        | (Val::Float64(_), Type::Float64)
+        | (Val::Char(_), Type::Char)
+        | (Val::String(_), Type::String)
+        | (Val::Resource(_), Type::Own(_) | Type::Borrow(_)) => true,
+        (Val::List(vals), Type::List(list)) => vals.iter().all(|val| has_type(val, &list.ty())),
+        (Val::Record(fields), Type::Record(record)) => {
+            fields.len() == record.fields().len()
+                && fields
+                    .iter()
+                    .zip(record.fields())
+                    .all(|((name, val), field)| name == field.name && has_type(val, &field.ty))
+        }
+        (Val::Tuple(vals), Type::Tuple(tuple)) => {
+            vals.len() == tuple.types().len()
+                && vals
+                    .iter()
+                    .zip(tuple.types())
+                    .all(|(val, ty)| has_type(val, &ty))
+        }
+        (Val::Variant(name, val), Type::Variant(variant)) => variant
+            .cases()
+            .find(|case| case.name == name)
+            .is_some_and(|case| payload(val, case.ty)),
+        (Val::Enum(name), Type::Enum(e)) => e.names().any(|case| case == name),
+        (Val::Option(None), Type::Option(_)) => true,
+        (Val::Option(Some(val)), Type::Option(option)) => has_type(val, &option.ty()),
+        (Val::Result(Ok(val)), Type::Result(result)) => payload(val, result.ok()),
+        (Val::Result(Err(val)), Type::Result(result)) => payload(val, result.err()),
+        (Val::Flags(names), Type::Flags(flags)) => names
+            .iter()
+            .all(|name| flags.names().any(|flag| flag == name)),
+        _ => false,
+    }
+}
+
+/// Displays a value in WAVE-like syntax, such as `{ name: "a", size: 3 }`
+pub(crate) fn format_val(val: &Val) -> String {
+    let join = |vals: &[Val]| vals.iter().map(format_val).collect::<Vec<_>>().join(", ");
+    let payload = |val: &Option<Box<Val>>| {
+        val.as_ref()
+            .map(|val| format!("({})", format_val(val)))
+            .unwrap_or_default()
+    };
+
+    match val {
+        Val::Bool(v) => v.to_string(),
+        Val::S8(v) => v.to_string(),
+        Val::U8(v) => v.to_string(),
+        Val::S16(v) => v.to_string(),
+        Val::U16(v) => v.to_string(),
+        Val::S32(v) => v.to_string(),
+        Val::U32(v) => v.to_string(),
+        Val::S64(v) => v.to_string(),
+        Val::U64(v) => v.to_string(),
+        Val::Float32(v) => v.to_string(),
+        Val::Float64(v) => v.to_string(),
+        Val::Char(v) => format!("{v:?}"),
+        Val::String(v) => format!("{v:?}"),
+        Val::List(vals) => format!("[{}]", join(vals)),
+        Val::Record(fields) => {
+            let fields = fields
+                .iter()
+                .map(|(name, val)| format!("{name}: {}", format_val(val)))
+                .collect::<Vec<_>>();
+            format!("{{ {} }}", fields.join(", "))
+        }
+        Val::Tuple(vals) => format!("({})", join(vals)),
+        Val::Variant(case, val) => format!("{case}{}", payload(val)),
+        Val::Enum(case) => case.clone(),
+        Val::Option(None) => "none".into(),
+        Val::Option(Some(val)) => format!("some({})", format_val(val)),
+        Val::Result(Ok(val)) => format!("ok{}", payload(val)),
+        Val::Result(Err(val)) => format!("err{}", payload(val)),
+        Val::Flags(flags) => format!("{{ {} }}", flags.join(", ")),
+        Val::Resource(_) => "<resource>".into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::limits::{LimitViolation, ResourceLimits};
+    use super::super::runtime::Imports;
+    use super::*;
+    use std::sync::{mpsc, Arc, Mutex};
+
+    const COMPONENT: &str = r#"
+        (component
+          (core module $m
+            (memory 1)
+            (func (export "add") (param i32 i32) (result i32)
+              local.get 0
+              local.get 1
+              i32.add)
+            (func (export "spin")
+              (loop $l (br $l)))
+            (func (export "grow") (param i32) (result i32)
+              (memory.grow (local.get 0))))
+          (core instance $i (instantiate $m))
+          (func (export "add") (param "a" u32) (param "b" u32) (result u32)
+            (canon lift (core func $i "add")))
+          (func (export "spin")
+            (canon lift (core func $i "spin")))
+          (func (export "grow") (param "pages" u32) (result s32)
+            (canon lift (core func $i "grow"))))
+    "#;
+
+    fn start(wat: &str, limits: ResourceLimits, imports: Imports) -> Plugin {
+        let wasm = wat::parse_str(wat).unwrap();
+        Plugin::start("test", wasm, limits, imports, |_| {})
+            .unwrap()
+            .plugin
+    }
+
+    /// Calls the named function, waiting for the outcome
+    fn call_now(plugin: &Plugin, name: &str, args: Vec<Val>) -> Result<Vec<Val>, CallError> {
+        let func = plugin.funcs.iter().find(|f| f.name() == name).unwrap();
+        let (done, outcome) = mpsc::channel();
+        plugin
+            .call(func.clone(), args, move |result| done.send(result).unwrap())
+            .unwrap();
+        outcome.recv().unwrap()
+    }
+
+    #[test]
+    fn lists_exported_functions() {
+        let plugin = start(COMPONENT, ResourceLimits::default(), Imports::default());
+        let add = plugin.funcs.iter().find(|f| f.name() == "add").unwrap();
+
+        assert_eq!(add.params.len(), 2);
+        assert_eq!(type_name(&add.params[0]), "u32");
+        assert_eq!(type_name(&add.results[0]), "u32");
+        assert!(plugin.funcs.iter().any(|f| f.name() == "spin"));
+    }
+
+    #[test]
+    fn calls_exported_function() {
+        let plugin = start(COMPONENT, ResourceLimits::default(), Imports::default());
+        let results = call_now(&plugin, "add", vec![Val::U32(2), Val::U32(3)]).unwrap();
+
+        assert_eq!(results, vec![Val::U32(5)]);
+    }
+
+    #[test]
+    fn bad_arguments_leave_the_plugin_running() {
+        let wasm = wat::parse_str(COMPONENT).unwrap();
+        let crashed = Arc::new(Mutex::new(None));
+        let on_crash = {
+            let crashed = crashed.clone();
+            move |e| *crashed.lock().unwrap() = Some(e)
+        };
+        let plugin = Plugin::start(
+            "test",
+            wasm,
+            ResourceLimits::default(),
+            Imports::default(),
+            on_crash,
+        )
+        .unwrap()
+        .plugin;
+
+        let args = vec![Val::String("2".into()), Val::U32(3)];
+        assert!(matches!(
+            call_now(&plugin, "add", args),
+            Err(CallError::Failed(_))
+        ));
+        assert!(crashed.lock().unwrap().is_none());
+
+        let results = call_now(&plugin, "add", vec![Val::U32(2), Val::U32(3)]).unwrap();
+        assert_eq!(results, vec![Val::U32(5)]);
+    }
+
+    #[test]
+    fn runaway_call_hits_fuel_limit() {
+        let limits = ResourceLimits {
+            fuel: 1_000,
+            ..Default::default()
+        };
+        let plugin = start(COMPONENT, limits, Imports::default());
+
+        assert_eq!(
+            call_now(&plugin, "spin", vec![]),
+            Err(CallError::Limit(LimitViolation::Fuel(1_000)))
+        );
+    }
+
+    #[test]
+    fn runaway_call_hits_time_limit() {
+        let limits = ResourceLimits {
+            fuel: u64::MAX,
+            timeout_ms: 50,
+            ..Default::default()
+        };
+        let plugin = start(COMPONENT, limits, Imports::default());
+
+        assert!(matches!(
+            call_now(&plugin, "spin", vec![]),
+            Err(CallError::Limit(LimitViolation::Timeout(_)))
+        ));
+    }
+
+    #[test]
+    fn growing_memory_hits_memory_limit() {
+        let limits = ResourceLimits {
+            memory_mib: 1,
+            ..Default::default()
+        };
+        let plugin = start(COMPONENT, limits, Imports::default());
+
+        // 16 pages = 1 MiB, on top of the page it starts with
+        assert!(matches!(
+            call_now(&plugin, "grow", vec![Val::U32(16)]),
+            Err(CallError::Limit(LimitViolation::Memory { .. }))
+        ));
+    }
+
+    #[test]
+    fn calls_reach_plugin_storage() {
+        const FORGET: &str = r#"
+            (component
+              (import "peerpiper:storage/kv" (instance $kv
+                (export "delete" (func (param "key" string)))))
+              (core module $mem
+                (memory (export "mem") 1)
+                (data (i32.const 0) "gone"))
+              (core instance $m (instantiate $mem))
+              (core func $delete (canon lower (func $kv "delete") (memory $m "mem")))
+              (core module $main
+                (import "kv" "delete" (func $delete (param i32 i32)))
+                (func (export "forget")
+                  (call $delete (i32.const 0) (i32.const 4))))
+              (core instance $i
+                (instantiate $main (with "kv" (instance (export "delete" (func $delete))))))
+              (func (export "forget") (canon lift (core func $i "forget"))))
+        "#;
+
+        let store = super::super::storage::KvStore::default();
+        store.set("p", "gone".into(), b"soon".to_vec()).unwrap();
+        store.set("p", "kept".into(), b"here".to_vec()).unwrap();
+
+        let imports = Imports {
+            kv: store.namespace("p"),
+            ..Default::default()
+        };
+        let plugin = start(FORGET, ResourceLimits::default(), imports);
+        call_now(&plugin, "forget", vec![]).unwrap();
+
+        assert_eq!(store.get("p", "gone"), None);
+        assert_eq!(store.get("p", "kept"), Some(b"here".to_vec()));
+    }
+
+    #[test]
+    fn formats_values() {
+        let val = Val::Record(vec![
+            ("name".into(), Val::String("a".into())),
+            ("tags".into(), Val::List(vec![Val::U8(1), Val::U8(2)])),
+            ("next".into(), Val::Option(None)),
+        ]);
+
+        assert_eq!(
+            format_val(&val),
+            r#"{ name: "a", tags: [1, 2], next: none }"#
+        );
+    }
+}
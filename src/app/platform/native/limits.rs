@@ -0,0 +1,207 @@
+//! Per-plugin resource limits.
+//!
+//! A plugin runs inside the same process as the UI, so a buggy one must not be able to hog the
+//! node. Each plugin gets a [ResourceLimits], whose memory is checked before the plugin is
+//! compiled, and which the [runtime](super::runtime) enforces while loading it and around every
+//! call into it: the [Store](wasmtime::Store) running the plugin meters fuel, interrupts calls
+//! past their deadline and traps growing memory past the limit with a [MemoryLimiter].
+
+use std::fmt;
+use std::time::Duration;
+
+use multinode_i18n::tr;
+
+/// Size of a wasm page, in bytes
+const WASM_PAGE_SIZE: u64 = 64 * 1024;
+
+/// Resource limits applied to a single plugin
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ResourceLimits {
+    /// Maximum number of instructions (fuel) a single call may consume
+    pub fuel: u64,
+
+    /// Maximum linear memory the plugin may declare or grow to, in MiB
+    pub memory_mib: u64,
+
+    /// Maximum wall-clock time per call, in milliseconds
+    pub timeout_ms: u64,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            fuel: 10_000_000,
+            memory_mib: 64,
+            timeout_ms: 5_000,
+        }
+    }
+}
+
+impl ResourceLimits {
+    /// Wall-clock time allowed for a single call into the plugin
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+
+    /// Maximum linear memory, in bytes
+    pub fn memory_bytes(&self) -> u64 {
+        self.memory_mib.saturating_mul(1024 * 1024)
+    }
+
+    /// Checks the memories declared by the wasm module or component against these limits.
+    ///
+    /// Every core module nested in a component is inspected. A memory without a declared
+    /// maximum can grow at runtime, so only its initial size can be checked here, and the
+    /// [MemoryLimiter] stops it growing past the limit.
+    pub fn check_memory(&self, wasm: &[u8]) -> Result<(), LimitViolation> {
+        let limit = self.memory_bytes();
+
+        for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+            let payload = payload.map_err(|e| LimitViolation::Invalid(e.to_string()))?;
+
+            if let wasmparser::Payload::MemorySection(reader) = payload {
+                for memory in reader {
+                    let memory = memory.map_err(|e| LimitViolation::Invalid(e.to_string()))?;
+                    let page_size = memory
+                        .page_size_log2
+                        .map(|log2| 1u64 << log2)
+                        .unwrap_or(WASM_PAGE_SIZE);
+                    let pages = memory.maximum.unwrap_or(memory.initial);
+                    let requested = pages.saturating_mul(page_size);
+
+                    if requested > limit {
+                        return Err(LimitViolation::Memory { requested, limit });
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A plugin exceeded one of its [ResourceLimits]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LimitViolation {
+    /// The plugin declares, or grew to, more linear memory than allowed
+    Memory { requested: u64, limit: u64 },
+
+    /// A call into the plugin took longer than allowed
+    Timeout(Duration),
+
+    /// A call into the plugin ran out of fuel
+    Fuel(u64),
+
+    /// The plugin bytes could not be parsed
+    Invalid(String),
+}
+
+impl fmt::Display for LimitViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            LimitViolation::Memory { requested, limit } => tr!(
+                "limit-memory",
+                requested = requested / 1024,
+                limit = limit / 1024
+            ),
+            LimitViolation::Timeout(duration) => {
+                tr!("limit-timeout", ms = duration.as_millis())
+            }
+            LimitViolation::Fuel(fuel) => tr!("limit-fuel", fuel = fuel),
+            LimitViolation::Invalid(e) => tr!("limit-invalid", error = e),
+        };
+        f.write_str(&message)
+    }
+}
+
+impl std::error::Error for LimitViolation {}
+
+/// Holds a running plugin to its memory limit, trapping it when it grows its memory past that
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MemoryLimiter {
+    /// Maximum linear memory, in bytes
+    pub(crate) limit: u64,
+}
+
+impl wasmtime::ResourceLimiter for MemoryLimiter {
+    fn memory_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        let requested = desired as u64;
+        if requested > self.limit {
+            return Err(LimitViolation::Memory {
+                requested,
+                limit: self.limit,
+            }
+            .into());
+        }
+        Ok(true)
+    }
+
+    fn table_growing(
+        &mut self,
+        _current: u32,
+        _desired: u32,
+        _maximum: Option<u32>,
+    ) -> wasmtime::Result<bool> {
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(memory_mib: u64) -> ResourceLimits {
+        ResourceLimits {
+            memory_mib,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn memory_within_limit() {
+        // 16 pages = 1 MiB
+        let wasm = wat::parse_str("(module (memory 1 16))").unwrap();
+        assert_eq!(limits(1).check_memory(&wasm), Ok(()));
+    }
+
+    #[test]
+    fn memory_maximum_over_limit() {
+        let wasm = wat::parse_str("(module (memory 1 17))").unwrap();
+        assert!(matches!(
+            limits(1).check_memory(&wasm),
+            Err(LimitViolation::Memory { .. })
+        ));
+    }
+
+    #[test]
+    fn unbounded_memory_checks_initial_size() {
+        let wasm = wat::parse_str("(module (memory 1))").unwrap();
+        assert_eq!(limits(1).check_memory(&wasm), Ok(()));
+
+        let wasm = wat::parse_str("(module (memory 32))").unwrap();
+        assert!(limits(1).check_memory(&wasm).is_err());
+    }
+
+    #[test]
+    fn nested_component_modules_are_checked() {
+        let wasm = wat::parse_str("(component (core module (memory 1 32)))").unwrap();
+        assert!(matches!(
+            limits(1).check_memory(&wasm),
+            Err(LimitViolation::Memory { .. })
+        ));
+    }
+
+    #[test]
+    fn garbage_is_invalid() {
+        assert!(matches!(
+            limits(1).check_memory(b"not wasm"),
+            Err(LimitViolation::Invalid(_))
+        ));
+    }
+}
@@ -0,0 +1,286 @@
+//! Plugin-contributed UI panels.
+//!
+//! A plugin which exports the `peerpiper:ui/panel` interface from `wit/ui.wit` describes simple
+//! UI as a list of elements. The host shows them in the plugin's own tab and routes each
+//! interaction back to the plugin, which then renders again. Panels may keep state across
+//! restarts through the `peerpiper:storage/kv` import, talk to other plugins through
+//! `peerpiper:bus/pubsub`, and ask the wallet for signatures through `peerpiper:wallet/signer`.
+
+use std::sync::{Arc, Mutex};
+
+use super::runtime::{CallError, Loaded, Plugin};
+
+wasmtime::component::bindgen!({
+    path: "wit",
+    world: "panel-plugin",
+    with: {
+        "peerpiper:storage/kv": super::storage::kv,
+        "peerpiper:bus/pubsub": super::bus::pubsub,
+        "peerpiper:wallet/signer": super::signer::signer,
+    },
+});
+
+use exports::peerpiper::ui::panel::{Element, Event, TextChange};
+
+/// Handles `event`, if any, then renders the elements again
+fn update(loaded: &mut Loaded, event: Option<Event>) -> Result<Vec<Element>, CallError> {
+    loaded.call(|loaded| {
+        let Loaded { store, panel, .. } = loaded;
+        let Some(panel) = panel else {
+            return Ok(Vec::new());
+        };
+        let panel = panel.peerpiper_ui_panel();
+        if let Some(event) = event {
+            panel.call_handle(&mut *store, &event)?;
+        }
+        panel.call_render(store)
+    })
+}
+
+/// Opens the plugin's panel, if it exports one
+pub(super) fn open(loaded: &mut Loaded) -> Result<Option<PluginPanel>, CallError> {
+    if loaded.panel.is_none() {
+        return Ok(None);
+    }
+    let title = loaded.call(|loaded| {
+        let Loaded { store, panel, .. } = loaded;
+        match panel {
+            Some(panel) => panel.peerpiper_ui_panel().call_title(store),
+            None => Ok(String::new()),
+        }
+    })?;
+    let elements = update(loaded, None)?;
+
+    Ok(Some(PluginPanel {
+        view: Arc::new(Mutex::new(View {
+            title,
+            elements,
+            error: None,
+        })),
+        open: true,
+    }))
+}
+
+/// What the panel shows
+#[derive(Default)]
+struct View {
+    title: String,
+    elements: Vec<Element>,
+    /// The last call failed, so the elements may be stale
+    error: Option<CallError>,
+}
+
+/// A plugin's UI panel
+#[derive(Clone)]
+pub(crate) struct PluginPanel {
+    view: Arc<Mutex<View>>,
+
+    /// Whether the tab is open
+    pub(crate) open: bool,
+}
+
+impl PluginPanel {
+    /// Title of the panel tab
+    pub(crate) fn title(&self) -> String {
+        self.view.lock().unwrap().title.clone()
+    }
+
+    /// Sends `event` to the plugin, after the events sent before it, then renders again.
+    ///
+    /// `on_done` is called once the plugin has re-rendered. If the plugin is too busy to take
+    /// the event, the panel shows so instead.
+    fn send(&self, plugin: &Plugin, event: Event, on_done: impl FnOnce() + Send + 'static) {
+        self.update(plugin, Some(event), on_done);
+    }
+
+    /// Renders the panel again, after the jobs sent to the plugin before, such as an action
+    /// which changed what it shows
+    pub(crate) fn refresh(&self, plugin: &Plugin, on_done: impl FnOnce() + Send + 'static) {
+        self.update(plugin, None, on_done);
+    }
+
+    /// Sends `event`, if any, to the plugin, then renders again
+    fn update(
+        &self,
+        plugin: &Plugin,
+        event: Option<Event>,
+        on_done: impl FnOnce() + Send + 'static,
+    ) {
+        let view = self.view.clone();
+        let sent = plugin.run(move |loaded| {
+            let result = update(loaded, event);
+            let mut view = view.lock().unwrap();
+            match result {
+                Ok(elements) => {
+                    view.elements = elements;
+                    view.error = None;
+                }
+                Err(e) => view.error = Some(e),
+            }
+            drop(view);
+            on_done();
+        });
+        if let Err(e) = sent {
+            self.view.lock().unwrap().error = Some(e);
+        }
+    }
+
+    /// Shows the panel's elements, routing interactions back to the plugin
+    pub(crate) fn ui(
+        &self,
+        ui: &mut egui::Ui,
+        plugin: &Plugin,
+        on_done: impl Fn() + Clone + Send + 'static,
+    ) {
+        let mut events = Vec::new();
+
+        {
+            let mut view = self.view.lock().unwrap();
+
+            if let Some(e) = &view.error {
+                ui.colored_label(ui.visuals().error_fg_color, e.to_string());
+            }
+
+            for (i, element) in view.elements.iter_mut().enumerate() {
+                match element {
+                    Element::Heading(text) => {
+                        ui.heading(text.as_str());
+                    }
+                    Element::Label(text) => {
+                        ui.label(text.as_str());
+                    }
+                    Element::Button(button) => {
+                        if ui.button(&button.text).clicked() {
+                            events.push(Event::Clicked(button.id.clone()));
+                        }
+                    }
+                    Element::TextInput(input) => {
+                        ui.horizontal(|ui| {
+                            ui.label(&input.label);
+                            // Edit the local copy so typing stays responsive until re-rendered
+                            if ui.text_edit_singleline(&mut input.value).changed() {
+                                events.push(Event::TextChanged(TextChange {
+                                    id: input.id.clone(),
+                                    value: input.value.clone(),
+                                }));
+                            }
+                        });
+                    }
+                    Element::Table(table) => {
+                        egui::Grid::new(ui.id().with(("plugin_table", i)))
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for header in &table.headers {
+                                    ui.strong(header);
+                                }
+                                ui.end_row();
+
+                                for row in &table.rows {
+                                    for cell in row {
+                                        ui.label(cell);
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    }
+                    Element::Separator => {
+                        ui.separator();
+                    }
+                }
+            }
+        }
+
+        for event in events {
+            self.send(plugin, event, on_done.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::limits::ResourceLimits;
+    use super::super::runtime::Imports;
+    use super::*;
+    use std::sync::mpsc;
+
+    /// Shows the id of the last button clicked, and traps on text changes
+    const ECHO: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/panel.wat"
+    ));
+
+    fn start(wat: &str) -> Result<(Plugin, Option<PluginPanel>), CallError> {
+        let wasm = wat::parse_str(wat).unwrap();
+        let started = Plugin::start(
+            "echo",
+            wasm,
+            ResourceLimits::default(),
+            Imports::default(),
+            |_| {},
+        )?;
+        Ok((started.plugin, started.panel))
+    }
+
+    /// The text of the panel's only element, once the events sent so far are handled
+    fn label(plugin: &Plugin, panel: &PluginPanel) -> String {
+        let (done, handled) = mpsc::channel();
+        plugin.run(move |_| done.send(()).unwrap()).unwrap();
+        handled.recv().unwrap();
+
+        match panel.view.lock().unwrap().elements.as_slice() {
+            [Element::Label(text)] => text.clone(),
+            _ => panic!("expected a single label"),
+        }
+    }
+
+    #[test]
+    fn handles_events_in_order() {
+        let (plugin, panel) = start(ECHO).unwrap();
+        let panel = panel.unwrap();
+        assert_eq!(panel.title(), "Echo");
+        assert_eq!(label(&plugin, &panel), "none");
+
+        for i in 0..50 {
+            panel.send(&plugin, Event::Clicked(i.to_string()), || {});
+        }
+        assert_eq!(label(&plugin, &panel), "49");
+    }
+
+    #[test]
+    fn shows_failed_events() {
+        let (plugin, panel) = start(ECHO).unwrap();
+        let panel = panel.unwrap();
+
+        let change = TextChange {
+            id: "name".into(),
+            value: "x".into(),
+        };
+        panel.send(&plugin, Event::TextChanged(change), || {});
+        label(&plugin, &panel);
+
+        assert!(matches!(
+            panel.view.lock().unwrap().error,
+            Some(CallError::Failed(_))
+        ));
+    }
+
+    #[test]
+    fn plugins_without_a_panel_have_none() {
+        let (_, panel) = start("(component)").unwrap();
+        assert!(panel.is_none());
+    }
+
+    #[test]
+    fn misshapen_panels_fail_to_load() {
+        const TITLE_ONLY: &str = r#"
+            (component
+              (core module $m (func (export "title") (result i32) (i32.const 0)))
+              (core instance $i (instantiate $m))
+              (func $title (result u32) (canon lift (core func $i "title")))
+              (instance $panel (export "title" (func $title)))
+              (export "peerpiper:ui/panel" (instance $panel)))
+        "#;
+
+        assert!(matches!(start(TITLE_ONLY), Err(CallError::Failed(_))));
+    }
+}
@@ -0,0 +1,480 @@
+//! The app's instances of the loaded plugins.
+//!
+//! The node runs each plugin it's handed. The app compiles and instantiates the plugin once
+//! more, in a [Store] metered by its [ResourceLimits], for what the app itself does with it:
+//! calls from the detail view, its UI panel and the messages the bus delivers to it, which all
+//! see the same state. The instance lives on a thread of its own, which runs one job at a time
+//! in the order they were sent.
+//!
+//! The app only provides the host imports in `wit/deps`. Plugins importing anything else from
+//! the node get no instance here, rather than one trapping on its first call to the node.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, OnceLock, Weak};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use multinode_i18n::tr;
+use wasmtime::component::{Component, Instance, Linker};
+use wasmtime::{Config, Engine, Store, Trap, UpdateDeadline, WasmBacktrace};
+
+use super::actions::{self, ActionsPlugin, PluginAction};
+use super::bus::{self, pubsub, BusPlugin, Mailbox};
+use super::exports::{self, ExportedFunc};
+use super::limits::{LimitViolation, MemoryLimiter, ResourceLimits};
+use super::panel::{self, PanelPlugin, PluginPanel};
+use super::signer::{self, signer as sign};
+use super::storage::{kv, Namespace};
+
+/// How often the [Engine] epoch ticks, which bounds the wall-clock precision of a call
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// The shared [Engine], with fuel metering and epoch interruption enabled
+pub(super) fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut config = Config::new();
+        config
+            .wasm_component_model(true)
+            .consume_fuel(true)
+            .epoch_interruption(true);
+        let engine = Engine::new(&config).expect("Failed to create wasmtime Engine");
+
+        // Tick the epoch so calls past their deadline are interrupted
+        let ticker = engine.weak();
+        std::thread::spawn(move || {
+            while let Some(engine) = ticker.upgrade() {
+                engine.increment_epoch();
+                drop(engine);
+                std::thread::sleep(EPOCH_TICK);
+            }
+        });
+
+        engine
+    })
+}
+
+/// Epoch ticks in `duration`, at least one
+fn ticks(duration: Duration) -> u64 {
+    (duration.as_millis() / EPOCH_TICK.as_millis()).max(1) as u64
+}
+
+/// State behind the host imports of one plugin
+#[derive(Clone, Default)]
+pub(crate) struct Imports {
+    pub(crate) kv: Namespace,
+    pub(crate) pubsub: bus::Client,
+    pub(crate) signer: signer::Client,
+}
+
+/// Data of a plugin's [Store]: its memory limit and the state behind its host imports
+pub(super) struct Host {
+    limiter: MemoryLimiter,
+    imports: Imports,
+}
+
+/// A [Store] with the plugin's memory limit, but no fuel or deadline set yet.
+///
+/// Time spent waiting for the user to approve a signature doesn't count against the deadline:
+/// once reached, it's pushed back by as long as the plugin waited since the call started.
+fn store(limits: &ResourceLimits, imports: Imports) -> Store<Host> {
+    let mut store = Store::new(
+        engine(),
+        Host {
+            limiter: MemoryLimiter {
+                limit: limits.memory_bytes(),
+            },
+            imports,
+        },
+    );
+    store.limiter(|host| &mut host.limiter);
+    store.epoch_deadline_callback(|mut store| {
+        let waited = std::mem::take(&mut store.data_mut().imports.signer.waited);
+        if waited.is_zero() {
+            Err(Trap::Interrupt.into())
+        } else {
+            Ok(UpdateDeadline::Continue(ticks(waited)))
+        }
+    });
+    store
+}
+
+/// The host imports the app provides, see `wit/deps`
+const HOST_IMPORTS: [&str; 3] = [
+    "peerpiper:storage/kv",
+    "peerpiper:bus/pubsub",
+    "peerpiper:wallet/signer",
+];
+
+/// The first import of the component which the app doesn't provide, if any
+fn unsupported_import(component: &Component) -> Option<String> {
+    component
+        .component_type()
+        .imports(engine())
+        .map(|(name, _)| name)
+        .find(|name| !HOST_IMPORTS.contains(name))
+        .map(str::to_owned)
+}
+
+/// A [Linker] providing the host imports
+fn linker() -> wasmtime::Result<Linker<Host>> {
+    let mut linker = Linker::new(engine());
+    kv::add_to_linker(&mut linker, |host: &mut Host| &mut host.imports.kv)?;
+    pubsub::add_to_linker(&mut linker, |host: &mut Host| &mut host.imports.pubsub)?;
+    sign::add_to_linker(&mut linker, |host: &mut Host| &mut host.imports.signer)?;
+    Ok(linker)
+}
+
+/// Error calling into a plugin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum CallError {
+    /// The call exceeded one of the plugin's [ResourceLimits]
+    Limit(LimitViolation),
+
+    /// The call failed for another reason, such as a trap or bad arguments
+    Failed(String),
+
+    /// The plugin imports something only the node provides, so the app can't instantiate it
+    Unsupported(String),
+}
+
+impl std::fmt::Display for CallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CallError::Limit(violation) => write!(f, "{violation}"),
+            CallError::Failed(e) => write!(f, "{e}"),
+            CallError::Unsupported(import) => {
+                f.write_str(&tr!("plugin-unsupported-import", import = import))
+            }
+        }
+    }
+}
+
+/// Maps fuel, epoch and memory traps to a [LimitViolation]
+fn limit_or(e: wasmtime::Error, limits: &ResourceLimits) -> CallError {
+    if let Some(violation) = e.downcast_ref::<LimitViolation>() {
+        return CallError::Limit(violation.clone());
+    }
+    match e.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => CallError::Limit(LimitViolation::Fuel(limits.fuel)),
+        Some(Trap::Interrupt) => CallError::Limit(LimitViolation::Timeout(limits.timeout())),
+        _ => CallError::Failed(format!("{e:#}")),
+    }
+}
+
+/// How many jobs may wait for a plugin, beyond which it's too busy to take more
+const QUEUE: usize = 64;
+
+/// The instance of a loaded plugin, owned by the thread running it
+pub(super) struct Loaded {
+    pub(super) store: Store<Host>,
+    pub(super) instance: Instance,
+
+    /// Bindings of the panel, if the plugin exports one
+    pub(super) panel: Option<PanelPlugin>,
+
+    /// Bindings of the actions, if the plugin exports them
+    pub(super) actions: Option<ActionsPlugin>,
+
+    /// Bindings of the bus subscriber, if the plugin exports one
+    pub(super) subscriber: Option<BusPlugin>,
+
+    /// Where the bus delivers the plugin's messages
+    pub(super) mailbox: Weak<Mailbox>,
+
+    /// The limits of every call
+    limits: ResourceLimits,
+
+    /// Why the plugin crashed. A trap leaves the instance unusable, so it's never called again.
+    crashed: Option<CallError>,
+
+    /// Told when the plugin crashes
+    on_crash: Arc<dyn Fn(CallError) + Send + Sync>,
+}
+
+impl Loaded {
+    /// Calls into the plugin with `f`, refuelled and with a fresh deadline.
+    ///
+    /// A trap, including one for going over the limits, crashes the plugin. Errors the host
+    /// raises before entering it, such as arguments of the wrong type, are only returned.
+    pub(super) fn call<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> wasmtime::Result<T>,
+    ) -> Result<T, CallError> {
+        if let Some(e) = &self.crashed {
+            return Err(CallError::Failed(tr!(
+                "plugin-crashed",
+                error = e.to_string()
+            )));
+        }
+
+        let limits = self.limits;
+        let result = self.store.set_fuel(limits.fuel).and_then(|()| {
+            self.store.set_epoch_deadline(ticks(limits.timeout()));
+            self.store.data_mut().imports.signer.waited = Duration::ZERO;
+            f(self)
+        });
+
+        result.map_err(|e| {
+            // Errors which unwound through the plugin's code carry its backtrace
+            let trapped = e.downcast_ref::<Trap>().is_some()
+                || e.downcast_ref::<LimitViolation>().is_some()
+                || e.downcast_ref::<WasmBacktrace>().is_some();
+            let e = limit_or(e, &limits);
+            if trapped {
+                self.crash(e.clone());
+            }
+            e
+        })
+    }
+
+    /// Stops delivering messages to the plugin and tells of the crash
+    fn crash(&mut self, e: CallError) {
+        self.bus().detach(&self.mailbox);
+        self.panel = None;
+        self.actions = None;
+        self.subscriber = None;
+        self.crashed = Some(e.clone());
+        (self.on_crash)(e);
+    }
+
+    /// The bus as the plugin sees it
+    pub(super) fn bus(&self) -> &bus::Client {
+        &self.store.data().imports.pubsub
+    }
+}
+
+/// Work for the thread running a plugin
+pub(super) type Job = Box<dyn FnOnce(&mut Loaded) + Send>;
+
+/// A plugin which was loaded, and runs on a thread of its own until stopped or dropped
+pub(crate) struct Plugin {
+    jobs: mpsc::SyncSender<Job>,
+
+    /// Where the bus delivers the plugin's messages. The plugin's instance holds the bus
+    /// through its imports, so the bus only holds this weakly, lest the plugin keep itself
+    /// running.
+    mailbox: Arc<Mailbox>,
+
+    /// Set to skip the jobs still waiting once the plugin is stopped
+    stopped: Arc<AtomicBool>,
+
+    /// Every exported function, including those in exported interfaces
+    pub(crate) funcs: Vec<ExportedFunc>,
+
+    /// The actions the plugin lists in the command palette
+    pub(crate) actions: Vec<PluginAction>,
+
+    worker: JoinHandle<()>,
+}
+
+/// What the plugin showed when it was loaded
+pub(crate) struct Started {
+    pub(crate) plugin: Plugin,
+
+    /// The plugin's panel, if it exports one
+    pub(crate) panel: Option<PluginPanel>,
+}
+
+impl Plugin {
+    /// Compiles and instantiates the plugin, then opens its panel and subscribes it to its
+    /// topics, if it exports those.
+    ///
+    /// Compiling and instantiating must be done within the plugin's time limit. This blocks
+    /// until the plugin is running, so call it off the UI thread.
+    ///
+    /// `on_crash` is told if a call fails once the plugin is running, after which the plugin
+    /// isn't called again.
+    pub(crate) fn start(
+        name: &str,
+        wasm: Vec<u8>,
+        limits: ResourceLimits,
+        imports: Imports,
+        on_crash: impl Fn(CallError) + Send + Sync + 'static,
+    ) -> Result<Started, CallError> {
+        let (jobs, queue) = mpsc::sync_channel::<Job>(QUEUE);
+        let (started, outcome) = mpsc::sync_channel(1);
+
+        let deliveries = jobs.clone();
+        let mailbox: Arc<Mailbox> = Arc::new(move |message| {
+            let job: Job = Box::new(move |loaded| bus::deliver(loaded, message));
+            deliveries.try_send(job).is_ok()
+        });
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let name = name.to_owned();
+        let weak_mailbox = Arc::downgrade(&mailbox);
+        let stopping = stopped.clone();
+        let worker = std::thread::Builder::new()
+            .name(format!("plugin {name}"))
+            .spawn(move || {
+                let on_crash = Arc::new(on_crash);
+                let loaded = load(&name, &wasm, limits, imports, on_crash, weak_mailbox);
+                let mut loaded = match loaded {
+                    Ok((loaded, exported)) => {
+                        let _ = started.send(Ok(exported));
+                        loaded
+                    }
+                    Err(e) => {
+                        let _ = started.send(Err(e));
+                        return;
+                    }
+                };
+                for job in queue {
+                    if stopping.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    job(&mut loaded);
+                }
+                loaded.bus().detach(&loaded.mailbox);
+            })
+            .map_err(|e| CallError::Failed(e.to_string()))?;
+
+        let Exported {
+            funcs,
+            actions,
+            panel,
+        } = outcome
+            .recv()
+            .map_err(|_| CallError::Failed(tr!("plugin-stopped")))??;
+        Ok(Started {
+            plugin: Plugin {
+                jobs,
+                mailbox,
+                stopped,
+                funcs,
+                actions,
+                worker,
+            },
+            panel,
+        })
+    }
+
+    /// Runs `job` with the instance, after the jobs sent before it. Fails if too many jobs
+    /// are waiting.
+    pub(super) fn run(
+        &self,
+        job: impl FnOnce(&mut Loaded) + Send + 'static,
+    ) -> Result<(), CallError> {
+        self.jobs.try_send(Box::new(job)).map_err(|e| match e {
+            mpsc::TrySendError::Full(_) => CallError::Failed(tr!("plugin-busy")),
+            mpsc::TrySendError::Disconnected(_) => CallError::Failed(tr!("plugin-stopped")),
+        })
+    }
+
+    /// Sets the limits of the calls after those already sent
+    pub(crate) fn set_limits(&self, limits: ResourceLimits) {
+        let set = self.run(move |loaded| {
+            loaded.limits = limits;
+            loaded.store.data_mut().limiter.limit = limits.memory_bytes();
+        });
+        if let Err(e) = set {
+            tracing::warn!("Failed to set plugin limits: {e}");
+        }
+    }
+
+    /// Stops the plugin once the call it's in returns, skipping the jobs still waiting, and
+    /// waits for its thread to end
+    pub(crate) fn stop(self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        let Plugin {
+            jobs,
+            mailbox,
+            worker,
+            ..
+        } = self;
+        // The mailbox holds a sender too, so both are dropped to end the queue
+        drop((jobs, mailbox));
+        if worker.join().is_err() {
+            tracing::error!("A plugin's thread panicked");
+        }
+    }
+}
+
+/// What a loaded plugin exports to the host
+struct Exported {
+    funcs: Vec<ExportedFunc>,
+    actions: Vec<PluginAction>,
+    panel: Option<PluginPanel>,
+}
+
+/// Compiles and instantiates the plugin, before its deadline
+fn load(
+    name: &str,
+    wasm: &[u8],
+    limits: ResourceLimits,
+    imports: Imports,
+    on_crash: Arc<dyn Fn(CallError) + Send + Sync>,
+    mailbox: Weak<Mailbox>,
+) -> Result<(Loaded, Exported), CallError> {
+    let failed = |e: wasmtime::Error| CallError::Failed(format!("{e:#}"));
+    let started = Instant::now();
+
+    let component = Component::new(engine(), wasm).map_err(failed)?;
+    if let Some(import) = unsupported_import(&component) {
+        return Err(CallError::Unsupported(import));
+    }
+    let Some(left) = limits.timeout().checked_sub(started.elapsed()) else {
+        return Err(CallError::Limit(LimitViolation::Timeout(limits.timeout())));
+    };
+
+    let mut store = store(&limits, imports);
+    store.set_fuel(limits.fuel).map_err(failed)?;
+    store.set_epoch_deadline(ticks(left));
+    let linker = linker().map_err(failed)?;
+    let instance = linker
+        .instantiate(&mut store, &component)
+        .map_err(|e| limit_or(e, &limits))?;
+
+    let funcs = exports::funcs(&component);
+    let panel = PanelPlugin::new(&mut store, &instance);
+    let actions = ActionsPlugin::new(&mut store, &instance);
+    let subscriber = BusPlugin::new(&mut store, &instance);
+    let mut loaded = Loaded {
+        panel: bindings(&component, "peerpiper:ui/panel", panel)?,
+        actions: bindings(&component, "peerpiper:ui/actions", actions)?,
+        subscriber: bindings(&component, "peerpiper:bus/subscriber", subscriber)?,
+        store,
+        instance,
+        mailbox,
+        limits,
+        crashed: None,
+        // Failing to load is reported as such, rather than as a crash
+        on_crash: Arc::new(|_| {}),
+    };
+
+    let panel = panel::open(&mut loaded)?;
+    let actions = actions::list(&mut loaded)?;
+    bus::subscribe(&mut loaded, name)?;
+    loaded.on_crash = on_crash;
+    Ok((
+        loaded,
+        Exported {
+            funcs,
+            actions,
+            panel,
+        },
+    ))
+}
+
+/// The bindings of an interface the plugin may export. It's an error to export it, but not as
+/// the host expects.
+fn bindings<T>(
+    component: &Component,
+    interface: &str,
+    bindings: wasmtime::Result<T>,
+) -> Result<Option<T>, CallError> {
+    let exported = component
+        .component_type()
+        .exports(engine())
+        .any(|(name, _)| name == interface);
+    match bindings {
+        Ok(bindings) => Ok(Some(bindings)),
+        Err(e) if exported => Err(CallError::Failed(tr!(
+            "plugin-bad-export",
+            interface = interface,
+            error = format!("{e:#}")
+        ))),
+        Err(_) => Ok(None),
+    }
+}
@@ -0,0 +1,317 @@
+//! Wallet signing service for plugins.
+//!
+//! Plugins import `peerpiper:wallet/signer` from `wit/deps/wallet/signer.wit` to have payloads
+//! signed with a key derived from the wallet seed for each plugin. The calling plugin blocks
+//! until the user answers the confirmation dialog, unless they chose to always allow it.
+//!
+//! Plugins name themselves, so their keys and whether they're always allowed go by who signed
+//! them too, see [plugin_id]: another publisher's plugin loaded under the same name gets
+//! neither, while new builds from the same publisher keep both.
+
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ed25519_dalek::Signer as _;
+use sha2::{Digest as _, Sha256};
+
+use crate::app::wallet::{Seed, SignRequest, HARDENED, PLUGIN_PURPOSE};
+
+wasmtime::component::bindgen!({
+    path: "wit/deps/wallet",
+    world: "signer-plugin",
+});
+
+pub use peerpiper::wallet::signer;
+
+/// How long a plugin waits for the user to answer
+const APPROVAL_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// What the named plugin's key and being always allowed go by: the hex key of the publisher
+/// whose signature was verified, with the plugin's name, or the hex SHA-256 of the code of
+/// unsigned plugins
+pub(crate) fn plugin_id(name: &str, publisher: Option<&str>, wasm: &[u8]) -> String {
+    match publisher {
+        Some(publisher) => format!("{publisher}/{name}"),
+        None => hex::encode(Sha256::digest(wasm)),
+    }
+}
+
+/// Derivation path of the key of the plugin with the given id, see [plugin_id]
+pub(crate) fn plugin_path(id: &str) -> [u32; 2] {
+    let hash = Sha256::digest(id.as_bytes());
+    let index = u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]]) & !HARDENED;
+    [PLUGIN_PURPOSE, index]
+}
+
+/// A plugin's request to sign a payload, waiting for the user
+struct Request {
+    id: u64,
+    request: SignRequest,
+    respond: mpsc::Sender<bool>,
+}
+
+#[derive(Default)]
+struct State {
+    /// The unlocked seed, or `None` while the wallet is locked
+    seed: Option<Seed>,
+
+    /// Ids of the plugins which may sign without asking, see [plugin_id]
+    always_allow: BTreeSet<String>,
+
+    /// Requests waiting for the user, oldest first
+    pending: VecDeque<Request>,
+
+    /// Id of the next request
+    next_id: u64,
+}
+
+/// Signs for plugins once the user approves
+#[derive(Clone, Default)]
+pub(crate) struct Signer {
+    state: Arc<Mutex<State>>,
+
+    /// Called when a request starts waiting, so the dialog can be shown
+    on_request: Option<Arc<dyn Fn() + Send + Sync>>,
+}
+
+impl Signer {
+    pub(crate) fn new(on_request: impl Fn() + Send + Sync + 'static) -> Self {
+        Self {
+            state: Default::default(),
+            on_request: Some(Arc::new(on_request)),
+        }
+    }
+
+    /// Unlocks signing with `seed`, or locks it with `None`, which denies every waiting request
+    pub(crate) fn set_seed(&self, seed: Option<Seed>) {
+        let mut state = self.state.lock().unwrap();
+        if seed.is_none() {
+            state.pending.clear();
+        }
+        state.seed = seed;
+    }
+
+    /// Sets the ids of the plugins which may sign without asking, see [plugin_id]
+    pub(crate) fn set_always_allow(&self, ids: BTreeSet<String>) {
+        self.state.lock().unwrap().always_allow = ids;
+    }
+
+    /// The oldest waiting request
+    pub(crate) fn pending(&self) -> Option<SignRequest> {
+        self.state
+            .lock()
+            .unwrap()
+            .pending
+            .front()
+            .map(|pending| pending.request.clone())
+    }
+
+    /// Answers the oldest waiting request
+    pub(crate) fn answer(&self, approve: bool) {
+        if let Some(request) = self.state.lock().unwrap().pending.pop_front() {
+            let _ = request.respond.send(approve);
+        }
+    }
+
+    /// The signer as seen by the named plugin, with the given id, see [plugin_id]
+    pub(crate) fn client(&self, plugin: &str, id: &str) -> Client {
+        Client {
+            plugin: plugin.to_owned(),
+            id: id.to_owned(),
+            signer: self.clone(),
+            waited: Duration::ZERO,
+        }
+    }
+}
+
+/// One plugin's view of the [Signer], given to its instances as the `signer` import
+#[derive(Clone, Default)]
+pub(crate) struct Client {
+    plugin: String,
+
+    /// What the plugin's key goes by, see [plugin_id]
+    id: String,
+
+    signer: Signer,
+
+    /// How long the plugin waited for the user since this was last reset, which doesn't count
+    /// against its time limit
+    pub(crate) waited: Duration,
+}
+
+impl Client {
+    fn sign_with_seed(&self, payload: &[u8]) -> Result<Vec<u8>, String> {
+        let state = self.signer.state.lock().unwrap();
+        let seed = state.seed.as_ref().ok_or("The wallet is locked")?;
+        let key = seed.derive(&plugin_path(&self.id));
+        Ok(key.sign(payload).to_bytes().to_vec())
+    }
+}
+
+impl signer::Host for Client {
+    fn public_key(&mut self) -> Option<Vec<u8>> {
+        let state = self.signer.state.lock().unwrap();
+        let key = state.seed.as_ref()?.derive(&plugin_path(&self.id));
+        Some(key.verifying_key().as_bytes().to_vec())
+    }
+
+    fn sign(&mut self, payload: Vec<u8>) -> Result<Vec<u8>, String> {
+        let (id, answer) = {
+            let mut state = self.signer.state.lock().unwrap();
+            if state.seed.is_none() {
+                return Err("The wallet is locked".into());
+            }
+            if state.always_allow.contains(&self.id) {
+                drop(state);
+                return self.sign_with_seed(&payload);
+            }
+
+            let (respond, answer) = mpsc::channel();
+            let id = state.next_id;
+            state.next_id += 1;
+            state.pending.push_back(Request {
+                id,
+                request: SignRequest {
+                    plugin: self.plugin.clone(),
+                    id: self.id.clone(),
+                    payload: payload.clone(),
+                },
+                respond,
+            });
+            (id, answer)
+        };
+
+        if let Some(on_request) = &self.signer.on_request {
+            on_request();
+        }
+
+        let waiting = Instant::now();
+        let answer = answer.recv_timeout(APPROVAL_TIMEOUT);
+        self.waited += waiting.elapsed();
+        match answer {
+            Ok(true) => self.sign_with_seed(&payload),
+            Ok(false) => Err("The user denied the request".into()),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                // Withdraw the request, so the dialog doesn't outlive it
+                let mut state = self.signer.state.lock().unwrap();
+                state.pending.retain(|pending| pending.id != id);
+                Err("The user didn't answer in time".into())
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err("The wallet was locked".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signature, Verifier as _};
+    use signer::Host as _;
+
+    fn unlocked() -> Signer {
+        let signer = Signer::default();
+        signer.set_seed(Some(Seed::from_bytes([3; 32])));
+        signer
+    }
+
+    #[test]
+    fn signs_once_approved() {
+        let signer = unlocked();
+        let mut client = signer.client("plugin", "hash");
+        let public_key = client.public_key().unwrap();
+
+        let answering = signer.clone();
+        let user = std::thread::spawn(move || {
+            while answering.pending().is_none() {
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            let request = answering.pending().unwrap();
+            assert_eq!(request.plugin, "plugin");
+            assert_eq!(request.id, "hash");
+            assert_eq!(request.payload, b"payload");
+            answering.answer(true);
+        });
+
+        let signature = client.sign(b"payload".to_vec()).unwrap();
+        user.join().unwrap();
+
+        let key = ed25519_dalek::VerifyingKey::from_bytes(&public_key.try_into().unwrap()).unwrap();
+        let signature = Signature::from_slice(&signature).unwrap();
+        assert!(key.verify(b"payload", &signature).is_ok());
+        assert!(client.waited > Duration::ZERO);
+    }
+
+    #[test]
+    fn denied_locked_and_always_allowed() {
+        let signer = unlocked();
+        let mut client = signer.client("plugin", "hash");
+
+        let answering = signer.clone();
+        let user = std::thread::spawn(move || {
+            while answering.pending().is_none() {
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            answering.answer(false);
+        });
+        assert!(client.sign(b"no".to_vec()).is_err());
+        user.join().unwrap();
+
+        signer.set_always_allow(BTreeSet::from(["hash".to_owned()]));
+        let mut client = signer.client("plugin", "hash");
+        assert!(client.sign(b"yes".to_vec()).is_ok());
+        assert_eq!(
+            client.waited,
+            Duration::ZERO,
+            "always allowed plugins don't wait"
+        );
+
+        // Another plugin loaded under the same name still asks
+        let mut impostor = signer.client("plugin", "other hash");
+        let answering = signer.clone();
+        let user = std::thread::spawn(move || {
+            while answering.pending().is_none() {
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            answering.answer(false);
+        });
+        assert!(impostor.sign(b"no".to_vec()).is_err());
+        user.join().unwrap();
+
+        signer.set_seed(None);
+        assert!(client.public_key().is_none());
+        assert!(client.sign(b"locked".to_vec()).is_err());
+    }
+
+    #[test]
+    fn signed_plugins_keep_their_id_across_builds() {
+        let v1 = plugin_id("notes", Some("abcd"), b"v1");
+        assert_eq!(v1, plugin_id("notes", Some("abcd"), b"v2"));
+        assert_ne!(v1, plugin_id("notes", Some("ef01"), b"v1"));
+        assert_ne!(v1, plugin_id("todo", Some("abcd"), b"v1"));
+
+        // Unsigned plugins can only be told apart by their code
+        assert_ne!(
+            plugin_id("notes", None, b"v1"),
+            plugin_id("notes", None, b"v2")
+        );
+        assert_eq!(
+            plugin_id("notes", None, b"v1"),
+            plugin_id("todo", None, b"v1")
+        );
+    }
+
+    #[test]
+    fn plugins_get_their_own_keys() {
+        let signer = unlocked();
+        assert_ne!(
+            signer.client("a", "hash").public_key(),
+            signer.client("a", "other hash").public_key()
+        );
+        assert_eq!(
+            signer.client("a", "hash").public_key(),
+            signer.client("b", "hash").public_key()
+        );
+    }
+}
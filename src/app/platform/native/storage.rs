@@ -0,0 +1,495 @@
+//! Per-plugin key-value storage.
+//!
+//! Plugins import `peerpiper:storage/kv` from `wit/deps/storage/kv.wit` to keep state across
+//! restarts. Keys are namespaced by plugin name, and each plugin may store up to [QUOTA] bytes.
+//!
+//! Each plugin's keys are persisted under the native data directory, as a JSON snapshot and a
+//! log of the changes since, one JSON line each. Changes are appended to the log, which is
+//! compacted into a new snapshot once it outgrows the keys.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{BufRead as _, Write as _};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use multinode_i18n::tr;
+use sha2::{Digest as _, Sha256};
+
+wasmtime::component::bindgen!({
+    path: "wit/deps/storage",
+    world: "storage-plugin",
+    trappable_imports: ["set"],
+});
+
+pub use peerpiper::storage::kv;
+
+/// Most bytes of keys and values each plugin may store
+pub(crate) const QUOTA: usize = 4 * 1024 * 1024;
+
+/// Longest file name most file systems allow, in bytes
+const NAME_MAX: usize = 255;
+
+/// Keys and values of one plugin
+type Entries = BTreeMap<String, Vec<u8>>;
+
+/// A plugin's keys as snapshotted on disk, with values hex encoded
+#[derive(serde::Deserialize, serde::Serialize)]
+struct File {
+    plugin: String,
+    entries: BTreeMap<String, String>,
+}
+
+/// A change to a plugin's keys, as logged on disk
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Change {
+    Set { key: String, value: String },
+    Delete { key: String },
+}
+
+/// Storing a value would take the plugin over its [QUOTA]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct QuotaExceeded {
+    /// Bytes the plugin would store
+    pub(crate) size: usize,
+}
+
+impl fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = tr!(
+            "storage-quota-exceeded",
+            size = self.size.div_ceil(1024),
+            limit = QUOTA / 1024
+        );
+        f.write_str(&message)
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+/// Bytes a key and its value count against the quota
+fn size(key: &str, value: &[u8]) -> usize {
+    key.len() + value.len()
+}
+
+/// One plugin's keys, and how far its log has grown since the snapshot
+#[derive(Default)]
+struct Stored {
+    entries: Entries,
+
+    /// Bytes of keys and values, counted against the [QUOTA]
+    size: usize,
+
+    /// Bytes of the log
+    logged: u64,
+}
+
+impl Stored {
+    fn new(entries: Entries, logged: u64) -> Self {
+        let size = entries.iter().map(|(key, value)| size(key, value)).sum();
+        Self {
+            entries,
+            size,
+            logged,
+        }
+    }
+
+    /// Whether the log is worth compacting: it's grown past twice the size of the keys
+    fn bloated(&self) -> bool {
+        self.logged > 2 * self.size.max(4096) as u64
+    }
+}
+
+/// Every plugin's keys, kept in memory and written through to disk
+#[derive(Clone, Default)]
+pub(crate) struct KvStore {
+    /// Directory holding the files of each plugin, or `None` to keep keys in memory only
+    dir: Option<PathBuf>,
+
+    plugins: Arc<Mutex<BTreeMap<String, Stored>>>,
+}
+
+impl KvStore {
+    /// Opens the store in `dir`, loading the keys persisted there
+    pub(crate) fn open(dir: Option<PathBuf>) -> Self {
+        let mut plugins = BTreeMap::new();
+
+        let files = dir
+            .as_deref()
+            .and_then(|dir| std::fs::read_dir(dir).ok())
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"));
+
+        for path in files {
+            match Self::read(&path) {
+                Ok((plugin, stored)) => {
+                    plugins.insert(plugin, stored);
+                }
+                Err(e) => tracing::warn!("Skipping plugin storage {}: {e}", path.display()),
+            }
+        }
+
+        Self {
+            dir,
+            plugins: Arc::new(Mutex::new(plugins)),
+        }
+    }
+
+    /// Reads the snapshot at `path`, then replays its log
+    fn read(path: &Path) -> Result<(String, Stored), String> {
+        let json = std::fs::read(path).map_err(|e| e.to_string())?;
+        let file: File = serde_json::from_slice(&json).map_err(|e| e.to_string())?;
+        let mut entries = file
+            .entries
+            .into_iter()
+            .map(|(key, value)| Ok((key, hex::decode(value).map_err(|e| e.to_string())?)))
+            .collect::<Result<Entries, String>>()?;
+
+        let log = path.with_extension("log");
+        let mut logged = 0;
+        if let Ok(file) = std::fs::File::open(&log) {
+            for line in std::io::BufReader::new(file).lines() {
+                let line = line.map_err(|e| e.to_string())?;
+                logged += line.len() as u64 + 1;
+
+                // A crash while appending leaves the last line cut short
+                let change = serde_json::from_str(&line)
+                    .ok()
+                    .and_then(|change| match change {
+                        Change::Set { key, value } => Some((key, Some(hex::decode(value).ok()?))),
+                        Change::Delete { key } => Some((key, None)),
+                    });
+                match change {
+                    Some((key, Some(value))) => {
+                        entries.insert(key, value);
+                    }
+                    Some((key, None)) => {
+                        entries.remove(&key);
+                    }
+                    None => tracing::warn!("Skipping a change in {}", log.display()),
+                }
+            }
+        }
+
+        Ok((file.plugin, Stored::new(entries, logged)))
+    }
+
+    /// Snapshot of the plugin's keys, with the log next to it. Plugin names may contain any
+    /// character, so the files are named by the hex of the name, or by its hash if that would
+    /// be too long a name.
+    fn path(dir: &Path, plugin: &str) -> PathBuf {
+        let name = hex::encode(plugin);
+        let name = if name.len() + ".json.tmp".len() <= NAME_MAX {
+            name
+        } else {
+            format!("sha256-{}", hex::encode(Sha256::digest(plugin)))
+        };
+        dir.join(format!("{name}.json"))
+    }
+
+    /// Writes a snapshot of the plugin's keys to a temporary file, renames it into place,
+    /// then removes the log. A crash leaves either the old or the new keys on disk.
+    fn compact(&self, plugin: &str, stored: &mut Stored) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        let path = Self::path(dir, plugin);
+        let file = File {
+            plugin: plugin.to_owned(),
+            entries: stored
+                .entries
+                .iter()
+                .map(|(key, value)| (key.clone(), hex::encode(value)))
+                .collect(),
+        };
+        let tmp = path.with_extension("json.tmp");
+        let result = std::fs::create_dir_all(dir)
+            .and_then(|_| {
+                std::fs::write(&tmp, serde_json::to_vec_pretty(&file).unwrap_or_default())
+            })
+            .and_then(|_| std::fs::rename(&tmp, &path))
+            .and_then(|_| remove(&path.with_extension("log")));
+
+        match result {
+            Ok(()) => stored.logged = 0,
+            Err(e) => tracing::error!("Failed to persist storage of plugin {plugin}: {e}"),
+        }
+    }
+
+    /// Appends `change` to the plugin's log, first snapshotting its keys if it has no
+    /// snapshot yet or its log has grown too long
+    fn persist(&self, plugin: &str, stored: &mut Stored, change: Change) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        let path = Self::path(dir, plugin);
+        if !path.exists() || stored.bloated() {
+            self.compact(plugin, stored);
+            return;
+        }
+
+        let mut line = serde_json::to_vec(&change).unwrap_or_default();
+        line.push(b'\n');
+        let result = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path.with_extension("log"))
+            .and_then(|mut log| log.write_all(&line));
+
+        match result {
+            Ok(()) => stored.logged += line.len() as u64,
+            Err(e) => tracing::error!("Failed to persist storage of plugin {plugin}: {e}"),
+        }
+    }
+
+    /// Removes the plugin's files
+    fn forget(&self, plugin: &str) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        let path = Self::path(dir, plugin);
+        if let Err(e) = remove(&path.with_extension("log")).and_then(|_| remove(&path)) {
+            tracing::error!("Failed to persist storage of plugin {plugin}: {e}");
+        }
+    }
+
+    /// The store as seen by the named plugin
+    pub(crate) fn namespace(&self, plugin: &str) -> Namespace {
+        Namespace {
+            plugin: plugin.to_owned(),
+            store: self.clone(),
+        }
+    }
+
+    /// Names of the plugins with stored keys
+    pub(crate) fn plugins(&self) -> Vec<String> {
+        self.plugins.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Snapshot of the plugin's keys and values
+    pub(crate) fn entries(&self, plugin: &str) -> Entries {
+        self.plugins
+            .lock()
+            .unwrap()
+            .get(plugin)
+            .map(|stored| stored.entries.clone())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn get(&self, plugin: &str, key: &str) -> Option<Vec<u8>> {
+        self.plugins
+            .lock()
+            .unwrap()
+            .get(plugin)?
+            .entries
+            .get(key)
+            .cloned()
+    }
+
+    /// Stores `value` under `key`, unless that would take the plugin over its [QUOTA]
+    pub(crate) fn set(
+        &self,
+        plugin: &str,
+        key: String,
+        value: Vec<u8>,
+    ) -> Result<(), QuotaExceeded> {
+        let mut plugins = self.plugins.lock().unwrap();
+        let stored = plugins.entry(plugin.to_owned()).or_default();
+
+        let replaced = stored.entries.get(&key).map_or(0, |old| size(&key, old));
+        let size = stored.size - replaced + size(&key, &value);
+        if size > QUOTA {
+            if stored.entries.is_empty() {
+                plugins.remove(plugin);
+            }
+            return Err(QuotaExceeded { size });
+        }
+        stored.size = size;
+
+        let change = Change::Set {
+            key: key.clone(),
+            value: hex::encode(&value),
+        };
+        stored.entries.insert(key, value);
+        self.persist(plugin, stored, change);
+        Ok(())
+    }
+
+    pub(crate) fn delete(&self, plugin: &str, key: &str) {
+        let mut plugins = self.plugins.lock().unwrap();
+        let Some(stored) = plugins.get_mut(plugin) else {
+            return;
+        };
+        let Some(value) = stored.entries.remove(key) else {
+            return;
+        };
+        stored.size -= size(key, &value);
+
+        if stored.entries.is_empty() {
+            plugins.remove(plugin);
+            self.forget(plugin);
+        } else {
+            let change = Change::Delete {
+                key: key.to_owned(),
+            };
+            self.persist(plugin, stored, change);
+        }
+    }
+
+    /// Removes every key of the plugin
+    pub(crate) fn clear(&self, plugin: &str) {
+        self.plugins.lock().unwrap().remove(plugin);
+        self.forget(plugin);
+    }
+}
+
+/// Removes the file at `path`, if there is one
+fn remove(path: &Path) -> std::io::Result<()> {
+    std::fs::remove_file(path).or_else(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => Ok(()),
+        _ => Err(e),
+    })
+}
+
+/// One plugin's view of the [KvStore], given to its instances as the `kv` import
+#[derive(Clone, Default)]
+pub(crate) struct Namespace {
+    plugin: String,
+    store: KvStore,
+}
+
+impl kv::Host for Namespace {
+    fn get(&mut self, key: String) -> Option<Vec<u8>> {
+        self.store.get(&self.plugin, &key)
+    }
+
+    /// Going over the quota traps the plugin, which has no other way to be told
+    fn set(&mut self, key: String, value: Vec<u8>) -> wasmtime::Result<()> {
+        Ok(self.store.set(&self.plugin, key, value)?)
+    }
+
+    fn delete(&mut self, key: String) {
+        self.store.delete(&self.plugin, &key);
+    }
+
+    fn keys(&mut self) -> Vec<String> {
+        self.store.entries(&self.plugin).into_keys().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kv::Host as _;
+
+    /// A fresh directory under the system temp dir
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("kv-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn namespaces_keys_by_plugin() {
+        let store = KvStore::default();
+        let mut alice = store.namespace("alice");
+        let mut bob = store.namespace("bob");
+
+        alice.set("count".into(), b"1".to_vec()).unwrap();
+        bob.set("count".into(), b"2".to_vec()).unwrap();
+
+        assert_eq!(alice.get("count".into()), Some(b"1".to_vec()));
+        assert_eq!(bob.get("count".into()), Some(b"2".to_vec()));
+
+        alice.delete("count".into());
+        assert_eq!(alice.keys(), Vec::<String>::new());
+        assert_eq!(bob.keys(), vec!["count".to_owned()]);
+    }
+
+    #[test]
+    fn persists_across_restarts() {
+        let dir = temp_dir("persist");
+
+        let store = KvStore::open(Some(dir.clone()));
+        store
+            .set("plugin/with:odd name", "key".into(), vec![0, 1, 255])
+            .unwrap();
+        store.set("other", "key".into(), b"value".to_vec()).unwrap();
+        store.clear("other");
+
+        let reopened = KvStore::open(Some(dir.clone()));
+        assert_eq!(reopened.plugins(), vec!["plugin/with:odd name".to_owned()]);
+        assert_eq!(
+            reopened.get("plugin/with:odd name", "key"),
+            Some(vec![0, 1, 255])
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn replays_changes_logged_since_the_snapshot() {
+        let dir = temp_dir("log");
+
+        let store = KvStore::open(Some(dir.clone()));
+        for i in 0..1000u16 {
+            let count = i.to_be_bytes().to_vec();
+            store.set("counter", "count".into(), count).unwrap();
+        }
+        store.set("counter", "gone".into(), vec![1]).unwrap();
+        store.delete("counter", "gone");
+
+        // The log was compacted into the snapshot along the way
+        let snapshot = KvStore::path(&dir, "counter");
+        assert!(snapshot.exists());
+        let log = std::fs::metadata(snapshot.with_extension("log")).unwrap();
+        assert!(log.len() <= 2 * 4096 + 64);
+
+        let reopened = KvStore::open(Some(dir.clone()));
+        assert_eq!(
+            reopened.get("counter", "count"),
+            Some(999u16.to_be_bytes().to_vec())
+        );
+        assert_eq!(reopened.get("counter", "gone"), None);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn long_plugin_names_are_hashed() {
+        let dir = temp_dir("long");
+        let plugin = "a".repeat(1000);
+
+        let store = KvStore::open(Some(dir.clone()));
+        store.set(&plugin, "key".into(), b"value".to_vec()).unwrap();
+        let name = KvStore::path(&dir, &plugin);
+        assert!(name.file_name().unwrap().len() <= NAME_MAX);
+
+        let reopened = KvStore::open(Some(dir.clone()));
+        assert_eq!(reopened.get(&plugin, "key"), Some(b"value".to_vec()));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn plugins_are_held_to_their_quota() {
+        let store = KvStore::default();
+        let half = vec![0; QUOTA / 2];
+
+        store.set("hog", "a".into(), half.clone()).unwrap();
+        assert!(store.set("hog", "b".into(), half.clone()).is_err());
+        assert_eq!(store.get("hog", "b"), None);
+
+        // Replacing a value only counts the difference
+        store.set("hog", "a".into(), half.clone()).unwrap();
+        store.delete("hog", "a");
+        store.set("hog", "b".into(), half).unwrap();
+
+        assert!(store.set("other", "a".into(), vec![0; QUOTA + 1]).is_err());
+        assert_eq!(store.plugins(), vec!["hog".to_owned()]);
+    }
+}
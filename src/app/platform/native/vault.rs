@@ -0,0 +1,75 @@
+//! The wallet's encrypted vault, kept in a file of its own
+//!
+//! The app state is only saved now and then, so a changed password could be lost with it. The
+//! vault is written on its own as soon as it changes instead: to a temporary file, flushed to
+//! disk, then renamed over the last one, so a crash leaves either the old vault or the new one.
+
+use std::fs::File;
+use std::io::{self, Write as _};
+use std::path::PathBuf;
+
+/// Where the profile's vault is kept, if the app has a data directory
+#[derive(Debug, Clone, Default)]
+pub(crate) struct VaultFile(Option<PathBuf>);
+
+impl VaultFile {
+    pub(crate) fn new(path: Option<PathBuf>) -> Self {
+        Self(path)
+    }
+
+    /// The vault last saved, if there is one
+    pub(crate) fn load(&self) -> Option<String> {
+        let path = self.0.as_ref()?;
+        match std::fs::read_to_string(path) {
+            Ok(vault) => Some(vault),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => {
+                tracing::error!("Failed to read the wallet from {}: {e}", path.display());
+                None
+            }
+        }
+    }
+
+    /// Replaces the saved vault with `vault` once it's safely on disk
+    pub(crate) fn save(&self, vault: &str) -> io::Result<()> {
+        let Some(path) = &self.0 else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no data directory to keep the wallet in",
+            ));
+        };
+        let dir = path.parent().unwrap_or(path);
+        std::fs::create_dir_all(dir)?;
+
+        let tmp = path.with_extension("json.tmp");
+        let mut file = File::create(&tmp)?;
+        file.write_all(vault.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)?;
+
+        // The rename only lasts once the directory listing is on disk too
+        #[cfg(unix)]
+        File::open(dir)?.sync_all()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_the_saved_vault() {
+        let dir = std::env::temp_dir().join(format!("multinode-vault-{}", std::process::id()));
+        let file = VaultFile::new(Some(dir.join("profile").join("vault.json")));
+        assert_eq!(file.load(), None);
+
+        file.save("first").unwrap();
+        file.save("second").unwrap();
+        assert_eq!(file.load().as_deref(), Some("second"));
+        assert!(!dir.join("profile").join("vault.json.tmp").exists());
+
+        assert!(VaultFile::default().save("nowhere").is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
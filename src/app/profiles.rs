@@ -0,0 +1,169 @@
+//! Node profiles
+//!
+//! Each profile is a node of its own, with its own wallet, trusted publishers, plugin settings
+//! and plugin data, so several identities can be run side by side for testing. One profile is
+//! active at a time, and switching restarts the [`Platform`](super::Platform) with the data of
+//! the one picked. The layout and the appearance are shared by every profile.
+
+use std::collections::BTreeMap;
+
+use multinode_i18n::tr;
+
+use super::backend_panel::BackendPanel;
+
+/// The profile there is from the start
+pub const DEFAULT: &str = "default";
+
+/// Longest name a profile can have
+const MAX_NAME: usize = 32;
+
+/// Everything a node keeps to itself
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Profile {
+    /// The wallet, plugin settings and trusted publishers
+    pub backend_panel: BackendPanel,
+}
+
+/// The profiles, and which one is active
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Profiles {
+    /// Name of the active profile
+    active: String,
+
+    by_name: BTreeMap<String, Profile>,
+
+    /// Name of the profile being added
+    #[serde(skip)]
+    new: String,
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT.to_owned(),
+            by_name: BTreeMap::from([(DEFAULT.to_owned(), Profile::default())]),
+            new: String::new(),
+        }
+    }
+}
+
+/// Whether `name` can name a profile. Profiles' data is kept in directories named after them,
+/// so names are short and only use letters, digits, `-` and `_`.
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+impl Profiles {
+    /// Name of the active profile
+    pub fn active(&self) -> &str {
+        &self.active
+    }
+
+    /// The active profile
+    pub fn current(&mut self) -> &mut Profile {
+        self.by_name.entry(self.active.clone()).or_default()
+    }
+
+    /// Makes the named profile the active one, adding it if there's none by that name. Returns
+    /// false, changing nothing, if the name isn't [valid](valid_name).
+    pub fn select(&mut self, name: &str) -> bool {
+        if !valid_name(name) {
+            return false;
+        }
+        self.active = name.to_owned();
+        self.current();
+        true
+    }
+
+    /// Name of the active profile's data, or `None` for the default profile, whose data is
+    /// where it was before there were profiles
+    pub fn data(&self) -> Option<&str> {
+        (self.active != DEFAULT).then_some(self.active.as_str())
+    }
+
+    /// Swaps each profile's wallet with the one of the same name in `other`, adding the profiles
+    /// this lacks, so wallets in use stay in use
+    pub fn take_wallets(&mut self, other: &mut Profiles) {
+        for (name, profile) in &mut other.by_name {
+            self.by_name
+                .entry(name.clone())
+                .or_default()
+                .backend_panel
+                .take_wallet(&mut profile.backend_panel);
+        }
+    }
+
+    /// A menu of the profiles, for the top bar. Returns the profile the user picked, if it
+    /// isn't the active one.
+    pub fn switcher(&mut self, ui: &mut egui::Ui) -> Option<String> {
+        let mut picked = None;
+        ui.menu_button(tr!("profile-menu", name = self.active), |ui| {
+            for name in self.by_name.keys() {
+                if ui.selectable_label(*name == self.active, name).clicked() {
+                    picked = Some(name.clone());
+                    ui.close_menu();
+                }
+            }
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.new)
+                        .hint_text(tr!("profile-new-hint"))
+                        .desired_width(120.0),
+                );
+                let valid = valid_name(&self.new) && !self.by_name.contains_key(&self.new);
+                if ui
+                    .add_enabled(valid, egui::Button::new(tr!("profile-add")))
+                    .clicked()
+                {
+                    picked = Some(std::mem::take(&mut self.new));
+                    ui.close_menu();
+                }
+            });
+            if !self.new.is_empty() && !valid_name(&self.new) {
+                ui.weak(tr!("profile-invalid-name"));
+            }
+        })
+        .response
+        .on_hover_text(tr!("profile-switch"));
+
+        picked.filter(|name| *name != self.active)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_safe_directory_names() {
+        assert!(valid_name("default"));
+        assert!(valid_name("test-node_2"));
+        assert!(!valid_name(""));
+        assert!(!valid_name("../wallet"));
+        assert!(!valid_name("a b"));
+        assert!(!valid_name(&"x".repeat(MAX_NAME + 1)));
+    }
+
+    #[test]
+    fn selects_and_adds_profiles() {
+        let mut profiles = Profiles::default();
+        assert_eq!(profiles.active(), DEFAULT);
+        assert_eq!(profiles.data(), None);
+
+        assert!(profiles.select("alice"));
+        assert_eq!(profiles.active(), "alice");
+        assert_eq!(profiles.data(), Some("alice"));
+        assert_eq!(profiles.by_name.len(), 2);
+
+        assert!(!profiles.select("../bob"));
+        assert_eq!(profiles.active(), "alice");
+    }
+}
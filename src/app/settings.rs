@@ -0,0 +1,358 @@
+//! Settings window
+//!
+//! Lets the user pick the language, the theme, an accent color, how big text is and which font
+//! it uses, or start from a high contrast preset. The choices persist with the rest of the app
+//! state, and are applied to egui only when they change or the screen switches between mobile
+//! and desktop, rather than every frame.
+//!
+//! The user also picks which node events notify them, and desktop builds can keep running in
+//! the tray.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use super::notifications::Notifications;
+use super::style;
+use multinode_i18n::tr;
+
+mod font;
+
+/// Range of the scale applied to every text size
+const FONT_SCALES: std::ops::RangeInclusive<f32> = 0.5..=3.0;
+
+/// Range of each text style's size, in points
+const TEXT_SIZES: std::ops::RangeInclusive<f32> = 6.0..=48.0;
+
+/// Extensions of the font files egui can use
+const FONT_EXTENSIONS: [&str; 2] = ["ttf", "otf"];
+
+/// A font file the user loaded. Only where it is persists, its bytes are loaded apart from the
+/// state (see [font]).
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CustomFont {
+    pub name: String,
+
+    /// The file's path natively, or the key of its bytes in IndexedDB on the web
+    pub key: String,
+}
+
+/// A font file the user picked, with its bytes
+type PickedFont = (CustomFont, Vec<u8>);
+
+/// The bytes of the custom font
+#[derive(Default)]
+struct FontFile {
+    /// The font the bytes are of
+    font: Option<CustomFont>,
+
+    /// The bytes once loaded, or why they couldn't be
+    bytes: Option<Result<Vec<u8>, String>>,
+}
+
+/// How the app looks
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Appearance {
+    pub theme: egui::ThemePreference,
+
+    /// Color of selections and links, instead of the theme's
+    pub accent: Option<egui::Color32>,
+
+    /// Scale applied to every text size
+    pub font_scale: f32,
+
+    /// Size of each text style before scaling, in points
+    pub text_sizes: BTreeMap<egui::TextStyle, f32>,
+
+    /// Font used ahead of the default ones
+    pub font: Option<CustomFont>,
+
+    /// Whether to use pure black and white with bold outlines
+    pub high_contrast: bool,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self {
+            theme: egui::ThemePreference::System,
+            accent: None,
+            font_scale: 1.0,
+            text_sizes: egui::Style::default()
+                .text_styles
+                .into_iter()
+                .map(|(text_style, font_id)| (text_style, font_id.size))
+                .collect(),
+            font: None,
+            high_contrast: false,
+        }
+    }
+}
+
+impl Appearance {
+    /// Large text in black and white, with a yellow accent
+    pub fn high_contrast() -> Self {
+        Self {
+            theme: egui::ThemePreference::Dark,
+            accent: Some(egui::Color32::YELLOW),
+            font_scale: 1.25,
+            high_contrast: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// The user's settings, and the window to change them
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Settings {
+    appearance: Appearance,
+
+    /// Code of the language of the UI
+    language: String,
+
+    /// Whether closing the window keeps the node running in the tray
+    #[cfg(not(target_arch = "wasm32"))]
+    pub background: bool,
+
+    /// Which node events raise notifications
+    pub notifications: Notifications,
+
+    /// Whether the window is open
+    #[serde(skip)]
+    pub open: bool,
+
+    /// Whether the screen was mobile, and whether the custom font was loaded, when the
+    /// appearance was last applied, if it is applied
+    #[serde(skip)]
+    applied: Option<(bool, bool)>,
+
+    /// The custom font's bytes, loaded in the background on the web
+    #[serde(skip)]
+    font_file: Arc<Mutex<FontFile>>,
+
+    /// A font file the user picked and its bytes, until the next frame checks it
+    #[serde(skip)]
+    picked_font: Arc<Mutex<Option<PickedFont>>>,
+
+    /// Why the last font file was refused
+    #[serde(skip)]
+    font_error: Option<String>,
+}
+
+impl Settings {
+    /// Applies the appearance to egui, unless it already is
+    pub fn apply(&mut self, ctx: &egui::Context, mobile: bool) {
+        self.load_font(ctx);
+        let font_file = self.font_file.lock().unwrap();
+        let font = font_file
+            .bytes
+            .as_ref()
+            .and_then(|bytes| bytes.as_deref().ok());
+        let applied = Some((mobile, font.is_some()));
+        if self.applied != applied {
+            if !multinode_i18n::set_language(&self.language) {
+                multinode_i18n::set_language(multinode_i18n::DEFAULT_LANGUAGE);
+            }
+            style::apply(ctx, &self.appearance, font, mobile);
+            self.applied = applied;
+        }
+    }
+
+    /// Loads the bytes of the custom font, unless they are already of it
+    fn load_font(&self, ctx: &egui::Context) {
+        let font = self.appearance.font.clone();
+        {
+            let mut font_file = self.font_file.lock().unwrap();
+            if font_file.font == font {
+                return;
+            }
+            *font_file = FontFile {
+                font: font.clone(),
+                bytes: None,
+            };
+        }
+
+        let Some(font) = font else {
+            return;
+        };
+        let font_file = self.font_file.clone();
+        let ctx = ctx.clone();
+        let loading = font.clone();
+        font::load(&font, move |bytes| {
+            let mut font_file = font_file.lock().unwrap();
+            // Unless another font was picked meanwhile
+            if font_file.font.as_ref() == Some(&loading) {
+                if let Err(e) = &bytes {
+                    tracing::warn!("Failed to load font {}: {e}", loading.key);
+                }
+                font_file.bytes = Some(bytes);
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    /// Has the appearance applied again on the next frame
+    fn changed(&mut self) {
+        self.applied = None;
+    }
+
+    /// A button cycling through the themes, for the top bar
+    pub fn theme_switch(&mut self, ui: &mut egui::Ui) {
+        use egui::ThemePreference::{Dark, Light, System};
+
+        let (icon, name, next) = match self.appearance.theme {
+            Dark => ("🌙", tr!("settings-theme-dark"), Light),
+            Light => ("☀", tr!("settings-theme-light"), System),
+            System => ("💻", tr!("settings-theme-system"), Dark),
+        };
+        if ui
+            .button(icon)
+            .on_hover_text(tr!("settings-theme-hover", theme = name))
+            .clicked()
+        {
+            self.appearance.theme = next;
+            self.changed();
+        }
+    }
+
+    /// Shows the settings window, if open
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let mut open = self.open;
+        egui::Window::new(tr!("settings-title"))
+            .id(egui::Id::new("settings"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| self.ui(ui));
+        self.open = open;
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(tr!("settings-language"));
+            let current = multinode_i18n::language();
+            let selected = multinode_i18n::languages()
+                .find(|(code, _)| *code == current)
+                .map_or(current, |(_, name)| name);
+            egui::ComboBox::from_id_salt("language")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    for (code, name) in multinode_i18n::languages() {
+                        if ui.selectable_label(code == current, name).clicked() {
+                            self.language = code.to_owned();
+                            multinode_i18n::set_language(code);
+                        }
+                    }
+                });
+        });
+
+        #[cfg(not(target_arch = "wasm32"))]
+        ui.checkbox(&mut self.background, tr!("settings-background"))
+            .on_hover_text(tr!("settings-background-hint"));
+        self.notifications.ui(ui);
+        ui.separator();
+
+        let mut changed = false;
+        let appearance = &mut self.appearance;
+
+        ui.horizontal(|ui| {
+            ui.label(tr!("settings-theme"));
+            let theme = appearance.theme;
+            appearance.theme.radio_buttons(ui);
+            changed |= appearance.theme != theme;
+        });
+        changed |= ui
+            .checkbox(&mut appearance.high_contrast, tr!("settings-high-contrast"))
+            .changed();
+
+        ui.horizontal(|ui| {
+            let mut custom = appearance.accent.is_some();
+            if ui.checkbox(&mut custom, tr!("settings-accent")).changed() {
+                appearance.accent = custom.then(|| ui.visuals().selection.bg_fill);
+                changed = true;
+            }
+            if let Some(accent) = &mut appearance.accent {
+                changed |= ui.color_edit_button_srgba(accent).changed();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr!("settings-font-scale"));
+            changed |= ui
+                .add(egui::Slider::new(&mut appearance.font_scale, FONT_SCALES).step_by(0.05))
+                .changed();
+        });
+
+        ui.collapsing(tr!("settings-text-sizes"), |ui| {
+            egui::Grid::new("text_sizes").num_columns(2).show(ui, |ui| {
+                for (text_style, size) in appearance.text_sizes.iter_mut() {
+                    ui.label(text_style.to_string());
+                    changed |= ui
+                        .add(egui::DragValue::new(size).range(TEXT_SIZES).suffix(" pt"))
+                        .changed();
+                    ui.end_row();
+                }
+            });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr!("settings-font"));
+            match &appearance.font {
+                Some(font) => ui.monospace(&font.name),
+                None => ui.weak(tr!("settings-default-font")),
+            };
+            if ui.button(tr!("settings-load-font")).clicked() {
+                let picked = self.picked_font.clone();
+                let ctx = ui.ctx().clone();
+                font::pick(move |font, bytes| {
+                    *picked.lock().unwrap() = Some((font, bytes));
+                    ctx.request_repaint();
+                });
+            }
+            if appearance.font.is_some() && ui.button(tr!("settings-use-default-font")).clicked() {
+                appearance.font = None;
+                changed = true;
+            }
+        });
+
+        // egui can't use a file which isn't a font, so check before handing it over
+        if let Some((font, bytes)) = self.picked_font.lock().unwrap().take() {
+            if ab_glyph::FontRef::try_from_slice(&bytes).is_ok() {
+                font::keep(&font, &bytes);
+                appearance.font = Some(font.clone());
+                *self.font_file.lock().unwrap() = FontFile {
+                    font: Some(font),
+                    bytes: Some(Ok(bytes)),
+                };
+                self.font_error = None;
+                changed = true;
+            } else {
+                self.font_error = Some(tr!("settings-not-a-font", name = font.name));
+            }
+        }
+        if let Some(error) = &self.font_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        if let (Some(font), Some(Err(_))) =
+            (&appearance.font, &self.font_file.lock().unwrap().bytes)
+        {
+            let error = tr!("settings-font-unavailable", name = font.name);
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            if ui.button(tr!("settings-high-contrast-preset")).clicked() {
+                *appearance = Appearance::high_contrast();
+                changed = true;
+            }
+            if ui.button(tr!("settings-reset-appearance")).clicked() {
+                *appearance = Appearance::default();
+                changed = true;
+            }
+        });
+
+        if changed {
+            self.changed();
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! Picking and loading the font file of a [CustomFont].
+//!
+//! Natively the font stays where the user picked it, by path. Pages can't read a file again once
+//! it was picked, so on the web its bytes are copied to IndexedDB and kept under its name.
+
+use super::{CustomFont, FONT_EXTENSIONS};
+use crate::app::backend_panel::file_dialog;
+
+/// Asks the user for a font file, and hands it to `on_pick` with its bytes
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) fn pick(on_pick: impl FnOnce(CustomFont, Vec<u8>) + 'static) {
+    let Some(path) = file_dialog::pick_file(&FONT_EXTENSIONS) else {
+        return;
+    };
+    match std::fs::read(&path) {
+        Ok(bytes) => {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let key = path.to_string_lossy().into_owned();
+            on_pick(CustomFont { name, key }, bytes);
+        }
+        Err(e) => tracing::error!("Failed to read font file {}: {e}", path.display()),
+    }
+}
+
+/// Asks the user for a font file, and hands it to `on_pick` with its bytes once the browser
+/// has read it
+#[cfg(target_arch = "wasm32")]
+pub(super) fn pick(on_pick: impl FnOnce(CustomFont, Vec<u8>) + 'static) {
+    let on_open = move |name: String, bytes| {
+        let key = name.clone();
+        on_pick(CustomFont { name, key }, bytes);
+    };
+    if let Err(e) = file_dialog::open_file(&FONT_EXTENSIONS, on_open) {
+        tracing::error!("Failed to open font file: {:?}", e);
+    }
+}
+
+/// Keeps the bytes of the `font` the user picked, so it can be loaded again. The file itself is
+/// kept natively.
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) fn keep(_font: &CustomFont, _bytes: &[u8]) {}
+
+/// Keeps the bytes of the `font` the user picked, so it can be loaded again
+#[cfg(target_arch = "wasm32")]
+pub(super) fn keep(font: &CustomFont, bytes: &[u8]) {
+    multinode_web::fonts::save(&font.key, bytes);
+}
+
+/// Loads the bytes of `font`, handing them to `on_load` straight away
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) fn load(font: &CustomFont, on_load: impl FnOnce(Result<Vec<u8>, String>) + 'static) {
+    on_load(std::fs::read(&font.key).map_err(|e| e.to_string()));
+}
+
+/// Loads the bytes of `font` in the background, handing them to `on_load`
+#[cfg(target_arch = "wasm32")]
+pub(super) fn load(font: &CustomFont, on_load: impl FnOnce(Result<Vec<u8>, String>) + 'static) {
+    multinode_web::fonts::load(&font.key, on_load);
+}
@@ -1,45 +1,156 @@
-// use std::collections::BTreeMap;
+use super::settings::Appearance;
 
-// use egui::{FontId, TextStyle};
+/// Narrow screens are mobile once narrower than this, in points
+const MOBILE_BELOW: f32 = 550.0;
 
-/// Function to set the style
-pub(crate) fn style(ctx: &egui::Context) {
-    ctx.style_mut(|style| {
-        // Increase padding for all widgets
-        // style.spacing.item_spacing = egui::vec2(10.0, 10.0);
+/// Mobile screens stop being mobile once wider than this, in points. The gap keeps the layout
+/// from flickering between both when the width hovers around the threshold, such as while a
+/// device rotates.
+const DESKTOP_ABOVE: f32 = 600.0;
+
+/// Height of controls on mobile, big enough to tap with a finger
+const TOUCH_SIZE: f32 = 44.0;
+
+/// Name of the user's custom font among egui's fonts
+const CUSTOM_FONT: &str = "custom";
+
+/// Sets the style of both themes, and the fonts, from the `appearance` the user chose. `font` is
+/// the bytes of its custom font, once loaded.
+pub(crate) fn apply(
+    ctx: &egui::Context,
+    appearance: &Appearance,
+    font: Option<&[u8]>,
+    mobile: bool,
+) {
+    ctx.set_theme(appearance.theme);
+
+    for theme in [egui::Theme::Dark, egui::Theme::Light] {
+        let mut style = theme.default_style();
 
         // Increase padding specifically for buttons
         style.spacing.button_padding = egui::vec2(10.0, 5.0);
 
-        // style.text_styles = BTreeMap::from([
-        //     (
-        //         TextStyle::Heading,
-        //         FontId::new(24.0, egui::FontFamily::Proportional),
-        //     ),
-        //     (
-        //         TextStyle::Body,
-        //         FontId::new(18.0, egui::FontFamily::Proportional),
-        //     ),
-        //     (
-        //         TextStyle::Monospace,
-        //         FontId::new(16.0, egui::FontFamily::Monospace),
-        //     ),
-        //     (
-        //         TextStyle::Button,
-        //         FontId::new(16.0, egui::FontFamily::Proportional),
-        //     ),
-        //     (
-        //         TextStyle::Small,
-        //         FontId::new(12.0, egui::FontFamily::Proportional),
-        //     ),
-        // ]);
-    });
+        // Bigger controls on touch screens
+        if mobile {
+            style.spacing.interact_size.y = TOUCH_SIZE;
+            style.spacing.button_padding = egui::vec2(14.0, 10.0);
+            style.spacing.item_spacing = egui::vec2(10.0, 8.0);
+        }
+
+        for (text_style, font_id) in style.text_styles.iter_mut() {
+            let size = appearance
+                .text_sizes
+                .get(text_style)
+                .copied()
+                .unwrap_or(font_id.size);
+            font_id.size = size * appearance.font_scale;
+        }
+
+        if let Some(accent) = appearance.accent {
+            style.visuals.selection.bg_fill = accent;
+            style.visuals.selection.stroke.color = contrasting(accent);
+            style.visuals.hyperlink_color = accent;
+        }
+
+        if appearance.high_contrast {
+            high_contrast(&mut style.visuals);
+        }
+
+        ctx.set_style_of(theme, style);
+    }
+
+    let mut fonts = egui::FontDefinitions::default();
+    if let Some(font) = font {
+        fonts.font_data.insert(
+            CUSTOM_FONT.to_owned(),
+            egui::FontData::from_owned(font.to_vec()),
+        );
+        // Ahead of the default fonts, which still cover the characters it lacks
+        fonts
+            .families
+            .entry(egui::FontFamily::Proportional)
+            .or_default()
+            .insert(0, CUSTOM_FONT.to_owned());
+    }
+    ctx.set_fonts(fonts);
+}
+
+/// Black or white, whichever reads better on `color`
+fn contrasting(color: egui::Color32) -> egui::Color32 {
+    let luma = 0.299 * color.r() as f32 + 0.587 * color.g() as f32 + 0.114 * color.b() as f32;
+    if luma > 140.0 {
+        egui::Color32::BLACK
+    } else {
+        egui::Color32::WHITE
+    }
+}
+
+/// Pure black and white, with bold outlines
+fn high_contrast(visuals: &mut egui::Visuals) {
+    let (fg, bg) = if visuals.dark_mode {
+        (egui::Color32::WHITE, egui::Color32::BLACK)
+    } else {
+        (egui::Color32::BLACK, egui::Color32::WHITE)
+    };
+
+    visuals.override_text_color = Some(fg);
+    visuals.panel_fill = bg;
+    visuals.window_fill = bg;
+    visuals.extreme_bg_color = bg;
+    visuals.faint_bg_color = bg;
+    visuals.window_stroke = egui::Stroke::new(2.0_f32, fg);
+
+    let widgets = &mut visuals.widgets;
+    for widget in [
+        &mut widgets.noninteractive,
+        &mut widgets.inactive,
+        &mut widgets.hovered,
+        &mut widgets.active,
+        &mut widgets.open,
+    ] {
+        widget.bg_stroke = egui::Stroke::new(1.5_f32, fg);
+        widget.fg_stroke = egui::Stroke::new(1.5_f32, fg);
+    }
+}
+
+/// Whether a screen `width` points wide is mobile, given whether it was until now
+fn mobile_width(width: f32, was_mobile: bool) -> bool {
+    if was_mobile {
+        width <= DESKTOP_ABOVE
+    } else {
+        width < MOBILE_BELOW
+    }
 }
 
 /// Detect narrow screens.
 ///
 /// This is used to show a simpler UI on mobile devices, especially for the web
 pub fn is_mobile(ctx: &egui::Context) -> bool {
-    let screen_size = ctx.screen_rect().size();
-    screen_size.x < 550.0
+    let id = egui::Id::new("is_mobile");
+    let width = ctx.screen_rect().width();
+    ctx.data_mut(|d| {
+        let mobile = d.get_temp_mut_or_insert_with(id, || width < MOBILE_BELOW);
+        *mobile = mobile_width(width, *mobile);
+        *mobile
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contrasts_accents() {
+        assert_eq!(contrasting(egui::Color32::YELLOW), egui::Color32::BLACK);
+        assert_eq!(contrasting(egui::Color32::DARK_BLUE), egui::Color32::WHITE);
+    }
+
+    #[test]
+    fn mobile_has_hysteresis() {
+        assert!(mobile_width(400.0, false));
+        assert!(!mobile_width(570.0, false));
+        assert!(mobile_width(570.0, true));
+        assert!(mobile_width(600.0, true));
+        assert!(!mobile_width(620.0, true));
+    }
 }
@@ -0,0 +1,155 @@
+//! Exporting the settings, importing them on another machine, and resetting them
+//!
+//! Settings are exported as JSON without the wallets, see [`persistence::export`]. Natively the
+//! file goes through the system's file dialogs, while browsers download and upload it.
+
+use std::sync::{Arc, Mutex};
+
+use multinode_i18n::tr;
+
+use super::backend_panel::file_dialog;
+use super::{persistence, State};
+
+/// Name suggested for exported settings
+const FILE_NAME: &str = "multinode-settings.json";
+
+/// Name and contents of a file the user picked, shared with the dialog reading it
+type Picked = Arc<Mutex<Option<(String, Vec<u8>)>>>;
+
+/// What the user chose to do to the state
+pub enum Action {
+    /// Use settings imported from a file
+    Import(Box<State>),
+
+    /// Clear the saved settings, going back to the defaults
+    Reset,
+}
+
+/// Exports and imports the settings, and asks before they're reset
+#[derive(Default)]
+pub struct Transfer {
+    /// A settings file the user picked, until the next frame reads it
+    picked: Picked,
+
+    /// Whether to ask the user to confirm resetting all settings
+    confirm_reset: bool,
+
+    /// How the last import went, or why the last export failed
+    outcome: Option<Result<String, String>>,
+}
+
+impl Transfer {
+    /// Saves the settings to a file the user picks, or downloads them
+    pub fn export(&mut self, state: &State) {
+        let exported = persistence::export(state)
+            .map_err(|e| e.to_string())
+            .and_then(|json| {
+                file_dialog::save_file(FILE_NAME, json.as_bytes()).map_err(|e| format!("{e:?}"))
+            });
+        if let Err(e) = exported {
+            tracing::error!("Failed to export settings: {e}");
+            self.outcome = Some(Err(tr!("transfer-export-failed", error = e)));
+        }
+    }
+
+    /// Asks the user for a settings file to import
+    pub fn import(&mut self, ctx: &egui::Context) {
+        let picked = self.picked.clone();
+        let ctx = ctx.clone();
+        let on_open = move |name, bytes| {
+            *picked.lock().unwrap() = Some((name, bytes));
+            ctx.request_repaint();
+        };
+        if let Err(e) = file_dialog::open_file(&["json"], on_open) {
+            tracing::error!("Failed to open settings file: {:?}", e);
+        }
+    }
+
+    /// Tells the user the saved state couldn't be restored, and where it is backed up
+    pub fn unrestored(&mut self, error: &persistence::Error) {
+        self.outcome = Some(Err(tr!(
+            "transfer-unrestored",
+            error = error,
+            key = persistence::BACKUP_KEY,
+            location = saved_location()
+        )));
+    }
+
+    /// Asks the user to confirm resetting all settings
+    pub fn reset(&mut self) {
+        self.confirm_reset = true;
+    }
+
+    /// Reads the picked settings file and shows how it went, and asks to confirm a reset.
+    /// Returns what the user chose to do.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<Action> {
+        let mut action = None;
+
+        if let Some((name, bytes)) = self.picked.lock().unwrap().take() {
+            match persistence::import(&bytes) {
+                Ok(state) => {
+                    action = Some(Action::Import(Box::new(state)));
+                    self.outcome = Some(Ok(tr!("transfer-imported", name = name)));
+                }
+                Err(e) => {
+                    tracing::error!("Failed to import settings from {name}: {e}");
+                    self.outcome = Some(Err(tr!("transfer-import-failed", name = name, error = e)));
+                }
+            }
+        }
+
+        if self.confirm_reset {
+            egui::Window::new(tr!("transfer-reset-title"))
+                .id(egui::Id::new("confirm_reset"))
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label(tr!("transfer-reset-question"));
+                    ui.horizontal(|ui| {
+                        if ui.button(tr!("transfer-reset")).clicked() {
+                            action = Some(Action::Reset);
+                            self.confirm_reset = false;
+                        }
+                        if ui.button(tr!("transfer-cancel")).clicked() {
+                            self.confirm_reset = false;
+                        }
+                    });
+                });
+        }
+
+        if let Some(outcome) = &self.outcome {
+            let mut close = false;
+            egui::Window::new(tr!("transfer-title"))
+                .id(egui::Id::new("settings_transfer"))
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    match outcome {
+                        Ok(message) => ui.label(message),
+                        Err(error) => ui.colored_label(ui.visuals().error_fg_color, error),
+                    };
+                    close = ui.button(tr!("transfer-ok")).clicked();
+                });
+            if close {
+                self.outcome = None;
+            }
+        }
+
+        action
+    }
+}
+
+/// Where eframe saves the state: in `app.ron` in the app's data directory
+#[cfg(not(target_arch = "wasm32"))]
+fn saved_location() -> String {
+    eframe::storage_dir(crate::APP_NAME)
+        .map(|dir| dir.join("app.ron").display().to_string())
+        .unwrap_or_else(|| tr!("transfer-saved-nowhere"))
+}
+
+/// Where eframe saves the state: in the page's local storage
+#[cfg(target_arch = "wasm32")]
+fn saved_location() -> String {
+    tr!("transfer-local-storage")
+}
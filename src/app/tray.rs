@@ -0,0 +1,248 @@
+//! Running the desktop node in the background
+//!
+//! With the background setting on, the node gets an icon in the system tray, and closing the
+//! window only hides it. The tray's menu shows or hides the window, shows how the node is doing,
+//! and quits. Quitting, from the tray or the app, closes the window for real, which saves the
+//! state and stops the node in [`on_exit`](eframe::App::on_exit).
+//!
+//! Linux desktops show the icon as a StatusNotifierItem, over D-Bus, while Windows and macOS
+//! have their own tray.
+
+use std::sync::{Arc, Mutex};
+
+use multinode_i18n::tr;
+
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+mod desktop;
+#[cfg(target_os = "linux")]
+mod linux;
+
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+use desktop::Icon;
+#[cfg(target_os = "linux")]
+use linux::Icon;
+
+/// Other systems have no tray the app knows of
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+struct Icon;
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+impl Icon {
+    fn new(_menu: &Menu, _on_event: impl Fn(Event) + Send + Sync + 'static) -> Option<Self> {
+        tracing::warn!("No tray on this system, so the node can't run in the background");
+        None
+    }
+
+    fn set_menu(&self, _menu: &Menu) {}
+}
+
+/// What the user picked in the tray's menu
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Event {
+    ToggleWindow,
+    Quit,
+}
+
+/// The labels of the tray's menu
+#[derive(Debug, Clone, PartialEq)]
+struct Menu {
+    toggle_window: String,
+    status: String,
+    quit: String,
+}
+
+/// The icon, from the app's own
+fn icon() -> Option<egui::IconData> {
+    eframe::icon_data::from_png_bytes(include_bytes!("../../assets/icon-256.png"))
+        .inspect_err(|e| tracing::error!("Failed to load the tray icon: {e}"))
+        .ok()
+}
+
+/// Whether the window is hidden, and whether the user quit. Shared with the tray's callback,
+/// which acts on what the user picked right away rather than waiting for a frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Window {
+    /// Whether the window is hidden in the tray
+    hidden: bool,
+
+    /// Whether the user quit, so closing the window doesn't just hide it
+    quitting: bool,
+}
+
+impl Window {
+    /// What the user picked in the tray's menu
+    fn on_event(&mut self, ctx: &egui::Context, event: Event) {
+        match event {
+            Event::ToggleWindow => self.set_hidden(ctx, !self.hidden),
+            Event::Quit => self.quit(ctx),
+        }
+    }
+
+    /// Hides the window rather than closing it, unless the user quit
+    fn on_close_requested(&mut self, ctx: &egui::Context) {
+        if !self.quitting {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.set_hidden(ctx, true);
+        }
+    }
+
+    fn quit(&mut self, ctx: &egui::Context) {
+        self.quitting = true;
+        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+    }
+
+    /// Hides the window, or shows it again.
+    ///
+    /// A hidden window still gets the frames that carry out the tray's commands on Linux and
+    /// macOS, but Windows may stop painting it, so it's only minimized there. So is it where
+    /// windows can't be hidden, as on Wayland.
+    fn set_hidden(&mut self, ctx: &egui::Context, hidden: bool) {
+        use egui::ViewportCommand;
+
+        self.hidden = hidden;
+        if cfg!(not(target_os = "windows")) {
+            ctx.send_viewport_cmd(ViewportCommand::Visible(!hidden));
+        }
+        ctx.send_viewport_cmd(ViewportCommand::Minimized(hidden));
+        if !hidden {
+            ctx.send_viewport_cmd(ViewportCommand::Focus);
+        }
+        ctx.request_repaint();
+    }
+}
+
+/// The tray icon, and its menu
+struct Tray {
+    icon: Icon,
+    menu: Menu,
+}
+
+impl Tray {
+    /// Adds the icon, handling what the user picks in its menu with `window`. Returns `None` if
+    /// the desktop has no tray.
+    fn new(ctx: &egui::Context, menu: Menu, window: Arc<Mutex<Window>>) -> Option<Self> {
+        let ctx = ctx.clone();
+        let on_event = move |event| window.lock().unwrap().on_event(&ctx, event);
+        let icon = Icon::new(&menu, on_event)?;
+        Some(Self { icon, menu })
+    }
+
+    fn set_menu(&mut self, menu: Menu) {
+        if menu != self.menu {
+            self.icon.set_menu(&menu);
+            self.menu = menu;
+        }
+    }
+}
+
+/// Keeps the node running in the background while the background setting is on
+#[derive(Default)]
+pub struct Background {
+    tray: Option<Tray>,
+
+    /// The setting the tray was last set up for
+    enabled: Option<bool>,
+
+    window: Arc<Mutex<Window>>,
+}
+
+impl Background {
+    /// Adds or removes the tray icon as the setting changes, and hides the window rather than
+    /// closing it while there's a tray to bring it back
+    pub fn update(&mut self, ctx: &egui::Context, enabled: bool, status: String) {
+        if self.enabled != Some(enabled) {
+            self.enabled = Some(enabled);
+            let menu = self.menu(status.clone());
+            self.tray = enabled
+                .then(|| Tray::new(ctx, menu, self.window.clone()))
+                .flatten();
+            let mut window = self.window.lock().unwrap();
+            if self.tray.is_none() && window.hidden {
+                window.set_hidden(ctx, false);
+            }
+        }
+
+        let menu = self.menu(status);
+        let Some(tray) = &mut self.tray else {
+            return;
+        };
+        tray.set_menu(menu);
+
+        if ctx.input(|i| i.viewport().close_requested()) {
+            self.window.lock().unwrap().on_close_requested(ctx);
+        }
+    }
+
+    /// Closes the window and stops the node, even if the tray would keep it running
+    pub fn quit(&mut self, ctx: &egui::Context) {
+        self.window.lock().unwrap().quit(ctx);
+    }
+
+    fn menu(&self, status: String) -> Menu {
+        Menu {
+            toggle_window: match self.window.lock().unwrap().hidden {
+                true => tr!("tray-show"),
+                false => tr!("tray-hide"),
+            },
+            status,
+            quit: tr!("tray-quit"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::ViewportCommand;
+
+    /// The commands `f` sends to the window
+    fn commands(f: impl FnOnce(&egui::Context)) -> Vec<ViewportCommand> {
+        let ctx = egui::Context::default();
+        let mut f = Some(f);
+        let output = ctx.run(Default::default(), |ctx| {
+            if let Some(f) = f.take() {
+                f(ctx);
+            }
+        });
+        output
+            .viewport_output
+            .into_values()
+            .flat_map(|viewport| viewport.commands)
+            .collect()
+    }
+
+    #[test]
+    fn closing_hides_until_the_user_quits() {
+        let mut window = Window::default();
+
+        let hidden = commands(|ctx| window.on_close_requested(ctx));
+        assert!(window.hidden);
+        assert_eq!(hidden[0], ViewportCommand::CancelClose);
+        assert!(hidden.contains(&ViewportCommand::Minimized(true)));
+
+        let shown = commands(|ctx| window.on_event(ctx, Event::ToggleWindow));
+        assert!(!window.hidden);
+        assert!(shown.contains(&ViewportCommand::Focus));
+
+        let quit = commands(|ctx| window.on_event(ctx, Event::Quit));
+        assert!(window.quitting);
+        assert_eq!(quit, vec![ViewportCommand::Close]);
+
+        // Closing once the user quit closes for real
+        assert!(commands(|ctx| window.on_close_requested(ctx)).is_empty());
+        assert!(!window.hidden);
+    }
+
+    #[test]
+    fn tray_events_act_without_a_frame() {
+        let ctx = egui::Context::default();
+        let window = Arc::new(Mutex::new(Window::default()));
+        let shared = window.clone();
+        let on_event = move |event| shared.lock().unwrap().on_event(&ctx, event);
+
+        on_event(Event::ToggleWindow);
+        assert!(window.lock().unwrap().hidden);
+        on_event(Event::Quit);
+        assert!(window.lock().unwrap().quitting);
+    }
+}
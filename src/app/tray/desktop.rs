@@ -0,0 +1,86 @@
+//! The tray icon on Windows and macOS, which the app adds from its main thread
+
+use tray_icon::menu::{MenuEvent, MenuItem, PredefinedMenuItem};
+use tray_icon::{TrayIcon, TrayIconBuilder};
+
+use super::{Event, Menu};
+
+/// Ids of the menu items picking something
+const TOGGLE_WINDOW: &str = "toggle_window";
+const QUIT: &str = "quit";
+
+pub(super) struct Icon {
+    /// Removes the icon when dropped
+    _tray: TrayIcon,
+
+    toggle_window: MenuItem,
+    status: MenuItem,
+    quit: MenuItem,
+}
+
+impl Icon {
+    /// Adds the icon to the tray
+    pub(super) fn new(
+        menu: &Menu,
+        on_event: impl Fn(Event) + Send + Sync + 'static,
+    ) -> Option<Self> {
+        let toggle_window = MenuItem::with_id(TOGGLE_WINDOW, &menu.toggle_window, true, None);
+        let status = MenuItem::new(&menu.status, false, None);
+        let quit = MenuItem::with_id(QUIT, &menu.quit, true, None);
+
+        let tray_menu = tray_icon::menu::Menu::new();
+        let tray = tray_menu
+            .append_items(&[
+                &toggle_window,
+                &status,
+                &PredefinedMenuItem::separator(),
+                &quit,
+            ])
+            .map_err(|e| e.to_string())
+            .and_then(|()| {
+                let mut builder = TrayIconBuilder::new()
+                    .with_menu(Box::new(tray_menu))
+                    .with_tooltip(crate::APP_NAME);
+                if let Some(icon) = super::icon() {
+                    let icon = tray_icon::Icon::from_rgba(icon.rgba, icon.width, icon.height)
+                        .map_err(|e| e.to_string())?;
+                    builder = builder.with_icon(icon);
+                }
+                builder.build().map_err(|e| e.to_string())
+            });
+        let tray = match tray {
+            Ok(tray) => tray,
+            Err(e) => {
+                tracing::error!("Failed to add the tray icon: {e}");
+                return None;
+            }
+        };
+
+        MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
+            if event.id == TOGGLE_WINDOW {
+                on_event(Event::ToggleWindow);
+            } else if event.id == QUIT {
+                on_event(Event::Quit);
+            }
+        }));
+
+        Some(Self {
+            _tray: tray,
+            toggle_window,
+            status,
+            quit,
+        })
+    }
+
+    pub(super) fn set_menu(&self, menu: &Menu) {
+        self.toggle_window.set_text(&menu.toggle_window);
+        self.status.set_text(&menu.status);
+        self.quit.set_text(&menu.quit);
+    }
+}
+
+impl Drop for Icon {
+    fn drop(&mut self) {
+        MenuEvent::set_event_handler(None::<fn(MenuEvent)>);
+    }
+}
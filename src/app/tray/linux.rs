@@ -0,0 +1,121 @@
+//! The tray icon as a StatusNotifierItem, which Linux desktops show over D-Bus
+
+use std::sync::Arc;
+
+use ksni::TrayMethods as _;
+
+use super::{Event, Menu};
+
+/// What the desktop asks D-Bus about the icon
+struct Item {
+    menu: Menu,
+
+    /// ARGB32 pixels of the icon
+    icon: Option<ksni::Icon>,
+
+    on_event: Arc<dyn Fn(Event) + Send + Sync>,
+}
+
+impl ksni::Tray for Item {
+    fn id(&self) -> String {
+        env!("CARGO_PKG_NAME").into()
+    }
+
+    fn title(&self) -> String {
+        crate::APP_NAME.into()
+    }
+
+    fn icon_pixmap(&self) -> Vec<ksni::Icon> {
+        self.icon.iter().cloned().collect()
+    }
+
+    fn tool_tip(&self) -> ksni::ToolTip {
+        ksni::ToolTip {
+            title: crate::APP_NAME.into(),
+            description: self.menu.status.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Clicking the icon shows or hides the window
+    fn activate(&mut self, _x: i32, _y: i32) {
+        (self.on_event)(Event::ToggleWindow);
+    }
+
+    fn menu(&self) -> Vec<ksni::MenuItem<Self>> {
+        use ksni::menu::{MenuItem, StandardItem};
+
+        let item = |label: &str, event: Option<Event>| {
+            StandardItem {
+                label: label.to_owned(),
+                enabled: event.is_some(),
+                activate: Box::new(move |item: &mut Self| {
+                    if let Some(event) = event {
+                        (item.on_event)(event);
+                    }
+                }),
+                ..Default::default()
+            }
+            .into()
+        };
+        vec![
+            item(&self.menu.toggle_window, Some(Event::ToggleWindow)),
+            item(&self.menu.status, None),
+            MenuItem::Separator,
+            item(&self.menu.quit, Some(Event::Quit)),
+        ]
+    }
+}
+
+pub(super) struct Icon {
+    handle: ksni::Handle<Item>,
+}
+
+impl Icon {
+    /// Registers the icon with the desktop, if it has a tray
+    pub(super) fn new(
+        menu: &Menu,
+        on_event: impl Fn(Event) + Send + Sync + 'static,
+    ) -> Option<Self> {
+        let icon = super::icon().map(|icon| {
+            let mut data = icon.rgba;
+            data.chunks_exact_mut(4)
+                .for_each(|pixel| pixel.rotate_right(1));
+            ksni::Icon {
+                width: icon.width as i32,
+                height: icon.height as i32,
+                data,
+            }
+        });
+        let item = Item {
+            menu: menu.clone(),
+            icon,
+            on_event: Arc::new(on_event),
+        };
+
+        // The app runs in tokio, but the frame waits for the icon, to know there is a tray
+        let spawned = tokio::task::block_in_place(|| {
+            tokio::runtime::Handle::current().block_on(item.spawn())
+        });
+        match spawned {
+            Ok(handle) => Some(Self { handle }),
+            Err(e) => {
+                tracing::error!("Failed to add the tray icon: {e}");
+                None
+            }
+        }
+    }
+
+    pub(super) fn set_menu(&self, menu: &Menu) {
+        let handle = self.handle.clone();
+        let menu = menu.clone();
+        tokio::spawn(async move { handle.update(|item| item.menu = menu).await });
+    }
+}
+
+impl Drop for Icon {
+    fn drop(&mut self) {
+        // Asked to shut down right away, without waiting for it to be done
+        drop(self.handle.shutdown());
+    }
+}
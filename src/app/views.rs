@@ -0,0 +1,503 @@
+//! Views of the main window, arranged in a dock and natively able to pop out into windows of
+//! their own.
+//!
+//! The Wallet, Node, Node Log, Plugins and Peers views, and the panels plugins contribute, are
+//! tabs of a dock filling the main window, which can be dragged around and split. Natively, all
+//! but the Node view can also be popped out into a separate window using egui's multiple
+//! viewports, and where that window was and how big it was is remembered. Browsers only have the
+//! one canvas, so there the views always stay in the dock.
+//!
+//! Screens too narrow for a dock, such as phones, show one view at a time instead, picked from a
+//! bar of tabs along the bottom.
+
+use std::collections::BTreeMap;
+
+use super::IS_WEB;
+use egui_dock::{DockArea, DockState, NodeIndex, TabViewer};
+use multinode_i18n::tr;
+
+/// Size of a popped out window the first time
+const DEFAULT_SIZE: egui::Vec2 = egui::vec2(480.0, 400.0);
+
+/// A view of the main window
+#[derive(
+    Debug,
+    Clone,
+    Hash,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Deserialize,
+    serde::Serialize,
+)]
+pub enum View {
+    /// The plugin detail view
+    #[default]
+    Node,
+    Log,
+    Plugins,
+    Peers,
+    Wallet,
+
+    /// The UI panel of the named plugin
+    Plugin(String),
+}
+
+impl View {
+    /// The views which are always there, unlike plugin panels
+    pub const CORE: [View; 5] = [
+        View::Node,
+        View::Log,
+        View::Plugins,
+        View::Peers,
+        View::Wallet,
+    ];
+
+    /// The view's title, looking plugin panels' titles up in `panels`
+    pub fn title(&self, panels: &[(String, String)]) -> String {
+        match self {
+            View::Node => tr!("view-node"),
+            View::Log => tr!("view-log"),
+            View::Plugins => tr!("view-plugins"),
+            View::Peers => tr!("view-peers"),
+            View::Wallet => tr!("view-wallet"),
+            View::Plugin(name) => {
+                let title = panels
+                    .iter()
+                    .find(|(panel, _)| panel == name)
+                    .map_or(name.as_str(), |(_, title)| title.as_str());
+                format!("🔌 {title}")
+            }
+        }
+    }
+
+    /// Whether the view can have a window of its own
+    fn can_pop_out(&self) -> bool {
+        *self != View::Node && !IS_WEB
+    }
+}
+
+/// The dock as it is first, and after resetting the layout: the wallet and plugins on the left,
+/// and the node above its log on the right
+fn default_dock() -> DockState<View> {
+    let mut dock = DockState::new(vec![View::Wallet, View::Plugins]);
+    let surface = dock.main_surface_mut();
+    let [_, right] = surface.split_right(NodeIndex::root(), 0.3, vec![View::Node, View::Peers]);
+    surface.split_below(right, 0.7, vec![View::Log]);
+    dock
+}
+
+/// The dock's own labels, in the user's language
+fn translations() -> egui_dock::Translations {
+    egui_dock::Translations {
+        tab_context_menu: egui_dock::TabContextMenuTranslations {
+            close_button: tr!("dock-close-tab"),
+            eject_button: tr!("dock-pop-out"),
+        },
+        window: egui_dock::WindowTranslations {
+            close_button_tooltip: String::new(),
+        },
+    }
+}
+
+/// Where a view's own window is, if it has one
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ViewWindow {
+    /// Whether the view is in a window of its own rather than the dock
+    pub popped_out: bool,
+
+    /// Position of the window's outer corner, in points
+    pub pos: Option<egui::Pos2>,
+
+    /// Size of the window's contents, in points
+    pub size: Option<egui::Vec2>,
+}
+
+/// Saves the windows as a list of `(view, window)` pairs, since JSON can only key maps by strings
+/// and plugin views carry their name
+mod window_list {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::{View, ViewWindow};
+
+    pub fn serialize<S: Serializer>(
+        windows: &BTreeMap<View, ViewWindow>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(windows)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<View, ViewWindow>, D::Error> {
+        Ok(Vec::<(View, ViewWindow)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+/// Saves the dock without the areas of its nodes. They're laid out again each frame, and are
+/// infinite until then, which JSON can't hold.
+mod dock_layout {
+    use egui_dock::{DockState, Node};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::View;
+
+    pub fn serialize<S: Serializer>(
+        dock: &DockState<View>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut dock = dock.clone();
+        for (_, node) in dock.iter_all_nodes_mut() {
+            node.set_rect(egui::Rect::ZERO);
+            if let Node::Leaf { viewport, .. } = node {
+                *viewport = egui::Rect::ZERO;
+            }
+        }
+        dock.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DockState<View>, D::Error> {
+        DockState::deserialize(deserializer)
+    }
+}
+
+/// Shows the tabs of the dock, and notes those the user closed or popped out
+struct Viewer<'a, F> {
+    panels: &'a [(String, String)],
+    content: &'a mut F,
+    closed: Vec<View>,
+    popped_out: Vec<View>,
+}
+
+impl<F: FnMut(&View, &mut egui::Ui)> TabViewer for Viewer<'_, F> {
+    type Tab = View;
+
+    fn title(&mut self, tab: &mut View) -> egui::WidgetText {
+        tab.title(self.panels).into()
+    }
+
+    /// Titles change with the language and plugin panels, so tabs go by the view instead
+    fn id(&mut self, tab: &mut View) -> egui::Id {
+        egui::Id::new(("view", &*tab))
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, tab: &mut View) {
+        (self.content)(tab, ui)
+    }
+
+    fn context_menu(
+        &mut self,
+        ui: &mut egui::Ui,
+        tab: &mut View,
+        _surface: egui_dock::SurfaceIndex,
+        _node: NodeIndex,
+    ) {
+        if tab.can_pop_out() && ui.button(tr!("dock-pop-out")).clicked() {
+            self.popped_out.push(tab.clone());
+            ui.close_menu();
+        }
+    }
+
+    fn closeable(&mut self, tab: &mut View) -> bool {
+        matches!(tab, View::Plugin(_))
+    }
+
+    fn on_close(&mut self, tab: &mut View) -> bool {
+        self.closed.push(tab.clone());
+        true
+    }
+
+    /// Views pop out into windows of their own, rather than the dock's windows inside this one
+    fn allowed_in_windows(&self, _tab: &mut View) -> bool {
+        false
+    }
+}
+
+/// The views of the main window, and their windows
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Views {
+    /// How the views in the main window are arranged
+    #[serde(with = "dock_layout")]
+    dock: DockState<View>,
+
+    #[serde(with = "window_list")]
+    windows: BTreeMap<View, ViewWindow>,
+
+    /// The view shown on narrow screens
+    mobile_tab: View,
+
+    /// How each window was opened this session. egui changes a window whenever the builder
+    /// does, so it's kept as it was rather than following the window as it's moved.
+    #[serde(skip)]
+    builders: BTreeMap<View, egui::ViewportBuilder>,
+}
+
+impl Default for Views {
+    fn default() -> Self {
+        Self {
+            dock: default_dock(),
+            windows: Default::default(),
+            mobile_tab: Default::default(),
+            builders: Default::default(),
+        }
+    }
+}
+
+impl Views {
+    /// Brings `view` to the front: its tab in the dock or on narrow screens, or its window
+    pub fn show_view(&mut self, ctx: &egui::Context, view: View) {
+        if self.popped_out(&view) {
+            ctx.send_viewport_cmd_to(
+                egui::ViewportId::from_hash_of(("view", &view)),
+                egui::ViewportCommand::Focus,
+            );
+        } else if let Some((surface, node, tab)) = self.dock.find_tab(&view) {
+            self.dock.set_active_tab((surface, node, tab));
+            self.dock.set_focused_node_and_surface((surface, node));
+        } else {
+            self.push(view.clone());
+        }
+        self.mobile_tab = view;
+    }
+
+    /// Adds `view` to the dock next to the Node view, or wherever there's room if that's gone
+    fn push(&mut self, view: View) {
+        let surface = self.dock.main_surface_mut();
+        match surface.find_tab(&View::Node) {
+            Some((node, _)) => surface[node].append_tab(view),
+            None => surface.push_to_first_leaf(view),
+        }
+    }
+
+    /// Puts every view back in the dock, arranged as at first
+    pub fn reset_layout(&mut self) {
+        self.dock = default_dock();
+        for window in self.windows.values_mut() {
+            window.popped_out = false;
+        }
+    }
+
+    /// The views which are open: the core ones and the open plugin panels
+    fn open(panels: &[(String, String)]) -> Vec<View> {
+        View::CORE
+            .into_iter()
+            .chain(panels.iter().map(|(name, _)| View::Plugin(name.clone())))
+            .collect()
+    }
+
+    fn popped_out(&self, view: &View) -> bool {
+        view.can_pop_out() && self.windows.get(view).is_some_and(|w| w.popped_out)
+    }
+
+    /// Makes the dock hold exactly the views which are open and not popped out. Plugin panels
+    /// opening show next to the Node view.
+    fn sync(&mut self, open: &[View]) {
+        let gone: Vec<View> = self
+            .dock
+            .iter_all_tabs()
+            .map(|(_, tab)| tab)
+            .filter(|tab| !open.contains(tab) || self.popped_out(tab))
+            .cloned()
+            .collect();
+        // Removing tabs one at a time keeps each group's active tab the one it was
+        for tab in gone {
+            if let Some(found) = self.dock.find_tab(&tab) {
+                self.dock.remove_tab(found);
+            }
+        }
+        for view in open {
+            if !self.popped_out(view) && self.dock.find_tab(view).is_none() {
+                self.push(view.clone());
+            }
+        }
+    }
+
+    /// Shows the dock in `ui` and each popped out view in its own window, with `content` filling
+    /// in each view. `panels` are the names and titles of the open plugin panels. Returns the
+    /// views the user closed.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        ui: &mut egui::Ui,
+        panels: &[(String, String)],
+        mut content: impl FnMut(&View, &mut egui::Ui),
+    ) -> Vec<View> {
+        let open = Self::open(panels);
+        self.sync(&open);
+
+        let mut viewer = Viewer {
+            panels,
+            content: &mut content,
+            closed: Vec::new(),
+            popped_out: Vec::new(),
+        };
+        self.dock.translations = translations();
+        DockArea::new(&mut self.dock)
+            .style(egui_dock::Style::from_egui(ui.style()))
+            .show_inside(ui, &mut viewer);
+
+        let Viewer {
+            closed, popped_out, ..
+        } = viewer;
+        for view in popped_out {
+            self.windows.entry(view).or_default().popped_out = true;
+        }
+
+        for view in open {
+            if !self.popped_out(&view) {
+                self.builders.remove(&view);
+                continue;
+            }
+
+            let title = view.title(panels);
+            let window = self.windows.entry(view.clone()).or_default();
+            let builder = self
+                .builders
+                .entry(view.clone())
+                .or_insert_with(|| {
+                    let builder = egui::ViewportBuilder::default()
+                        .with_title(title)
+                        .with_inner_size(window.size.unwrap_or(DEFAULT_SIZE));
+                    match window.pos {
+                        Some(pos) => builder.with_position(pos),
+                        None => builder,
+                    }
+                })
+                .clone();
+
+            ctx.show_viewport_immediate(
+                egui::ViewportId::from_hash_of(("view", &view)),
+                builder,
+                |ctx, _class| {
+                    egui::CentralPanel::default().show(ctx, |ui| content(&view, ui));
+
+                    ctx.input(|i| {
+                        let viewport = i.viewport();
+                        if let Some(rect) = viewport.outer_rect {
+                            window.pos = Some(rect.min);
+                        }
+                        if let Some(rect) = viewport.inner_rect {
+                            window.size = Some(rect.size());
+                        }
+                        // Closing the window puts the view back in the dock
+                        if viewport.close_requested() {
+                            window.popped_out = false;
+                        }
+                    });
+                },
+            );
+        }
+
+        closed
+    }
+
+    /// Shows the bar of tabs picking the view on narrow screens, with controls big enough to tap
+    pub fn mobile_tabs_ui(&mut self, ui: &mut egui::Ui, panels: &[(String, String)]) {
+        let open = Self::open(panels);
+        if !open.contains(&self.mobile_tab) {
+            self.mobile_tab = View::Node;
+        }
+
+        egui::ScrollArea::horizontal().show(ui, |ui| {
+            ui.horizontal(|ui| {
+                for view in open {
+                    let title = view.title(panels);
+                    let selected = self.mobile_tab == view;
+                    let size = egui::vec2(0.0, ui.spacing().interact_size.y);
+                    if ui
+                        .add(egui::Button::new(title).selected(selected).min_size(size))
+                        .clicked()
+                    {
+                        self.mobile_tab = view;
+                    }
+                }
+            });
+        });
+    }
+
+    /// Shows the view picked on narrow screens in `ui`, with `content` filling it in
+    pub fn show_mobile(
+        &mut self,
+        ui: &mut egui::Ui,
+        mut content: impl FnMut(&View, &mut egui::Ui),
+    ) {
+        content(&self.mobile_tab, ui);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contains(views: &Views, view: &View) -> bool {
+        views.dock.find_tab(view).is_some()
+    }
+
+    /// The tab shown in the group holding `view`
+    fn active_next_to(views: &Views, view: &View) -> Option<View> {
+        let (node, _) = views.dock.find_main_surface_tab(view)?;
+        match &views.dock.main_surface()[node] {
+            egui_dock::Node::Leaf { tabs, active, .. } => tabs.get(active.0).cloned(),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn syncs_the_dock_with_open_views() {
+        let mut views = Views::default();
+        let plugin = View::Plugin("chat".to_owned());
+        let mut open = View::CORE.to_vec();
+        open.push(plugin.clone());
+
+        views.sync(&open);
+        assert!(contains(&views, &plugin));
+
+        // Popped out views leave the dock, except where they can't pop out
+        views.windows.entry(View::Log).or_default().popped_out = true;
+        views.windows.entry(View::Node).or_default().popped_out = true;
+        views.sync(&open);
+        assert_eq!(contains(&views, &View::Log), IS_WEB);
+        assert!(contains(&views, &View::Node));
+
+        // Closed plugin panels leave the dock
+        open.pop();
+        views.sync(&open);
+        assert!(!contains(&views, &plugin));
+
+        views.reset_layout();
+        views.sync(&open);
+        let layout = |dock: &DockState<View>| {
+            let mut json = serde_json::Serializer::new(Vec::new());
+            dock_layout::serialize(dock, &mut json).unwrap();
+            json.into_inner()
+        };
+        assert_eq!(layout(&views.dock), layout(&default_dock()));
+    }
+
+    #[test]
+    fn closing_a_tab_keeps_the_one_shown_next_to_it() {
+        let ctx = egui::Context::default();
+        let mut views = Views::default();
+        let plugin = View::Plugin("chat".to_owned());
+        let mut open = View::CORE.to_vec();
+        open.push(plugin.clone());
+
+        views.show_view(&ctx, plugin.clone());
+        assert_eq!(active_next_to(&views, &View::Node), Some(plugin));
+
+        views.show_view(&ctx, View::Peers);
+        open.pop();
+        views.sync(&open);
+        assert_eq!(active_next_to(&views, &View::Node), Some(View::Peers));
+    }
+}
@@ -0,0 +1,406 @@
+//! Wallet seed, its encrypted vault, and the keys derived from it.
+//!
+//! The seed is wrapped with AES key wrap under a key stretched from the user's password by
+//! Argon2id, both by [seed_keeper_core], and only the wrapped form is persisted. Signing keys
+//! are derived from the seed with SLIP-10 for ed25519, which only supports hardened derivation.
+//! Paths are written like `m/0'/1'`.
+//!
+//! The seed is backed up as a 24 word BIP-39 mnemonic of its bytes.
+
+use ed25519_dalek::SigningKey;
+use hmac::{Hmac, Mac};
+use multinode_i18n::tr;
+use sha2::Sha512;
+use zeroize::Zeroizing;
+
+/// Minimum password length, as required by `MinString<8>` credentials
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+const SALT_LEN: usize = 16;
+
+/// Number of words in the seed's mnemonic
+pub const MNEMONIC_WORDS: usize = 24;
+
+/// Offset of hardened child indices
+pub const HARDENED: u32 = 0x8000_0000;
+
+/// First index of the path of every plugin key, "PP", which identities can't use
+pub const PLUGIN_PURPOSE: u32 = 0x5050;
+
+/// Errors from the wallet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalletError {
+    /// The password is shorter than [MIN_PASSWORD_LEN]
+    PasswordTooShort,
+
+    /// The password doesn't decrypt the vault, or the vault is corrupt
+    WrongPassword,
+
+    /// There is no seed in the vault yet
+    Empty,
+
+    /// The platform has no source of randomness
+    Random(String),
+
+    /// A derivation path isn't of the form `m/0'/1'`
+    InvalidPath(String),
+
+    /// A derivation path is among those of plugin keys, see [PLUGIN_PURPOSE]
+    ReservedPath(String),
+
+    /// A typed mnemonic isn't a valid seed phrase
+    InvalidMnemonic(String),
+}
+
+impl std::fmt::Display for WalletError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            WalletError::PasswordTooShort => {
+                tr!("wallet-error-password-too-short", min = MIN_PASSWORD_LEN)
+            }
+            WalletError::WrongPassword => tr!("wallet-error-wrong-password"),
+            WalletError::Empty => tr!("wallet-error-empty"),
+            WalletError::Random(e) => tr!("wallet-error-random", error = e),
+            WalletError::InvalidPath(path) => tr!("wallet-error-invalid-path", path = path),
+            WalletError::ReservedPath(path) => tr!("wallet-error-reserved-path", path = path),
+            WalletError::InvalidMnemonic(e) => tr!("wallet-error-invalid-mnemonic", error = e),
+        };
+        f.write_str(&message)
+    }
+}
+
+fn random<const N: usize>() -> Result<[u8; N], WalletError> {
+    let mut bytes = [0u8; N];
+    getrandom::getrandom(&mut bytes).map_err(|e| WalletError::Random(e.to_string()))?;
+    Ok(bytes)
+}
+
+/// The wallet's secret seed, wiped from memory when dropped
+#[derive(Clone)]
+pub struct Seed(Zeroizing<[u8; 32]>);
+
+impl std::fmt::Debug for Seed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Seed(…)")
+    }
+}
+
+impl Seed {
+    /// A new random seed
+    pub fn generate() -> Self {
+        Self(seed_keeper_core::seed::rand_seed())
+    }
+
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(Zeroizing::new(bytes))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// The seed's mnemonic, to be written down as a backup
+    pub fn mnemonic(&self) -> Zeroizing<String> {
+        let mnemonic =
+            bip39::Mnemonic::from_entropy(self.0.as_slice()).expect("32 bytes are valid entropy");
+        Zeroizing::new(mnemonic.to_string())
+    }
+
+    /// Recovers the seed from its mnemonic, ignoring case and extra whitespace
+    pub fn from_mnemonic(phrase: &str) -> Result<Self, WalletError> {
+        let phrase = Zeroizing::new(phrase.split_whitespace().collect::<Vec<_>>().join(" "));
+        let phrase = Zeroizing::new(phrase.to_lowercase());
+        let mnemonic = bip39::Mnemonic::parse_normalized(&phrase)
+            .map_err(|e| WalletError::InvalidMnemonic(e.to_string()))?;
+        if mnemonic.word_count() != MNEMONIC_WORDS {
+            return Err(WalletError::InvalidMnemonic(format!(
+                "expected {MNEMONIC_WORDS} words, got {}",
+                mnemonic.word_count()
+            )));
+        }
+
+        let entropy = Zeroizing::new(mnemonic.to_entropy());
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&entropy);
+        Ok(Self::from_bytes(bytes))
+    }
+
+    /// Derives the ed25519 key at `path` with SLIP-10. Every index is hardened.
+    pub fn derive(&self, path: &[u32]) -> SigningKey {
+        slip10(self.0.as_slice(), path)
+    }
+
+    /// The libp2p keypair of the key at `path`
+    pub fn keypair(&self, path: &[u32]) -> libp2p_identity::Keypair {
+        let mut secret = self.derive(path).to_bytes();
+        let secret = libp2p_identity::ed25519::SecretKey::try_from_bytes(&mut secret)
+            .expect("ed25519 secret keys are 32 bytes");
+        libp2p_identity::ed25519::Keypair::from(secret).into()
+    }
+}
+
+/// Parses a derivation path such as `m/0'/1'`. Every index is hardened, so the `'` is optional.
+pub fn parse_path(path: &str) -> Result<Vec<u32>, WalletError> {
+    let invalid = || WalletError::InvalidPath(path.to_owned());
+    let mut parts = path.trim().split('/');
+    if parts.next() != Some("m") {
+        return Err(invalid());
+    }
+    parts
+        .map(|part| {
+            let index: u32 = part
+                .strip_suffix('\'')
+                .unwrap_or(part)
+                .parse()
+                .map_err(|_| invalid())?;
+            if index >= HARDENED {
+                return Err(invalid());
+            }
+            Ok(index)
+        })
+        .collect()
+}
+
+/// Whether `path` is among those of plugin keys, which identities can't use
+pub fn is_plugin_path(path: &[u32]) -> bool {
+    path.first() == Some(&PLUGIN_PURPOSE)
+}
+
+/// Writes a derivation path as `m/0'/1'`
+pub fn format_path(path: &[u32]) -> String {
+    path.iter().fold("m".to_owned(), |mut text, index| {
+        text.push_str(&format!("/{}'", index & !HARDENED));
+        text
+    })
+}
+
+/// SLIP-10 derivation of the ed25519 key at `path` from a seed of any length
+fn slip10(seed: &[u8], path: &[u32]) -> SigningKey {
+    type HmacSha512 = Hmac<Sha512>;
+
+    let split = |mac: HmacSha512| {
+        let mut i = Zeroizing::new([0u8; 64]);
+        i.copy_from_slice(&mac.finalize().into_bytes());
+        let mut key = Zeroizing::new([0u8; 32]);
+        let mut chain = Zeroizing::new([0u8; 32]);
+        key.copy_from_slice(&i[..32]);
+        chain.copy_from_slice(&i[32..]);
+        (key, chain)
+    };
+
+    let mut mac = <HmacSha512 as Mac>::new_from_slice(b"ed25519 seed").expect("any key length");
+    mac.update(seed);
+    let (mut key, mut chain) = split(mac);
+
+    for index in path {
+        let mut mac =
+            <HmacSha512 as Mac>::new_from_slice(chain.as_slice()).expect("any key length");
+        mac.update(&[0]);
+        mac.update(key.as_slice());
+        mac.update(&(index | HARDENED).to_be_bytes());
+        (key, chain) = split(mac);
+    }
+
+    SigningKey::from_bytes(&key)
+}
+
+/// A plugin's request to sign, waiting for the user to approve it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignRequest {
+    /// Name the plugin was loaded as, which anyone could give their plugin
+    pub plugin: String,
+
+    /// Who signed the plugin and its name, or the hash of its code if unsigned, which its key
+    /// and being always allowed go by
+    pub id: String,
+
+    pub payload: Vec<u8>,
+}
+
+/// The persisted wallet: the seed, encrypted with the user's password
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Vault {
+    /// Salt and wrapped seed, or `None` before a wallet is created
+    pub encrypted_seed: Option<Vec<u8>>,
+}
+
+/// Stretches the password into the key the seed is wrapped with
+fn key(password: &str, salt: &[u8]) -> Result<Zeroizing<[u8; 32]>, WalletError> {
+    seed_keeper_core::derive_key(password, salt).map_err(|_| WalletError::WrongPassword)
+}
+
+impl Vault {
+    /// Encrypts `seed` with `password` into a new vault
+    pub fn seal(seed: &Seed, password: &str) -> Result<Self, WalletError> {
+        if password.chars().count() < MIN_PASSWORD_LEN {
+            return Err(WalletError::PasswordTooShort);
+        }
+
+        let salt: [u8; SALT_LEN] = random()?;
+        let seed = Zeroizing::new(seed.as_bytes().to_vec());
+        let wrapped = seed_keeper_core::wrap::encrypt(key(password, &salt)?, seed)
+            .map_err(|_| WalletError::WrongPassword)?;
+
+        Ok(Self {
+            encrypted_seed: Some([&salt[..], &wrapped].concat()),
+        })
+    }
+
+    /// Decrypts the seed with `password`
+    pub fn unlock(&self, password: &str) -> Result<Seed, WalletError> {
+        let sealed = self.encrypted_seed.as_deref().ok_or(WalletError::Empty)?;
+        if sealed.len() < SALT_LEN {
+            return Err(WalletError::WrongPassword);
+        }
+        let (salt, wrapped) = sealed.split_at(SALT_LEN);
+
+        // Unwrapping checks the seed's integrity, so a wrong password fails here
+        let seed = seed_keeper_core::wrap::decrypt(key(password, salt)?, wrapped)
+            .map_err(|_| WalletError::WrongPassword)?;
+        let bytes: [u8; 32] = seed
+            .as_slice()
+            .try_into()
+            .map_err(|_| WalletError::WrongPassword)?;
+        Ok(Seed::from_bytes(bytes))
+    }
+
+    /// Re-encrypts the seed with `new` into a new vault, once `old` decrypts it. This vault is
+    /// left as it is, to be replaced once the new one is saved.
+    pub fn change_password(&self, old: &str, new: &str) -> Result<Self, WalletError> {
+        let seed = self.unlock(old)?;
+        Self::seal(&seed, new)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.encrypted_seed.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_slip10_test_vectors() {
+        // Test vector 1 for ed25519 from SLIP-0010
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let private = |path: &[u32]| hex::encode(slip10(&seed, path).to_bytes());
+
+        assert_eq!(
+            private(&[]),
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+        );
+        assert_eq!(
+            private(&[0]),
+            "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"
+        );
+        assert_eq!(
+            private(&[0, 1]),
+            "b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2"
+        );
+        assert_eq!(
+            hex::encode(slip10(&seed, &[0]).verifying_key().as_bytes()),
+            "8c8a13df77a28f3445213a0f432fde644acaa215fc72dcdf300d5efaa85d350c"
+        );
+
+        // Indices are always hardened
+        assert_eq!(private(&[0]), private(&[HARDENED]));
+    }
+
+    #[test]
+    fn parses_and_formats_paths() {
+        assert_eq!(parse_path("m").unwrap(), Vec::<u32>::new());
+        assert_eq!(parse_path("m/0'/1'").unwrap(), vec![0, 1]);
+        assert_eq!(parse_path(" m/44/7' ").unwrap(), vec![44, 7]);
+        assert_eq!(format_path(&[44, 7]), "m/44'/7'");
+        assert_eq!(format_path(&[]), "m");
+
+        for invalid in ["", "0/1", "m/", "m/x", "m/-1", "m/2147483648"] {
+            assert_eq!(
+                parse_path(invalid),
+                Err(WalletError::InvalidPath(invalid.to_owned())),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn keypairs_match_derived_keys() {
+        let seed = Seed::from_bytes([9; 32]);
+        let keypair = seed.keypair(&[0, 1]);
+        let public = keypair.public().try_into_ed25519().unwrap();
+
+        assert_eq!(
+            public.to_bytes(),
+            seed.derive(&[0, 1]).verifying_key().to_bytes()
+        );
+        assert_ne!(
+            keypair.public().to_peer_id(),
+            seed.keypair(&[0, 2]).public().to_peer_id()
+        );
+    }
+
+    #[test]
+    fn changes_password() {
+        let seed = Seed::from_bytes([1; 32]);
+        let vault = Vault::seal(&seed, "old password").unwrap();
+
+        assert_eq!(
+            vault.change_password("not the password", "new password"),
+            Err(WalletError::WrongPassword)
+        );
+        assert_eq!(
+            vault.change_password("old password", "short"),
+            Err(WalletError::PasswordTooShort)
+        );
+
+        let changed = vault
+            .change_password("old password", "new password")
+            .unwrap();
+        assert_eq!(changed.unlock("new password").unwrap().as_bytes(), &[1; 32]);
+        assert!(changed.unlock("old password").is_err());
+        assert!(
+            vault.unlock("old password").is_ok(),
+            "the old vault is kept"
+        );
+    }
+
+    #[test]
+    fn recovers_from_mnemonic() {
+        let seed = Seed::from_bytes([5; 32]);
+        let mnemonic = seed.mnemonic();
+        assert_eq!(mnemonic.split(' ').count(), MNEMONIC_WORDS);
+
+        let messy = format!("  {}\n", mnemonic.to_uppercase().replace(' ', "  "));
+        assert_eq!(Seed::from_mnemonic(&messy).unwrap().as_bytes(), &[5; 32]);
+
+        // A 12 word phrase is valid BIP-39, but too short for a seed
+        let twelve = bip39::Mnemonic::from_entropy(&[5; 16]).unwrap().to_string();
+        assert!(Seed::from_mnemonic(&twelve).is_err());
+
+        let mut words: Vec<_> = mnemonic.split(' ').collect();
+        words.swap(0, 1);
+        assert!(Seed::from_mnemonic(&words.join(" ")).is_err());
+    }
+
+    #[test]
+    fn seals_and_unlocks() {
+        let seed = Seed::from_bytes([7; 32]);
+        let vault = Vault::seal(&seed, "correct horse").unwrap();
+
+        assert_eq!(vault.unlock("correct horse").unwrap().as_bytes(), &[7; 32]);
+        assert_eq!(
+            vault.unlock("wrong horse").unwrap_err(),
+            WalletError::WrongPassword
+        );
+        assert_eq!(
+            Vault::seal(&seed, "short").unwrap_err(),
+            WalletError::PasswordTooShort
+        );
+        assert_eq!(
+            Vault::default().unlock("anything").unwrap_err(),
+            WalletError::Empty
+        );
+    }
+}
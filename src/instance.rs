@@ -0,0 +1,436 @@
+//! Plugin files the OS asks the native app to open, and the one instance opening them
+//!
+//! `.wasm` files passed as arguments, say by a file manager's "Open with", are loaded as
+//! plugins. Only one instance runs each profile's node: it listens on a local socket in the
+//! app's data directory, and later launches hand their files to it over that socket and exit.
+//! The instance moves to the socket of whichever profile it switches to, and launches without
+//! `--profile` look for the profile whose node ran last.
+//! On Unix the socket is a Unix domain socket, which other users can't write to. Elsewhere it
+//! is a loopback TCP socket, whose port is kept in a file next to a token the launches must send
+//! first, as any local user could connect to the port.
+//!
+//! Files handed over go through the same signature checks as the ones picked in the app.
+
+use std::io::{BufRead as _, BufReader, Write as _};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::app::profiles;
+
+/// How long a launch may take to send its files, so a stalled one can't hold up the others
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// File in the data directory naming the profile last listened for, empty for the default one
+const ACTIVE: &str = "instance-active";
+
+/// Plugin files to open, as they come in
+#[derive(Clone, Default)]
+pub struct Files {
+    inbox: Inbox,
+
+    /// The data directory the sockets are in, if there is one
+    dir: Option<PathBuf>,
+
+    /// The socket of the profile whose node runs, while listening on it
+    socket: Arc<Mutex<Option<Socket>>>,
+}
+
+/// The files handed over, as the thread accepting launches shares them
+#[derive(Clone, Default)]
+struct Inbox {
+    /// The files of the launches since they were last taken, if there were any
+    paths: Arc<Mutex<Option<Vec<PathBuf>>>>,
+
+    /// Repaints the app, once it has started, so it opens the files handed over
+    ctx: Arc<Mutex<Option<egui::Context>>>,
+}
+
+impl Inbox {
+    fn push(&self, paths: Vec<PathBuf>) {
+        self.paths
+            .lock()
+            .unwrap()
+            .get_or_insert_with(Vec::new)
+            .extend(paths);
+        if let Some(ctx) = &*self.ctx.lock().unwrap() {
+            ctx.request_repaint();
+        }
+    }
+}
+
+impl Files {
+    /// Takes the files to open, if the app was launched again since they were last taken, even
+    /// without files. Repaints `ctx` when it is next launched.
+    pub(crate) fn take(&self, ctx: &egui::Context) -> Option<Vec<PathBuf>> {
+        let Inbox {
+            paths,
+            ctx: repaint,
+        } = &self.inbox;
+        repaint.lock().unwrap().get_or_insert_with(|| ctx.clone());
+        paths.lock().unwrap().take()
+    }
+
+    /// Listens on the named profile's socket, rather than the one of the profile whose node ran
+    /// before, so later launches of that profile hand their files over to this instance
+    pub(crate) fn listen_for(&self, profile: Option<&str>) {
+        if self.dir.is_none() {
+            return;
+        }
+        if let Err(e) = self.listen(profile) {
+            tracing::error!("Failed to listen for files from later launches: {e}");
+        }
+    }
+
+    fn listen(&self, profile: Option<&str>) -> std::io::Result<()> {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+        let name = socket_name(profile);
+        let mut socket = self.socket.lock().unwrap();
+        if socket.as_ref().is_some_and(|socket| socket.name == name) {
+            return Ok(());
+        }
+        // The node of the last profile is gone, so its launches start their own
+        *socket = None;
+
+        std::fs::create_dir_all(dir)?;
+        *socket = Some(Socket::listen(dir, name, self.inbox.clone())?);
+        std::fs::write(dir.join(ACTIVE), profile.unwrap_or_default())
+    }
+}
+
+/// A socket later launches hand their files over on, listened on until it's dropped
+struct Socket {
+    dir: PathBuf,
+    name: String,
+
+    /// Tells the thread accepting launches to stop, once woken
+    stopped: Arc<AtomicBool>,
+}
+
+impl Socket {
+    /// Receives paths from later launches, on a thread of its own
+    fn listen(dir: &Path, name: String, inbox: Inbox) -> std::io::Result<Self> {
+        let listener = socket::bind(dir, &name)?;
+        let stopped = Arc::new(AtomicBool::new(false));
+        let stop = stopped.clone();
+        std::thread::spawn(move || loop {
+            let stream = listener.accept();
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+            let paths = stream.and_then(|stream| {
+                BufReader::new(stream)
+                    .lines()
+                    .map(|line| line.map(PathBuf::from))
+                    .collect::<std::io::Result<Vec<_>>>()
+            });
+            match paths {
+                Ok(paths) => inbox.push(paths),
+                Err(e) => tracing::warn!("Failed to receive files from a launch: {e}"),
+            }
+        });
+        Ok(Self {
+            dir: dir.to_owned(),
+            name,
+            stopped,
+        })
+    }
+}
+
+impl Drop for Socket {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        // Wakes the thread up, waiting for the next launch
+        drop(socket::connect(&self.dir, &self.name));
+        if let Err(e) = socket::remove(&self.dir, &self.name) {
+            tracing::warn!("Failed to remove the socket {}: {e}", self.name);
+        }
+    }
+}
+
+/// The `.wasm` files among the command line arguments
+pub fn wasm_args() -> Vec<PathBuf> {
+    std::env::args_os()
+        .skip(1)
+        .map(PathBuf::from)
+        .filter(|path| path.extension().is_some_and(|ext| ext == "wasm") && path.is_file())
+        // The running instance may have another working directory
+        .filter_map(|path| std::fs::canonicalize(path).ok())
+        .collect()
+}
+
+/// Hands `paths` to the instance already running the named profile's node, if there is one,
+/// and returns `None`. Without a name, that's the profile whose node ran last. Otherwise this
+/// becomes that instance, and the files to open are returned: `paths`, then those later
+/// launches hand over.
+pub fn start(profile: Option<&str>, paths: Vec<PathBuf>) -> Option<Files> {
+    let Some(dir) = eframe::storage_dir(crate::APP_NAME) else {
+        tracing::warn!("No data directory, so every launch starts its own node");
+        let files = Files::default();
+        files.inbox.push(paths);
+        return Some(files);
+    };
+    let profile = match profile {
+        Some(profile) => Some(profile.to_owned()),
+        None => last_active(&dir),
+    };
+    start_in(&dir, profile.as_deref(), paths)
+}
+
+fn start_in(dir: &Path, profile: Option<&str>, paths: Vec<PathBuf>) -> Option<Files> {
+    let name = socket_name(profile);
+    if forward(dir, &name, &paths).is_ok() {
+        tracing::info!("Handed {} file(s) to the running instance", paths.len());
+        return None;
+    }
+
+    let files = Files {
+        dir: Some(dir.to_owned()),
+        ..Default::default()
+    };
+    match files.listen(profile) {
+        Ok(()) => {}
+        // A launch at the same time got there first
+        Err(e)
+            if e.kind() == std::io::ErrorKind::AddrInUse && forward(dir, &name, &paths).is_ok() =>
+        {
+            tracing::info!("Handed {} file(s) to the running instance", paths.len());
+            return None;
+        }
+        Err(e) => tracing::error!("Failed to listen for files from later launches: {e}"),
+    }
+    files.inbox.push(paths);
+    Some(files)
+}
+
+/// The profile whose node ran last, `None` for the default one
+fn last_active(dir: &Path) -> Option<String> {
+    let profile = std::fs::read_to_string(dir.join(ACTIVE)).ok()?;
+    profiles::valid_name(&profile).then_some(profile)
+}
+
+/// Name of the socket of the named profile's instance
+fn socket_name(profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) if profiles::valid_name(profile) => format!("instance-{profile}"),
+        _ => "instance".to_owned(),
+    }
+}
+
+/// Sends `paths` to the running instance, one per line
+fn forward(dir: &Path, name: &str, paths: &[PathBuf]) -> std::io::Result<()> {
+    let mut stream = socket::connect(dir, name)?;
+    for path in paths {
+        match path.to_str() {
+            Some(path) if !path.contains('\n') => writeln!(stream, "{path}")?,
+            _ => tracing::warn!("Can't hand over {}", path.display()),
+        }
+    }
+    stream.flush()
+}
+
+#[cfg(unix)]
+mod socket {
+    use std::io;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+
+    pub struct Listener(UnixListener);
+
+    impl Listener {
+        /// The next launch's connection
+        pub fn accept(&self) -> io::Result<UnixStream> {
+            let stream = self.0.accept()?.0;
+            stream.set_read_timeout(Some(super::READ_TIMEOUT))?;
+            Ok(stream)
+        }
+    }
+
+    fn path(dir: &Path, name: &str) -> PathBuf {
+        dir.join(format!("{name}.sock"))
+    }
+
+    pub fn connect(dir: &Path, name: &str) -> io::Result<UnixStream> {
+        UnixStream::connect(path(dir, name))
+    }
+
+    /// Listens on the named socket, unless another instance does
+    pub fn bind(dir: &Path, name: &str) -> io::Result<Listener> {
+        let path = path(dir, name);
+        match UnixListener::bind(&path) {
+            // Left behind by an instance which didn't exit cleanly, as none answers on it
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse && connect(dir, name).is_err() => {
+                std::fs::remove_file(&path)?;
+                UnixListener::bind(&path)
+            }
+            bound => bound,
+        }
+        .map(Listener)
+    }
+
+    pub fn remove(dir: &Path, name: &str) -> io::Result<()> {
+        std::fs::remove_file(path(dir, name))
+    }
+}
+
+#[cfg(not(unix))]
+mod socket {
+    use std::io::{self, BufRead as _, BufReader, Write as _};
+    use std::net::{Ipv4Addr, TcpListener, TcpStream};
+    use std::path::{Path, PathBuf};
+
+    /// Length of the token, in bytes
+    const TOKEN_LEN: usize = 16;
+
+    pub struct Listener {
+        listener: TcpListener,
+
+        /// What launches send first, proving they could read the port file
+        token: String,
+    }
+
+    impl Listener {
+        /// The next launch's connection, once it sent the token
+        pub fn accept(&self) -> io::Result<BufReader<TcpStream>> {
+            let stream = self.listener.accept()?.0;
+            stream.set_read_timeout(Some(super::READ_TIMEOUT))?;
+            let mut stream = BufReader::new(stream);
+            let mut token = String::new();
+            stream.read_line(&mut token)?;
+            if token.trim_end() != self.token {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "wrong token",
+                ));
+            }
+            Ok(stream)
+        }
+    }
+
+    fn path(dir: &Path, name: &str) -> PathBuf {
+        dir.join(format!("{name}.port"))
+    }
+
+    pub fn connect(dir: &Path, name: &str) -> io::Result<TcpStream> {
+        let contents = std::fs::read_to_string(path(dir, name))?;
+        let (port, token) = contents
+            .split_once(' ')
+            .and_then(|(port, token)| Some((port.parse::<u16>().ok()?, token.trim())))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "unreadable port file"))?;
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port))?;
+        writeln!(stream, "{token}")?;
+        Ok(stream)
+    }
+
+    /// Listens on a port of its own, unless another instance runs the named profile's node
+    pub fn bind(dir: &Path, name: &str) -> io::Result<Listener> {
+        let mut token = [0; TOKEN_LEN];
+        getrandom::getrandom(&mut token).map_err(|e| io::Error::other(e.to_string()))?;
+        let token = hex::encode(token);
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let port = listener.local_addr()?.port();
+
+        // Written whole before it's linked in place, which fails if another instance's is there,
+        // so launches never read half a file or replace each other's
+        let written = dir.join(format!("{name}.port.{}", std::process::id()));
+        std::fs::write(&written, format!("{port} {token}"))?;
+        let path = path(dir, name);
+        let linked = match std::fs::hard_link(&written, &path) {
+            // Left behind by an instance which didn't exit cleanly, as none answers on it
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && connect(dir, name).is_err() => {
+                std::fs::remove_file(&path)?;
+                std::fs::hard_link(&written, &path)
+            }
+            linked => linked,
+        };
+        std::fs::remove_file(&written)?;
+        match linked {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                Err(io::Error::new(io::ErrorKind::AddrInUse, e))
+            }
+            linked => linked.map(|()| Listener { listener, token }),
+        }
+    }
+
+    pub fn remove(dir: &Path, name: &str) -> io::Result<()> {
+        std::fs::remove_file(path(dir, name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The files `files` receives on the thread listening for them
+    fn received(files: &Files, ctx: &egui::Context) -> Option<Vec<PathBuf>> {
+        (0..100).find_map(|_| {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            files.take(ctx)
+        })
+    }
+
+    #[test]
+    fn hands_files_to_the_running_instance() {
+        let dir = std::env::temp_dir().join(format!("multinode-instance-{}", std::process::id()));
+        let ctx = egui::Context::default();
+
+        let first = vec![PathBuf::from("/plugins/first.wasm")];
+        let files = start_in(&dir, None, first.clone()).expect("no instance was running");
+        assert_eq!(files.take(&ctx), Some(first));
+        assert_eq!(files.take(&ctx), None);
+
+        let later = vec![
+            PathBuf::from("/plugins/a.wasm"),
+            PathBuf::from("/plugins/b c.wasm"),
+        ];
+        assert!(start_in(&dir, None, later.clone()).is_none());
+        assert_eq!(received(&files, &ctx), Some(later));
+
+        // Launches at the same time leave the socket of the one listening alone
+        let e = socket::bind(&dir, &socket_name(None))
+            .err()
+            .expect("bound twice");
+        assert_eq!(e.kind(), std::io::ErrorKind::AddrInUse);
+        assert!(start_in(&dir, None, Vec::new()).is_none());
+        assert_eq!(received(&files, &ctx), Some(Vec::new()));
+
+        // Other profiles run their own node
+        assert!(start_in(&dir, Some("alice"), Vec::new()).is_some());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn switching_profiles_moves_the_socket() {
+        let dir = std::env::temp_dir().join(format!("multinode-switch-{}", std::process::id()));
+        let ctx = egui::Context::default();
+
+        let files = start_in(&dir, None, Vec::new()).expect("no instance was running");
+        files.take(&ctx);
+        files.listen_for(Some("bob"));
+        assert_eq!(last_active(&dir).as_deref(), Some("bob"));
+
+        let later = vec![PathBuf::from("/plugins/a.wasm")];
+        assert!(start_in(&dir, Some("bob"), later.clone()).is_none());
+        assert_eq!(received(&files, &ctx), Some(later));
+
+        // Launches without a profile go to the one whose node runs
+        assert!(start_in(&dir, last_active(&dir).as_deref(), Vec::new()).is_none());
+        assert_eq!(received(&files, &ctx), Some(Vec::new()));
+
+        // The default profile's node is gone, so its launches start their own
+        let default = start_in(&dir, None, Vec::new());
+        assert!(default.is_some());
+        drop(default);
+
+        // Dropping an instance stops it listening
+        drop(files);
+        assert!(start_in(&dir, Some("bob"), Vec::new()).is_some());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
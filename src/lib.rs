@@ -3,4 +3,8 @@
 mod app;
 pub use app::{Error, TemplateApp};
 
-mod style;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod instance;
+
+/// Name of the app, which also names its native data directory
+pub const APP_NAME: &str = "PeerPiper Multinode";
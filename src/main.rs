@@ -1,6 +1,21 @@
 #![warn(clippy::all, rust_2018_idioms)]
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
+/// The profile named with `--profile <name>`, if any
+#[cfg(not(target_arch = "wasm32"))]
+fn profile_arg() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if let Some(profile) = arg.strip_prefix("--profile=") {
+            return Some(profile.to_owned());
+        }
+        if arg == "--profile" {
+            return args.next();
+        }
+    }
+    None
+}
+
 // When compiling natively:
 #[cfg(not(target_arch = "wasm32"))]
 #[tokio::main]
@@ -20,10 +35,23 @@ pub async fn main() -> eframe::Result {
             ),
         ..Default::default()
     };
+    let profile = profile_arg();
+
+    // Hand plugin files to the node already running, if any, rather than starting another
+    let Some(files) =
+        egui_multinode::instance::start(profile.as_deref(), egui_multinode::instance::wasm_args())
+    else {
+        return Ok(());
+    };
+
     eframe::run_native(
-        "PeerPiper Multinode",
+        egui_multinode::APP_NAME,
         native_options,
-        Box::new(|cc| Ok(Box::new(egui_multinode::TemplateApp::new(cc)))),
+        Box::new(move |cc| {
+            Ok(Box::new(
+                egui_multinode::TemplateApp::new(cc, profile.as_deref()).opening(files),
+            ))
+        }),
     )
 }
 
@@ -53,7 +81,7 @@ fn main() {
             .start(
                 canvas,
                 web_options,
-                Box::new(|cc| Ok(Box::new(egui_multinode::TemplateApp::new(cc)))),
+                Box::new(|cc| Ok(Box::new(egui_multinode::TemplateApp::new(cc, None)))),
             )
             .await;
 
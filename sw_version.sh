@@ -0,0 +1,20 @@
+#!/usr/bin/env sh
+
+# Stamps the service worker Trunk staged with the crate version and a hash of the build's wasm,
+# which name its cache, and with the hashed names of the build's scripts and wasm, which it caches
+# when installed. Trunk runs this after each build, see Trunk.toml.
+
+set -e
+
+sw="$TRUNK_STAGING_DIR/sw.js"
+version=$(sed -n 's/^version = "\(.*\)"/\1/p' Cargo.toml | head -n 1)
+hash=$(cat "$TRUNK_STAGING_DIR"/*.wasm | cksum | cut -d ' ' -f 1)
+
+files=$(cd "$TRUNK_STAGING_DIR" && for file in *.js *.wasm; do
+  if [ -e "$file" ] && [ "$file" != sw.js ]; then
+    printf ', "./%s"' "$file"
+  fi
+done)
+
+sed -e "s/{{VERSION}}/$version-$hash/" -e "s| /\\* {{FILES}} \\*/|$files|" "$sw" > "$sw.tmp"
+mv "$sw.tmp" "$sw"
@@ -0,0 +1,59 @@
+;; Actions fixture: lists the one action `greet`, titled "Say hello", and traps when asked to
+;; run any other
+(component
+  ;; Memory with a bump allocator, so the host can pass ids in
+  (core module $memory
+    (memory (export "memory") 1)
+    (global $next (mut i32) (i32.const 1024))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      ;; align the next allocation
+      (local.set $ptr
+        (i32.and
+          (i32.add (global.get $next) (i32.sub (local.get 2) (i32.const 1)))
+          (i32.sub (i32.const 0) (local.get 2))))
+      (global.set $next (i32.add (local.get $ptr) (local.get 3)))
+      (local.get $ptr))
+    (data (i32.const 64) "greet")
+    (data (i32.const 80) "Say hello")
+    ;; the `greet` action: its id, then its title
+    (data (i32.const 256) "\40\00\00\00\05\00\00\00\50\00\00\00\09\00\00\00")
+    ;; `actions` result: a list of the one action at 256
+    (data (i32.const 512) "\00\01\00\00\01\00\00\00"))
+  (core instance $memory (instantiate $memory))
+
+  (core module $main
+    (import "memory" "memory" (memory 1))
+    (func (export "actions") (result i32)
+      (i32.const 512))
+    (func (export "run") (param $ptr i32) (param $len i32)
+      ;; "gree", then "t"
+      (if (i32.ne (local.get $len) (i32.const 5)) (then unreachable))
+      (if (i32.ne (i32.load (local.get $ptr)) (i32.const 0x65657267)) (then unreachable))
+      (if (i32.ne (i32.load8_u offset=4 (local.get $ptr)) (i32.const 0x74)) (then unreachable))))
+  (core instance $main (instantiate $main (with "memory" (instance $memory))))
+
+  (type $action (record (field "id" string) (field "title" string)))
+
+  (func $actions (result (list $action))
+    (canon lift (core func $main "actions") (memory $memory "memory")))
+  (func $run (param "id" string)
+    (canon lift (core func $main "run")
+      (memory $memory "memory") (realloc (func $memory "realloc"))))
+
+  ;; Names the types, as functions a component imports or exports may only use named ones
+  (component $actions
+    (type $action' (record (field "id" string) (field "title" string)))
+    (import "import-type-action" (type $action-i (eq $action')))
+    (import "import-func-actions" (func $actions (result (list $action-i))))
+    (import "import-func-run" (func $run (param "id" string)))
+
+    (export $action "action" (type $action-i))
+    (export "actions" (func $actions) (func (result (list $action))))
+    (export "run" (func $run)))
+
+  (instance $actions (instantiate $actions
+    (with "import-type-action" (type $action))
+    (with "import-func-actions" (func $actions))
+    (with "import-func-run" (func $run))))
+  (export "peerpiper:ui/actions" (instance $actions)))
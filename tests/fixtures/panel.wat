@@ -0,0 +1,120 @@
+;; Panel fixture: titled "Echo", it shows a label with the id of the last button clicked, and
+;; traps when a text input changes
+(component
+  ;; Memory with a bump allocator, so the host can pass events in
+  (core module $memory
+    (memory (export "memory") 1)
+    (global $next (mut i32) (i32.const 1024))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      ;; align the next allocation
+      (local.set $ptr
+        (i32.and
+          (i32.add (global.get $next) (i32.sub (local.get 2) (i32.const 1)))
+          (i32.sub (i32.const 0) (local.get 2))))
+      (global.set $next (i32.add (local.get $ptr) (local.get 3)))
+      (local.get $ptr))
+    ;; `title` result: a string pointing at "Echo"
+    (data (i32.const 0) "\40\00\00\00\04\00\00\00")
+    (data (i32.const 64) "Echo")
+    (data (i32.const 80) "none")
+    ;; `render` result: a list of the one element at 256
+    (data (i32.const 512) "\00\01\00\00\01\00\00\00"))
+  (core instance $memory (instantiate $memory))
+
+  (core module $main
+    (import "memory" "memory" (memory 1))
+    ;; the id of the last button clicked
+    (global $ptr (mut i32) (i32.const 80))
+    (global $len (mut i32) (i32.const 4))
+    (func (export "title") (result i32)
+      (i32.const 0))
+    (func (export "render") (result i32)
+      ;; a `label` element, showing the id
+      (i32.store8 (i32.const 256) (i32.const 1))
+      (i32.store (i32.const 260) (global.get $ptr))
+      (i32.store (i32.const 264) (global.get $len))
+      (i32.const 512))
+    (func (export "handle")
+      (param $case i32) (param $ptr i32) (param $len i32) (param i32) (param i32)
+      (if (local.get $case) (then unreachable))
+      (global.set $ptr (local.get $ptr))
+      (global.set $len (local.get $len))))
+  (core instance $main (instantiate $main (with "memory" (instance $memory))))
+
+  (type $button (record (field "id" string) (field "text" string)))
+  (type $text-input (record (field "id" string) (field "label" string) (field "value" string)))
+  (type $table (record (field "headers" (list string)) (field "rows" (list (list string)))))
+  (type $element (variant
+    (case "heading" string)
+    (case "label" string)
+    (case "button" $button)
+    (case "text-input" $text-input)
+    (case "table" $table)
+    (case "separator")))
+  (type $text-change (record (field "id" string) (field "value" string)))
+  (type $event (variant (case "clicked" string) (case "text-changed" $text-change)))
+
+  (func $title (result string)
+    (canon lift (core func $main "title") (memory $memory "memory")))
+  (func $render (result (list $element))
+    (canon lift (core func $main "render") (memory $memory "memory")))
+  (func $handle (param "event" $event)
+    (canon lift (core func $main "handle")
+      (memory $memory "memory") (realloc (func $memory "realloc"))))
+
+  ;; Names the types, as functions a component imports or exports may only use named ones
+  (component $panel
+    (type $button' (record (field "id" string) (field "text" string)))
+    (import "import-type-button" (type $button-i (eq $button')))
+    (type $text-input'
+      (record (field "id" string) (field "label" string) (field "value" string)))
+    (import "import-type-text-input" (type $text-input-i (eq $text-input')))
+    (type $table' (record (field "headers" (list string)) (field "rows" (list (list string)))))
+    (import "import-type-table" (type $table-i (eq $table')))
+    (type $element' (variant
+      (case "heading" string)
+      (case "label" string)
+      (case "button" $button-i)
+      (case "text-input" $text-input-i)
+      (case "table" $table-i)
+      (case "separator")))
+    (import "import-type-element" (type $element-i (eq $element')))
+    (type $text-change' (record (field "id" string) (field "value" string)))
+    (import "import-type-text-change" (type $text-change-i (eq $text-change')))
+    (type $event' (variant (case "clicked" string) (case "text-changed" $text-change-i)))
+    (import "import-type-event" (type $event-i (eq $event')))
+
+    (import "import-func-title" (func $title (result string)))
+    (import "import-func-render" (func $render (result (list $element-i))))
+    (import "import-func-handle" (func $handle (param "event" $event-i)))
+
+    (export $button "button" (type $button-i))
+    (export $text-input "text-input" (type $text-input-i))
+    (export $table "table" (type $table-i))
+    (type $element'' (variant
+      (case "heading" string)
+      (case "label" string)
+      (case "button" $button)
+      (case "text-input" $text-input)
+      (case "table" $table)
+      (case "separator")))
+    (export $element "element" (type $element''))
+    (export $text-change "text-change" (type $text-change-i))
+    (type $event'' (variant (case "clicked" string) (case "text-changed" $text-change)))
+    (export $event "event" (type $event''))
+    (export "title" (func $title))
+    (export "render" (func $render) (func (result (list $element))))
+    (export "handle" (func $handle) (func (param "event" $event))))
+
+  (instance $panel (instantiate $panel
+    (with "import-type-button" (type $button))
+    (with "import-type-text-input" (type $text-input))
+    (with "import-type-table" (type $table))
+    (with "import-type-element" (type $element))
+    (with "import-type-text-change" (type $text-change))
+    (with "import-type-event" (type $event))
+    (with "import-func-title" (func $title))
+    (with "import-func-render" (func $render))
+    (with "import-func-handle" (func $handle))))
+  (export "peerpiper:ui/panel" (instance $panel)))
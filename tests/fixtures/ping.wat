@@ -0,0 +1,21 @@
+;; Bus fixture: publishes "hi" to the `greetings` topic whenever `send` is called
+(component
+  (import "peerpiper:bus/pubsub" (instance $pubsub
+    (export "publish" (func (param "topic" string) (param "payload" (list u8))))))
+
+  (core module $memory
+    (memory (export "memory") 1)
+    (data (i32.const 0) "greetings")
+    (data (i32.const 16) "hi"))
+  (core instance $memory (instantiate $memory))
+
+  (core func $publish (canon lower (func $pubsub "publish") (memory $memory "memory")))
+
+  (core module $main
+    (import "pubsub" "publish" (func $publish (param i32 i32 i32 i32)))
+    (func (export "send")
+      (call $publish (i32.const 0) (i32.const 9) (i32.const 16) (i32.const 2))))
+  (core instance $main
+    (instantiate $main (with "pubsub" (instance (export "publish" (func $publish))))))
+
+  (func (export "send") (canon lift (core func $main "send"))))
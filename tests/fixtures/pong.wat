@@ -0,0 +1,53 @@
+;; Bus fixture: subscribes to `greetings` and stores each payload under the `last` key
+(component
+  (import "peerpiper:storage/kv" (instance $kv
+    (export "set" (func (param "key" string) (param "value" (list u8))))))
+
+  ;; Memory with a bump allocator, so the host can pass messages in
+  (core module $memory
+    (memory (export "memory") 1)
+    (global $next (mut i32) (i32.const 1024))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      ;; align the next allocation
+      (local.set $ptr
+        (i32.and
+          (i32.add (global.get $next) (i32.sub (local.get 2) (i32.const 1)))
+          (i32.sub (i32.const 0) (local.get 2))))
+      (global.set $next (i32.add (local.get $ptr) (local.get 3)))
+      (local.get $ptr))
+    ;; `topics` result: a list of one string, pointing at "greetings"
+    (data (i32.const 0) "\08\00\00\00\01\00\00\00")
+    (data (i32.const 8) "\20\00\00\00\09\00\00\00")
+    (data (i32.const 32) "greetings")
+    (data (i32.const 48) "last"))
+  (core instance $memory (instantiate $memory))
+
+  (core func $set (canon lower (func $kv "set") (memory $memory "memory")))
+
+  (core module $main
+    (import "kv" "set" (func $set (param i32 i32 i32 i32)))
+    (func (export "topics") (result i32)
+      (i32.const 0))
+    (func (export "on-message")
+      (param $topic i32) (param $topic-len i32)
+      (param $publisher i32) (param $publisher-len i32)
+      (param $payload i32) (param $payload-len i32)
+      (call $set (i32.const 48) (i32.const 4) (local.get $payload) (local.get $payload-len))))
+  (core instance $main
+    (instantiate $main (with "kv" (instance (export "set" (func $set))))))
+
+  (type $topics (func (result (list string))))
+  (type $on-message
+    (func (param "topic" string) (param "publisher" string) (param "payload" (list u8))))
+
+  (func $topics (type $topics)
+    (canon lift (core func $main "topics") (memory $memory "memory")))
+  (func $on-message (type $on-message)
+    (canon lift (core func $main "on-message")
+      (memory $memory "memory") (realloc (func $memory "realloc"))))
+
+  (instance $subscriber
+    (export "topics" (func $topics))
+    (export "on-message" (func $on-message)))
+  (export "peerpiper:bus/subscriber" (instance $subscriber)))